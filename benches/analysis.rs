@@ -0,0 +1,79 @@
+use std::{path::PathBuf, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use swc_ecma_visit::Visit;
+
+use customs_analysis::{
+    analysis::resolve_module_imports,
+    bench_fixture::{generate_fixture, write_fixture, FixtureOptions},
+    config::Config,
+    dependency_graph::ModuleKind,
+    module_visitor::ModuleVisitor,
+    parsing::{module_from_source, parse_all_modules},
+};
+
+/// Returns the source of a single large module from the fixture generator.
+fn large_module_source() -> String {
+    let options = FixtureOptions {
+        module_count: 2,
+        exports_per_module: 2000,
+        import_fan_out: 50,
+        ..FixtureOptions::default()
+    };
+
+    generate_fixture(&options)
+        .into_iter()
+        .find(|(path, _)| path.ends_with("module_1.ts"))
+        .map(|(_, source)| source)
+        .expect("Fixture should contain module_1.ts")
+}
+
+fn visitor_benchmarks(c: &mut Criterion) {
+    let source = large_module_source();
+
+    c.bench_function("parse large module", |b| {
+        b.iter(|| module_from_source(source.clone(), ModuleKind::TS).unwrap())
+    });
+
+    c.bench_function("visit large module", |b| {
+        b.iter_batched(
+            || module_from_source(source.clone(), ModuleKind::TS).unwrap(),
+            |(source_map, module)| {
+                let mut visitor = ModuleVisitor::new(PathBuf::from("bench.ts"), source_map);
+                visitor.visit_module(&module, &module);
+                visitor
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn resolution_benchmarks(c: &mut Criterion) {
+    let fixture_dir = std::env::temp_dir().join("customs-bench-fixture");
+    let options = FixtureOptions::default();
+
+    write_fixture(&fixture_dir, &options).expect("Failed to write benchmark fixture");
+
+    let config = Config {
+        root: Arc::new(fixture_dir),
+        ..Config::default()
+    };
+
+    c.bench_function("parse fixture project", |b| {
+        b.iter(|| parse_all_modules(&config).unwrap())
+    });
+
+    let modules = parse_all_modules(&config).unwrap();
+
+    // Marking exports as used is idempotent, so the same graph can be resolved repeatedly
+    c.bench_function("resolve fixture imports", |b| {
+        b.iter(|| resolve_module_imports(&modules))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = visitor_benchmarks, resolution_benchmarks
+}
+criterion_main!(benches);
@@ -1,57 +1,134 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use rayon::prelude::*;
+use swc_common::Span;
+use tracing::{trace, warn};
 
 use crate::{
-    config::Config,
+    baseline::BaselineExport,
+    config::{Config, KindFilter},
+    customs_config::build_glob_matcher,
     dependency_graph::{
-        ExportName, ImportName, Module, ModuleSourceAndLine, NormalizedModulePath, Usage,
+        namespace_member_name, Export, ExportKind, ExportName, ImportKind, ImportKinds, ImportName,
+        Module, ModuleSourceAndLine, NormalizedModulePath, PackageUsage, UnusedTypeParameter,
+        Usage,
     },
+    lockfile::find_lockfile,
     package_json::PackageJson,
+    package_types::PackageExportKinds,
+    progress::Progress,
 };
 
-pub fn resolve_module_imports(modules: &HashMap<NormalizedModulePath, Module>) {
-    for (path, module) in modules.iter() {
-        for (import_path, imports) in &module.imported_modules {
-            match modules.get(import_path) {
-                None => {
-                    println!(
-                        "WARNING: Failed to resolve module {} (in {})",
-                        import_path.display(),
-                        path.display()
-                    );
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportResolutionStats {
+    /// Imported modules which were found in the module graph.
+    pub resolved: usize,
+    /// Imported files which exist, but were not analyzed (outside the root, ignored, unparsable or not code).
+    pub external_local: usize,
+    /// Relative imports which didn't point to an existing file.
+    pub unresolved: usize,
+}
+
+impl ImportResolutionStats {
+    fn merge(self, other: ImportResolutionStats) -> ImportResolutionStats {
+        ImportResolutionStats {
+            resolved: self.resolved + other.resolved,
+            external_local: self.external_local + other.external_local,
+            unresolved: self.unresolved + other.unresolved,
+        }
+    }
+}
+
+fn resolve_imports_of_module(
+    path: &NormalizedModulePath,
+    module: &Module,
+    modules: &HashMap<NormalizedModulePath, Module>,
+) -> ImportResolutionStats {
+    let mut stats = ImportResolutionStats {
+        external_local: module.imported_external_modules.len(),
+        ..ImportResolutionStats::default()
+    };
+
+    for unresolved_path in &module.unresolved_imports {
+        stats.unresolved += 1;
+
+        warn!(
+            "Failed to resolve module {} (in {})",
+            unresolved_path.display(),
+            path.display()
+        );
+    }
+
+    for (import_path, imports) in &module.imported_modules {
+        match modules.get(import_path) {
+            None => {
+                // The file exists, but it was either ignored or it failed to parse.
+                stats.external_local += 1;
+            }
+            Some(source_module) => {
+                stats.resolved += 1;
+
+                if source_module.is_wildcard_imported() {
+                    // Module is already fully imported, bail.
+                    continue;
                 }
-                Some(source_module) => {
-                    if source_module.is_wildcard_imported() {
-                        // Module is already fully imported, bail.
+
+                for import in imports {
+                    let kinds = module.import_kinds(import_path, import);
+
+                    // Re-exported names are credited by `mark_forwarded_exports` once the re-export is used
+                    if kinds == ImportKinds::from(ImportKind::Reexport) {
                         continue;
                     }
 
-                    for import in imports {
-                        let key = match import {
-                            ImportName::Named(name) => ExportName::Named(name.clone()),
-                            ImportName::Default => ExportName::Default,
-                            ImportName::Wildcard => {
-                                source_module.mark_wildcard_imported();
-                                break;
-                            }
-                        };
-
-                        match source_module.exports.get(&key) {
-                            None => {
-                                println!(
-                                    "Failed to resolve export {} in module {} (imported from {})",
-                                    key,
-                                    import_path.display(),
-                                    path.display(),
-                                );
+                    // Members are marked used as well, unless only some of them are accessed
+                    let (key, uses_all_members) = match import {
+                        ImportName::Named(name) => (ExportName::Named(name.clone()), true),
+                        ImportName::Default => (ExportName::Default, false),
+                        ImportName::Wildcard => {
+                            source_module.mark_wildcard_imported();
+                            break;
+                        }
+                        ImportName::Member(namespace, member) => {
+                            // Properties of values which aren't namespaces have no exports
+                            let member_name = namespace_member_name(namespace, member);
+
+                            if let Some(export) = source_module.get_export(&member_name) {
+                                export.usage.mark_imported(kinds);
+
+                                for nested in source_module.namespace_members(&member_name) {
+                                    nested.usage.mark_imported(kinds);
+                                }
                             }
-                            Some(export) => {
-                                // TODO put behind debug logging
-                                // println!("Marking {}##{} as used", import_path.display(), key);
-
-                                export.usage.set(Usage {
-                                    used_externally: true,
-                                    ..export.usage.get()
-                                })
+
+                            (ExportName::Named(namespace.clone()), false)
+                        }
+                    };
+
+                    match source_module.get_export(&key) {
+                        None if source_module.has_dynamic_exports => {}
+                        None => {
+                            warn!(
+                                "Failed to resolve export {} in module {} (imported from {})",
+                                key,
+                                import_path.display(),
+                                path.display(),
+                            );
+                        }
+                        Some(export) => {
+                            trace!("Marking {}##{} as used", import_path.display(), key);
+
+                            export.usage.mark_imported(kinds);
+
+                            if uses_all_members {
+                                for member in source_module.namespace_members(&key) {
+                                    member.usage.mark_imported(kinds);
+                                }
                             }
                         }
                     }
@@ -59,10 +136,262 @@ pub fn resolve_module_imports(modules: &HashMap<NormalizedModulePath, Module>) {
             }
         }
     }
+
+    stats
+}
+
+/// Classifies re-exports of analyzed third party packages (`export { Foo } from "package"`)
+/// using the kinds of the exports in the package's type declarations.
+pub fn classify_package_reexports(
+    modules: &mut HashMap<NormalizedModulePath, Module>,
+    package_export_kinds: &HashMap<String, PackageExportKinds>,
+) {
+    if package_export_kinds.is_empty() {
+        return;
+    }
+
+    for module in modules.values_mut() {
+        for export in &mut module.exports {
+            if export.kind != ExportKind::Unknown {
+                continue;
+            }
+
+            let kind = match &export.reexported_from {
+                Some((source, ImportName::Named(name))) => package_export_kinds
+                    .get(source)
+                    .and_then(|kinds| kinds.get(&ExportName::Named(name.clone()))),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                export.kind = *kind;
+            }
+        }
+    }
+}
+
+/// Marks imported exports as used. Modules are processed in parallel, since usage flags are atomic.
+pub fn resolve_module_imports(
+    modules: &HashMap<NormalizedModulePath, Module>,
+) -> ImportResolutionStats {
+    modules
+        .par_iter()
+        .map(|(path, module)| resolve_imports_of_module(path, module, modules))
+        .reduce(ImportResolutionStats::default, ImportResolutionStats::merge)
+}
+
+/// Like `resolve_module_imports`, but with `ignore_unreachable_usages`, only the imports of modules which can be
+/// reached from the entry points are resolved. Exports which are only used by dead code are then unused too.
+pub fn resolve_reachable_module_imports(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<ImportResolutionStats> {
+    let reachable = if config.ignore_unreachable_usages {
+        reachable_modules(modules, config)?
+    } else {
+        None
+    };
+
+    let total = reachable.as_ref().map_or(modules.len(), HashSet::len);
+    let progress = Progress::new("Resolving imports", Some(total), config);
+
+    let stats = modules
+        .par_iter()
+        .filter(|(path, _)| match &reachable {
+            Some(reachable) => reachable.contains(path),
+            None => true,
+        })
+        .map(|(path, module)| {
+            let stats = resolve_imports_of_module(path, module, modules);
+            progress.tick();
+            stats
+        })
+        .reduce(ImportResolutionStats::default, ImportResolutionStats::merge);
+
+    progress.finish();
+
+    if let Some(metrics) = &config.metrics {
+        metrics.record_import_resolution(total, stats);
+    }
+
+    Ok(stats)
+}
+
+/// Marks the exports forwarded by used re-exports as used, following chains of re-exports.
+/// Runs after imports have been resolved and entry points marked, since either can make a re-export used.
+pub fn mark_forwarded_exports(modules: &HashMap<NormalizedModulePath, Module>) {
+    let reexport = ImportKinds::from(ImportKind::Reexport);
+
+    loop {
+        let mut changed = false;
+
+        for module in modules.values() {
+            let is_wildcard_imported = module.is_wildcard_imported();
+
+            for export in &module.exports {
+                let (source_path, imported_name) = match &export.forwards_to {
+                    Some(forwards_to) => forwards_to,
+                    None => continue,
+                };
+
+                if !export.usage.get().used_externally && !is_wildcard_imported {
+                    continue;
+                }
+
+                let source_module = match modules.get(source_path) {
+                    Some(source_module) => source_module,
+                    None => continue,
+                };
+
+                let key = match imported_name {
+                    ImportName::Named(name) => ExportName::Named(name.clone()),
+                    ImportName::Default => ExportName::Default,
+                    ImportName::Member(namespace, member) => {
+                        namespace_member_name(namespace, member)
+                    }
+                    ImportName::Wildcard => {
+                        changed |= !source_module.is_wildcard_imported();
+                        source_module.mark_wildcard_imported();
+                        continue;
+                    }
+                };
+
+                if let Some(forwarded) = source_module.get_export(&key) {
+                    let usage = forwarded.usage.get();
+                    changed |= !usage.used_externally || !usage.external_kinds.reexport;
+                    forwarded.usage.mark_imported(reexport);
+
+                    for member in source_module.namespace_members(&key) {
+                        member.usage.mark_imported(reexport);
+                    }
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// Marks all exports of entry point modules as used externally, since they are consumed outside the project.
+pub fn mark_entry_point_exports(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<usize> {
+    if config.entry_points.is_empty() {
+        return Ok(0);
+    }
+
+    let matcher = build_glob_matcher(&config.root, &config.entry_points)?;
+    let mut entry_point_count = 0;
+
+    for module in modules.values() {
+        if !matcher
+            .matched(&*module.path.root_relative, false)
+            .is_whitelist()
+        {
+            continue;
+        }
+
+        entry_point_count += 1;
+
+        for export in &module.exports {
+            export.usage.mark_used_externally();
+        }
+    }
+
+    Ok(entry_point_count)
+}
+
+/// An export which no other module uses.
+#[derive(Debug, Clone)]
+pub struct UnusedExport {
+    pub name: ExportName,
+    pub location: ModuleSourceAndLine,
+    pub kind: ExportKind,
+    pub usage: Usage,
+    /// The number of lines in the exported statement.
+    pub line_count: usize,
+    /// The statement which declares the export.
+    pub statement: ModuleSourceAndLine,
+    pub statement_span: Span,
+    /// How many exports the statement declares, used or not.
+    pub statement_export_count: usize,
+}
+
+/// The unused exports declared by one statement, like `export const { a, b } = c` or `export { a, b }`.
+/// Fixes have to handle the statement as a whole: it can only be deleted if all of its exports are unused.
+#[derive(Debug)]
+pub struct UnusedStatement<'a> {
+    pub exports: &'a [UnusedExport],
+}
+
+impl<'a> UnusedStatement<'a> {
+    pub fn statement(&self) -> &'a ModuleSourceAndLine {
+        &self.exports[0].statement
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.exports[0].line_count
+    }
+
+    /// Whether every export of the statement is unused, also locally.
+    pub fn is_deletable(&self) -> bool {
+        self.exports.len() == self.exports[0].statement_export_count
+            && self.exports.iter().all(|export| !export.usage.used_locally)
+    }
 }
 
 pub struct UnusedExportsResults {
-    pub sorted_exports: Vec<(ExportName, ModuleSourceAndLine, Usage)>,
+    /// Unused exports, sorted by location. Exports of the same statement are next to each other.
+    pub sorted_exports: Vec<UnusedExport>,
+}
+
+impl UnusedExportsResults {
+    /// Unused exports grouped by the statement which declares them, in the same order.
+    pub fn statements(&self) -> Vec<UnusedStatement<'_>> {
+        group_by_statement(&self.sorted_exports)
+    }
+
+    /// The total line count of statements whose exports are all unused, also locally, so they could be deleted.
+    pub fn deletable_lines(&self) -> usize {
+        self.statements()
+            .iter()
+            .filter(|statement| statement.is_deletable())
+            .map(|statement| statement.line_count())
+            .sum()
+    }
+}
+
+fn group_by_statement(sorted_exports: &[UnusedExport]) -> Vec<UnusedStatement<'_>> {
+    let mut statements = Vec::new();
+    let mut rest = sorted_exports;
+
+    while let Some(first) = rest.first() {
+        let length = rest
+            .iter()
+            .take_while(|export| {
+                export.statement.path() == first.statement.path()
+                    && export.statement_span == first.statement_span
+            })
+            .count();
+        let (exports, tail) = rest.split_at(length);
+
+        statements.push(UnusedStatement { exports });
+        rest = tail;
+    }
+
+    statements
+}
+
+/// How many exports each statement of the module declares.
+fn statement_export_counts(module: &Module) -> HashMap<Span, usize> {
+    module
+        .exports
+        .iter()
+        .map(|export| export.statement_span)
+        .counts()
 }
 
 pub fn find_unused_exports(
@@ -71,103 +400,1327 @@ pub fn find_unused_exports(
 ) -> UnusedExportsResults {
     let mut sorted_exports = modules
         .into_iter()
-        .filter(|(_, module)| !module.is_wildcard_imported())
-        .flat_map(|(_, module)| {
+        .filter(|(_, module)| !module.is_wildcard_imported() && !module.is_generated)
+        .flat_map(|(_, mut module)| {
+            let module_kind = module.kind;
+            let export_counts = statement_export_counts(&module);
+            let ignore_comments = std::mem::take(&mut module.ignore_comments);
+
+            module
+                .exports
+                .into_iter()
+                .filter(|export| is_unused_export(export, config))
+                .filter(move |export| export.ignore_comment(&ignore_comments).is_none())
+                .filter(move |export| !config.is_kind_ignored(export.kind, module_kind))
+                .filter(|export| !config.is_export_name_ignored(&export.name))
+                .map(move |export| UnusedExport {
+                    usage: export.usage.get(),
+                    statement_export_count: export_counts[&export.statement_span],
+                    name: export.name,
+                    location: export.location,
+                    kind: export.kind,
+                    line_count: export.line_count,
+                    statement: export.statement,
+                    statement_span: export.statement_span,
+                })
+        })
+        .collect::<Vec<_>>();
+
+    sort_by_location(&mut sorted_exports);
+
+    UnusedExportsResults { sorted_exports }
+}
+
+/// Unused exports of the modules in a folder (relative to the root) and its subfolders, sorted by location.
+/// For editor extensions which show dead code in a folder view: the module graph can be loaded and resolved once,
+/// and then queried for each folder without running the whole analysis.
+pub fn unused_exports_under(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    folder: &Path,
+    config: &Config,
+) -> Vec<UnusedExport> {
+    let mut exports = modules
+        .values()
+        .filter(|module| module.path.normalized.starts_with(folder))
+        .filter(|module| !module.is_wildcard_imported() && !module.is_generated)
+        .flat_map(|module| {
+            let export_counts = statement_export_counts(module);
+
             module
                 .exports
+                .iter()
+                .filter(move |export| {
+                    is_unused_export(export, config)
+                        && !config.is_kind_ignored(export.kind, module.kind)
+                        && export.ignore_comment(&module.ignore_comments).is_none()
+                })
+                .map(move |export| UnusedExport {
+                    usage: export.usage.get(),
+                    name: export.name.clone(),
+                    location: export.location.clone(),
+                    kind: export.kind,
+                    line_count: export.line_count,
+                    statement: export.statement.clone(),
+                    statement_span: export.statement_span,
+                    statement_export_count: export_counts[&export.statement_span],
+                })
+        })
+        .collect::<Vec<_>>();
+
+    sort_by_location(&mut exports);
+    exports
+}
+
+fn sort_by_location(exports: &mut [UnusedExport]) {
+    exports.sort_unstable_by(|a, b| {
+        a.location
+            .path()
+            .cmp(b.location.path())
+            .then_with(|| a.statement.line().cmp(&b.statement.line()))
+            .then_with(|| a.statement_span.lo.cmp(&b.statement_span.lo))
+            .then_with(|| a.location.line().cmp(&b.location.line()))
+    });
+}
+
+/// Whether the export should be reported as unused, before ignored kinds are applied.
+fn is_unused_export(export: &Export, config: &Config) -> bool {
+    let usage = export.usage.get();
+
+    !usage.used_externally
+        && !usage.used_dynamically
+        && !export.is_ambient
+        && export.kind.matches_analyze_target(config.analyze_target)
+}
+
+/// A suppression which no longer hides any finding, so it can be removed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnusedSuppression {
+    /// An `ignoreKinds` entry which matches no unused export.
+    IgnoredKind(KindFilter),
+    /// A `customs-ignore` comment whose statement has no unused exports.
+    IgnoreComment(ModuleSourceAndLine),
+    /// A baseline entry whose export is no longer unused, or no longer exists.
+    BaselineEntry(BaselineExport),
+}
+
+/// Finds `ignoreKinds` entries and `customs-ignore` comments which don't hide any unused export. Imports must have
+/// been resolved first. Stale baseline entries are found when the baseline is applied.
+pub fn find_unused_suppressions(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> Vec<UnusedSuppression> {
+    let mut unused_comments = modules
+        .values()
+        .filter(|module| !module.is_generated)
+        .flat_map(|module| {
+            module.ignore_comments.iter().filter_map(move |&comment| {
+                let hides_finding = !module.is_wildcard_imported()
+                    && module.exports.iter().any(|export| {
+                        is_unused_export(export, config)
+                            && export.ignore_comment(&module.ignore_comments) == Some(comment)
+                    });
+
+                (!hides_finding)
+                    .then(|| ModuleSourceAndLine::new(module.path.root_relative.clone(), comment))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    unused_comments.sort_by(|a, b| a.path().cmp(b.path()).then(a.line().cmp(&b.line())));
+
+    let mut unused_kinds = config.ignored_kinds.clone();
+
+    let exports = modules
+        .values()
+        .filter(|module| !module.is_wildcard_imported() && !module.is_generated)
+        .flat_map(|module| {
+            module
+                .exports
+                .iter()
+                .map(move |export| (module.kind, export))
+        })
+        .filter(|(_, export)| is_unused_export(export, config));
+
+    for (module_kind, export) in exports {
+        unused_kinds.retain(|filter| !filter.matches(export.kind, module_kind));
+
+        if unused_kinds.is_empty() {
+            break;
+        }
+    }
+
+    unused_kinds
+        .into_iter()
+        .map(UnusedSuppression::IgnoredKind)
+        .chain(
+            unused_comments
                 .into_iter()
-                .filter(|(_, export)| !export.usage.get().used_externally)
-                .filter(|(_, export)| export.kind.matches_analyze_target(config.analyze_target))
+                .map(UnusedSuppression::IgnoreComment),
+        )
+        .collect()
+}
+
+/// An export which other modules import, along with the ways they import it.
+pub struct ExportUsage {
+    pub name: ExportName,
+    pub location: ModuleSourceAndLine,
+    pub kinds: ImportKinds,
+}
+
+/// Lists the exports which other modules import, sorted by location. Imports must have been resolved first.
+/// Exports which are only used by entry points or through wildcard imports are not listed.
+pub fn find_export_usages(modules: &HashMap<NormalizedModulePath, Module>) -> Vec<ExportUsage> {
+    modules
+        .values()
+        .flat_map(|module| &module.exports)
+        .filter_map(|export| {
+            let kinds = export.usage.get().external_kinds;
+
+            if kinds == ImportKinds::default() {
+                return None;
+            }
+
+            Some(ExportUsage {
+                name: export.name.clone(),
+                location: export.location.clone(),
+                kinds,
+            })
         })
-        .map(|(name, export)| (name, export.location, export.usage.take()))
-        .collect::<Vec<(ExportName, ModuleSourceAndLine, Usage)>>();
+        .sorted_by(|a, b| {
+            a.location
+                .path()
+                .cmp(b.location.path())
+                .then_with(|| a.location.line().cmp(&b.location.line()))
+        })
+        .collect()
+}
+
+pub fn find_unused_type_parameters(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    _config: &Config,
+) -> Vec<UnusedTypeParameter> {
+    let mut type_parameters = modules
+        .values()
+        .filter(|module| !module.is_generated)
+        .flat_map(|module| module.unused_type_parameters.iter().cloned())
+        .collect::<Vec<_>>();
 
-    sorted_exports.sort_unstable_by(|(_, a_location, _), (_, b_location, _)| {
-        a_location
+    type_parameters.sort_unstable_by(|a, b| {
+        a.location
             .path()
-            .cmp(b_location.path())
-            .then_with(|| a_location.line().cmp(&b_location.line()))
+            .cmp(b.location.path())
+            .then_with(|| a.location.line().cmp(&b.location.line()))
     });
 
-    UnusedExportsResults { sorted_exports }
+    type_parameters
 }
 
+/// Finds dependencies which no module imports. Dependencies which are also overridden
+/// (`overrides`, `resolutions` or `pnpm.overrides`) are left out, since they're often declared only to pin a version.
+/// Packages listed in the `types` compiler option are used by the compiler, so they're left out as well.
 pub fn find_unused_dependencies(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    package_json: &PackageJson,
+    config: &Config,
+) -> Vec<String> {
+    let overridden_packages = package_json.overridden_packages();
+    let type_packages = config.compiler_options.type_packages();
+
+    find_unimported_dependencies(modules, package_json)
+        .into_iter()
+        .filter(|name| !overridden_packages.contains(*name))
+        .filter(|name| !type_packages.iter().any(|package| package == *name))
+        .map(String::from)
+        .collect()
+}
+
+/// Finds overridden dependencies which no module imports.
+pub fn find_unused_overridden_dependencies(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    package_json: &PackageJson,
+    _config: &Config,
+) -> Vec<String> {
+    let overridden_packages = package_json.overridden_packages();
+
+    find_unimported_dependencies(modules, package_json)
+        .into_iter()
+        .filter(|name| overridden_packages.contains(*name))
+        .map(String::from)
+        .collect()
+}
+
+/// Finds bundled dependencies which no module imports, since bundling them only grows the published package.
+pub fn find_unused_bundled_dependencies(
     modules: &HashMap<NormalizedModulePath, Module>,
     package_json: &PackageJson,
     _config: &Config,
 ) -> Vec<String> {
-    let imported_packages = modules
+    let imported_packages = imported_packages(modules);
+
+    package_json
+        .bundled_dependency_names()
+        .into_iter()
+        .filter(|name| !imported_packages.contains(name))
+        .map(String::from)
+        .sorted()
+        .collect()
+}
+
+fn imported_packages(modules: &HashMap<NormalizedModulePath, Module>) -> HashSet<&str> {
+    modules
         .values()
         .flat_map(|module| module.imported_packages.iter().map(String::as_str))
-        .collect::<HashSet<&str>>();
+        .collect()
+}
+
+fn find_unimported_dependencies<'a>(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    package_json: &'a PackageJson,
+) -> Vec<&'a str> {
+    let imported_packages = imported_packages(modules);
 
-    let installed_dependencies = package_json
+    package_json
         .dependencies
         .keys()
+        .chain(package_json.optional_dependencies.keys())
         .map(String::as_str)
-        .collect::<HashSet<&str>>();
-
-    installed_dependencies
-        .difference(&imported_packages)
-        .map(|item| (*item).to_string())
+        .filter(|name| !imported_packages.contains(name))
         .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use std::{path::PathBuf, sync::Arc};
+/// The section of package.json a dependency should be listed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencySection {
+    Dependencies,
+    DevDependencies,
+}
 
-    use crate::dependency_graph::{
-        Export, ExportKind, ModuleKind, ModulePath, Visibility::Exported,
-    };
+/// A dependency which is listed in the wrong section of package.json, along with the imports showing where it belongs.
+pub struct MisplacedDependency {
+    pub name: String,
+    pub move_to: DependencySection,
+    pub evidence: Vec<PackageUsage>,
+}
 
-    use super::*;
+/// Suggests moving dependencies which are only imported as types or from dev files (like tests) to devDependencies,
+/// and devDependencies which are imported as values from other modules to dependencies.
+pub fn find_misplaced_dependencies(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    package_json: &PackageJson,
+    config: &Config,
+) -> anyhow::Result<Vec<MisplacedDependency>> {
+    let dev_files = build_glob_matcher(&config.root, &config.dev_files)?;
 
-    #[test]
-    fn imports_smoke() {
-        let root_path: Arc<PathBuf> = Arc::new("".into());
+    let mut usages_by_package: HashMap<&str, Vec<(&PackageUsage, bool)>> = HashMap::new();
 
-        let mut modules = HashMap::new();
+    for module in modules.values() {
+        let is_dev_file = dev_files
+            .matched(&*module.path.root_relative, false)
+            .is_whitelist();
 
-        let module_a_path = NormalizedModulePath::new("a");
+        for usage in &module.package_usages {
+            usages_by_package
+                .entry(usage.package.as_str())
+                .or_default()
+                .push((usage, is_dev_file));
+        }
+    }
 
-        let mut module_a = Module::new(
-            ModulePath {
-                root: root_path.clone(),
-                root_relative: Arc::new("a".into()),
-                normalized: module_a_path.clone(),
-            },
-            ModuleKind::TS,
-        );
-        let export_foo = Export::new(ExportKind::Value, Exported, ModuleSourceAndLine::new_mock());
-        module_a.add_export(ExportName::named("foo"), export_foo);
-        let export_bar = Export::new(ExportKind::Value, Exported, ModuleSourceAndLine::new_mock());
-        module_a.add_export(ExportName::named("bar"), export_bar);
+    let mut misplaced = Vec::new();
 
-        modules.insert(module_a_path.clone(), module_a);
+    for (package, usages) in usages_by_package {
+        let is_dependency = package_json.dependencies.contains_key(package);
+        let is_dev_dependency = package_json.dev_dependencies.contains_key(package);
 
-        let module_b_path = NormalizedModulePath::new("b");
-        let mut module_b = Module::new(
-            ModulePath {
-                root: root_path.clone(),
-                root_relative: Arc::new("b".into()),
-                normalized: module_b_path.clone(),
-            },
-            ModuleKind::TS,
-        );
-        module_b
-            .imports_mut(module_a_path.clone())
-            .push(ImportName::named("foo"));
+        let (move_to, evidence) = match (is_dependency, is_dev_dependency) {
+            (true, false)
+                if usages
+                    .iter()
+                    .all(|(usage, is_dev)| usage.type_only || *is_dev) =>
+            {
+                (DependencySection::DevDependencies, usages)
+            }
+            (false, true) => {
+                let runtime_usages = usages
+                    .into_iter()
+                    .filter(|(usage, is_dev)| !usage.type_only && !*is_dev)
+                    .collect::<Vec<_>>();
 
-        modules.insert(module_b_path.clone(), module_b);
+                if runtime_usages.is_empty() {
+                    continue;
+                }
 
-        resolve_module_imports(&modules);
+                (DependencySection::Dependencies, runtime_usages)
+            }
+            _ => continue,
+        };
 
-        let module_a_exports = &modules.get(&module_a_path).unwrap().exports;
-        let export_foo = module_a_exports.get(&ExportName::named("foo")).unwrap();
-        assert!(export_foo.is_used(), "foo should be marked as used");
-        let export_foo = module_a_exports.get(&ExportName::named("bar")).unwrap();
-        assert!(!export_foo.is_used(), "bar should not be marked as used");
+        let evidence = evidence
+            .into_iter()
+            .map(|(usage, _)| usage.clone())
+            .sorted_by(|a, b| {
+                a.location
+                    .path()
+                    .cmp(b.location.path())
+                    .then_with(|| a.location.line().cmp(&b.location.line()))
+            })
+            .collect();
+
+        misplaced.push(MisplacedDependency {
+            name: package.to_string(),
+            move_to,
+            evidence,
+        });
+    }
+
+    misplaced.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(misplaced)
+}
+
+/// Modules built into Node. Browser projects often install packages with the same names (e.g. `buffer`) as polyfills.
+const NODE_BUILTIN_MODULES: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// Whether an imported package refers to a Node builtin module rather than an installed package.
+/// `node:` imports are always builtins. A package with a builtin's name is a polyfill if it's declared in package.json
+/// or listed in `polyfilled_builtins`.
+pub fn is_node_builtin(package: &str, package_json: &PackageJson, config: &Config) -> bool {
+    package.starts_with("node:")
+        || (NODE_BUILTIN_MODULES.contains(&package)
+            && !package_json.has_dependency(package)
+            && !package_json.peer_dependencies.contains_key(package)
+            && !config
+                .polyfilled_builtins
+                .iter()
+                .any(|polyfill| polyfill == package))
+}
+
+/// Differences between package.json, the lockfile and the imported packages.
+pub struct LockfileProblems {
+    pub lockfile: PathBuf,
+    /// Dependencies declared in package.json which the lockfile doesn't list, so they're not installed consistently.
+    pub missing_from_lockfile: Vec<String>,
+    /// Imports of packages which aren't declared in package.json, but are installed as dependencies of other packages.
+    /// Node builtin modules are not included, unless the package is a polyfill.
+    pub phantom_imports: Vec<PackageUsage>,
+}
+
+/// Cross-checks the declared dependencies and the imported packages against the lockfile.
+/// Returns `None` if there is no lockfile.
+pub fn find_lockfile_problems(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    package_json: &PackageJson,
+    config: &Config,
+) -> anyhow::Result<Option<LockfileProblems>> {
+    let lockfile = match find_lockfile(&config.root)? {
+        Some(lockfile) => lockfile,
+        None => return Ok(None),
+    };
+
+    let declared = package_json
+        .dependencies
+        .keys()
+        .chain(package_json.dev_dependencies.keys())
+        .chain(package_json.peer_dependencies.keys())
+        .chain(package_json.optional_dependencies.keys())
+        .collect::<HashSet<_>>();
+
+    // Local packages aren't necessarily listed with the installed packages
+    let is_local = |version: &str| {
+        ["workspace:", "file:", "link:", "portal:"]
+            .iter()
+            .any(|protocol| version.starts_with(protocol))
+    };
+
+    let missing_from_lockfile = package_json
+        .dependencies
+        .iter()
+        .chain(package_json.dev_dependencies.iter())
+        .filter(|(name, version)| !is_local(version) && !lockfile.packages.contains(*name))
+        .map(|(name, _)| name.clone())
+        .sorted()
+        .collect();
+
+    let phantom_imports = modules
+        .values()
+        .flat_map(|module| &module.package_usages)
+        .filter(|usage| {
+            !declared.contains(&usage.package)
+                && lockfile.packages.contains(&usage.package)
+                && !is_node_builtin(&usage.package, package_json, config)
+        })
+        .cloned()
+        .sorted_by(|a, b| {
+            a.location
+                .path()
+                .cmp(b.location.path())
+                .then_with(|| a.location.line().cmp(&b.location.line()))
+        })
+        .collect();
+
+    Ok(Some(LockfileProblems {
+        lockfile: lockfile.path,
+        missing_from_lockfile,
+        phantom_imports,
+    }))
+}
+
+/// Lists files in the asset folders which no module imports, sorted by path.
+/// Only imports from modules are tracked, so assets referenced from e.g. stylesheets or HTML are reported too.
+pub fn find_unused_assets(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> Vec<PathBuf> {
+    let imported_files = modules
+        .values()
+        .flat_map(|module| &module.imported_external_modules)
+        .map(|path| without_root(&config.root, path))
+        .collect::<HashSet<_>>();
+
+    let module_files = modules
+        .values()
+        .map(|module| without_root(&config.root, &module.path.root_relative))
+        .collect::<HashSet<_>>();
+
+    let mut unused_assets = config
+        .asset_folders
+        .iter()
+        .flat_map(|folder| config.walk_builder(&config.root.join(folder)).build())
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| without_root(&config.root, entry.path()))
+        .filter(|path| !imported_files.contains(path) && !module_files.contains(path))
+        .collect::<Vec<_>>();
+
+    unused_assets.sort_unstable();
+    unused_assets.dedup();
+    unused_assets
+}
+
+/// Lists modules which can't be reached by following imports and re-exports from the entry points, sorted by path.
+/// This also finds groups of modules which only import each other. Declaration files are never reported,
+/// since they are usually included globally rather than imported. Returns nothing if there are no entry points.
+pub fn find_unreachable_modules(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let reachable = match reachable_modules(modules, config)? {
+        Some(reachable) => reachable,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut unreachable = modules
+        .values()
+        .filter(|module| {
+            !module.kind.is_declaration()
+                && !module.is_generated
+                && !reachable.contains(&module.path.normalized)
+        })
+        .map(|module| (*module.path.root_relative).clone())
+        .collect::<Vec<_>>();
+
+    unreachable.sort_unstable();
+    Ok(unreachable)
+}
+
+/// Follows imports and re-exports from the entry points. Returns `None` if there are no entry points.
+fn reachable_modules<'a>(
+    modules: &'a HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<Option<HashSet<&'a NormalizedModulePath>>> {
+    if config.entry_points.is_empty() {
+        return Ok(None);
+    }
+
+    let matcher = build_glob_matcher(&config.root, &config.entry_points)?;
+
+    let mut stack = modules
+        .values()
+        .filter(|module| {
+            matcher
+                .matched(&*module.path.root_relative, false)
+                .is_whitelist()
+        })
+        .map(|module| &module.path.normalized)
+        .collect::<Vec<_>>();
+
+    let mut reachable = HashSet::new();
+
+    while let Some(path) = stack.pop() {
+        if !reachable.insert(path) {
+            continue;
+        }
+
+        if let Some(module) = modules.get(path) {
+            stack.extend(module.imported_modules.keys());
+        }
+    }
+
+    Ok(Some(reachable))
+}
+
+/// Unreachable modules which are connected by imports, so they can likely be deleted together.
+pub struct UnreachableIsland {
+    /// Sorted by path.
+    pub modules: Vec<PathBuf>,
+    pub line_count: usize,
+}
+
+/// Groups unreachable modules into islands of modules which import each other, directly or through other
+/// modules of the island. Islands are sorted by line count, largest first.
+pub fn find_unreachable_islands(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<Vec<UnreachableIsland>> {
+    let unreachable_paths = find_unreachable_modules(modules, config)?
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let unreachable = modules
+        .values()
+        .filter(|module| unreachable_paths.contains(&*module.path.root_relative))
+        .map(|module| (&module.path.normalized, module))
+        .collect::<HashMap<_, _>>();
+
+    // Imports are followed in both directions
+    let mut neighbors: HashMap<&NormalizedModulePath, Vec<&NormalizedModulePath>> = HashMap::new();
+
+    for (&path, module) in &unreachable {
+        for imported in module.imported_modules.keys() {
+            if unreachable.contains_key(imported) {
+                neighbors.entry(path).or_default().push(imported);
+                neighbors.entry(imported).or_default().push(path);
+            }
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut islands = Vec::new();
+
+    for start in unreachable.keys() {
+        if visited.contains(start) {
+            continue;
+        }
+
+        let mut island = Vec::new();
+        let mut stack = vec![*start];
+
+        while let Some(path) = stack.pop() {
+            if !visited.insert(path) {
+                continue;
+            }
+
+            island.push(unreachable[path]);
+            stack.extend(neighbors.get(path).into_iter().flatten().copied());
+        }
+
+        let mut paths = island
+            .iter()
+            .map(|module| (*module.path.root_relative).clone())
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+
+        islands.push(UnreachableIsland {
+            modules: paths,
+            line_count: island.iter().map(|module| module.line_count).sum(),
+        });
+    }
+
+    islands.sort_unstable_by(|a, b| {
+        b.line_count
+            .cmp(&a.line_count)
+            .then_with(|| a.modules.cmp(&b.modules))
+    });
+
+    Ok(islands)
+}
+
+/// Makes a path relative to the root, dropping `.` components so that paths built in different ways compare equal.
+fn without_root(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+/// Which half of a module's exports its consumers never import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnusedExportStyle {
+    /// The default export is unused, but the named exports are imported.
+    Default,
+    /// The named exports are unused, but the default export is imported.
+    Named,
+}
+
+/// A module whose consumers consistently import either only its default export or only its named exports.
+pub struct DefaultExportMismatch {
+    pub module: PathBuf,
+    pub unused: UnusedExportStyle,
+    /// The number of modules which import the used half.
+    pub importer_count: usize,
+}
+
+/// Finds modules with both a default export and named exports, where only one of the two styles is imported.
+/// Imports must have been resolved with `resolve_module_imports` first.
+pub fn find_default_export_mismatches(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    _config: &Config,
+) -> Vec<DefaultExportMismatch> {
+    // (default importers, named importers) of each module
+    let mut importer_counts: HashMap<&NormalizedModulePath, (usize, usize)> = HashMap::new();
+
+    for importer in modules.values() {
+        for (path, imports) in &importer.imported_modules {
+            let counts = importer_counts.entry(path).or_default();
+
+            if imports.contains(&ImportName::Default) {
+                counts.0 += 1;
+            }
+
+            if imports
+                .iter()
+                .any(|import| matches!(import, ImportName::Named(_) | ImportName::Member(..)))
+            {
+                counts.1 += 1;
+            }
+        }
+    }
+
+    let mut mismatches = modules
+        .values()
+        .filter(|module| !module.is_wildcard_imported() && !module.is_generated)
+        .filter_map(|module| {
+            let default = module.get_export(&ExportName::Default)?;
+            let named_exports = module
+                .exports
+                .iter()
+                .filter(|export| export.name != ExportName::Default)
+                .collect::<Vec<_>>();
+
+            if named_exports.is_empty() {
+                return None;
+            }
+
+            let default_used = default.usage.get().used_externally;
+            let named_used = named_exports
+                .iter()
+                .any(|export| export.usage.get().used_externally);
+            let (default_importers, named_importers) = importer_counts
+                .get(&module.path.normalized)
+                .copied()
+                .unwrap_or_default();
+
+            let (unused, importer_count) = match (default_used, named_used) {
+                (false, true) if default_importers == 0 => {
+                    (UnusedExportStyle::Default, named_importers)
+                }
+                (true, false) if named_importers == 0 => {
+                    (UnusedExportStyle::Named, default_importers)
+                }
+                _ => return None,
+            };
+
+            Some(DefaultExportMismatch {
+                module: (*module.path.root_relative).clone(),
+                unused,
+                importer_count,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    mismatches.sort_unstable_by(|a, b| a.module.cmp(&b.module));
+    mismatches
+}
+
+/// Everything known about why a single export is considered used or unused.
+pub struct ExportExplanation {
+    pub module: PathBuf,
+    pub name: ExportName,
+    pub kind: ExportKind,
+    pub location: ModuleSourceAndLine,
+    pub usage: Usage,
+    pub reexported_from: Option<(String, ImportName)>,
+    /// Root-relative paths of the modules which import the export (or the whole module), and how they import it.
+    pub importers: Vec<(PathBuf, ImportName)>,
+    pub is_wildcard_imported: bool,
+}
+
+/// Finds the module by its root-relative (or absolute) path and explains the usage of one of its exports.
+/// Imports must have been resolved with `resolve_module_imports` first.
+pub fn explain_export(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+    file: &Path,
+    export_name: &ExportName,
+) -> anyhow::Result<ExportExplanation> {
+    // Module paths include the root folder, but users will often omit it
+    let file = file.strip_prefix(&*config.root).unwrap_or(file);
+
+    let module = modules
+        .values()
+        .find(|module| {
+            let path = &module.path.root_relative;
+            path.strip_prefix(&*module.path.root).unwrap_or(path) == file
+        })
+        .ok_or_else(|| anyhow!("{} was not found in the analyzed modules", file.display()))?;
+
+    let export = module.get_export(export_name).ok_or_else(|| {
+        anyhow!(
+            "{} has no export named {}. Available exports: {}",
+            file.display(),
+            export_name,
+            module.exports.iter().map(|export| &export.name).join(", ")
+        )
+    })?;
+
+    let importers = modules
+        .values()
+        .filter_map(|importer| {
+            let imports = importer.imported_modules.get(&module.path.normalized)?;
+
+            let import = imports.iter().find(|import| match (import, export_name) {
+                (ImportName::Wildcard, _) | (ImportName::Default, ExportName::Default) => true,
+                (ImportName::Named(imported), ExportName::Named(exported)) => {
+                    imported == exported || exported.starts_with(&format!("{}.", imported))
+                }
+                (ImportName::Member(namespace, member), ExportName::Named(exported)) => {
+                    let member_name = format!("{}.{}", namespace, member);
+
+                    namespace == exported
+                        || **exported == *member_name
+                        || exported.starts_with(&format!("{}.", member_name))
+                }
+                _ => false,
+            })?;
+
+            Some(((*importer.path.root_relative).clone(), import.clone()))
+        })
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .collect();
+
+    Ok(ExportExplanation {
+        module: (*module.path.root_relative).clone(),
+        name: export.name.clone(),
+        kind: export.kind,
+        location: export.location.clone(),
+        usage: export.usage.get(),
+        reexported_from: export.reexported_from.clone(),
+        importers,
+        is_wildcard_imported: module.is_wildcard_imported(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dependency_graph::{ImportKind, ModuleKind},
+        tests::utils::{module_with_exports, test_export, test_module},
+    };
+
+    use super::*;
+
+    #[test]
+    fn imports_smoke() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        modules.insert(
+            module_a_path.clone(),
+            module_with_exports("a", &["foo", "bar"]),
+        );
+
+        let module_b_path = NormalizedModulePath::new("b");
+        let mut module_b = test_module("b", ModuleKind::TS);
+        module_b
+            .imports_mut(module_a_path.clone())
+            .push(ImportName::named("foo"));
+
+        modules.insert(module_b_path.clone(), module_b);
+
+        resolve_module_imports(&modules);
+
+        let module_a = modules.get(&module_a_path).unwrap();
+        let export_foo = module_a.get_export(&ExportName::named("foo")).unwrap();
+        assert!(export_foo.is_used(), "foo should be marked as used");
+        let export_foo = module_a.get_export(&ExportName::named("bar")).unwrap();
+        assert!(!export_foo.is_used(), "bar should not be marked as used");
+    }
+
+    #[test]
+    fn imports_of_non_analyzed_modules_are_counted_separately() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        modules.insert(module_a_path.clone(), module_with_exports("a", &["foo"]));
+
+        // b imports a, a file which failed to parse, a stylesheet and a missing file
+        let mut module_b = test_module("b", ModuleKind::TS);
+        module_b
+            .imports_mut(module_a_path)
+            .push(ImportName::named("foo"));
+        module_b
+            .imports_mut(NormalizedModulePath::new("broken"))
+            .push(ImportName::named("bar"));
+        module_b
+            .imported_external_modules
+            .insert(PathBuf::from("styles.css"));
+        module_b.unresolved_imports.insert(PathBuf::from("missing"));
+        modules.insert(NormalizedModulePath::new("b"), module_b);
+
+        let stats = resolve_module_imports(&modules);
+
+        assert_eq!(stats.resolved, 1);
+        assert_eq!(stats.external_local, 2);
+        assert_eq!(stats.unresolved, 1);
+    }
+
+    #[test]
+    fn reexport_chains_credit_the_original_export() {
+        let impl_path = NormalizedModulePath::new("impl");
+        let barrel_path = NormalizedModulePath::new("index");
+        let app_path = NormalizedModulePath::new("app");
+
+        // index: export { thing as renamed, other } from './impl'
+        let mut barrel = test_module("index", ModuleKind::TS);
+        for (export_name, imported_name) in [("renamed", "thing"), ("other", "other")] {
+            let mut export = test_export(ExportName::named(export_name), ExportKind::Unknown);
+            export.forwards_to = Some((impl_path.clone(), ImportName::named(imported_name)));
+            barrel.add_export(export);
+        }
+        barrel
+            .imports_mut(impl_path.clone())
+            .extend(vec![ImportName::named("thing"), ImportName::named("other")]);
+        barrel.import_kinds.insert(
+            impl_path.clone(),
+            vec![
+                (ImportName::named("thing"), ImportKind::Reexport.into()),
+                (ImportName::named("other"), ImportKind::Reexport.into()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let mut app = test_module("app", ModuleKind::TS);
+        app.imports_mut(barrel_path.clone())
+            .push(ImportName::named("renamed"));
+
+        let mut modules = HashMap::new();
+        modules.insert(
+            impl_path.clone(),
+            module_with_exports("impl", &["thing", "other"]),
+        );
+        modules.insert(barrel_path, barrel);
+        modules.insert(app_path, app);
+
+        resolve_module_imports(&modules);
+        mark_forwarded_exports(&modules);
+
+        let impl_module = &modules[&impl_path];
+        let thing = impl_module.get_export(&ExportName::named("thing")).unwrap();
+        let other = impl_module.get_export(&ExportName::named("other")).unwrap();
+
+        assert!(thing.usage.get().used_externally);
+        assert!(thing.usage.get().external_kinds.reexport);
+        assert!(!other.is_used(), "unused re-exports don't use the original");
+    }
+
+    #[test]
+    fn export_usages_by_import_kind() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        let mut module_a = test_module("a", ModuleKind::TS);
+
+        for name in ["Props", "render", "unused"] {
+            module_a.add_export(test_export(ExportName::named(name), ExportKind::Unknown));
+        }
+
+        modules.insert(module_a_path.clone(), module_a);
+
+        let module_b_path = NormalizedModulePath::new("b");
+        let mut module_b = test_module("b", ModuleKind::TS);
+        module_b.imports_mut(module_a_path.clone()).extend(vec![
+            ImportName::named("Props"),
+            ImportName::named("render"),
+        ]);
+        module_b.import_kinds.insert(
+            module_a_path.clone(),
+            vec![(ImportName::named("Props"), ImportKind::TypeOnly.into())]
+                .into_iter()
+                .collect(),
+        );
+
+        modules.insert(module_b_path, module_b);
+
+        resolve_module_imports(&modules);
+
+        let usages = find_export_usages(&modules)
+            .into_iter()
+            .map(|usage| (usage.name.to_string(), usage.kinds.is_type_only()))
+            .sorted()
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            usages,
+            vec![
+                (String::from("Props"), true),
+                (String::from("render"), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn namespace_member_imports() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        modules.insert(
+            module_a_path.clone(),
+            module_with_exports("a", &["Api", "Api.get", "Api.post", "Other", "Other.get"]),
+        );
+
+        let module_b_path = NormalizedModulePath::new("b");
+        let mut module_b = test_module("b", ModuleKind::TS);
+        module_b.imports_mut(module_a_path.clone()).extend(vec![
+            ImportName::Member("Api".into(), "get".into()),
+            ImportName::named("Other"),
+        ]);
+        modules.insert(module_b_path, module_b);
+
+        resolve_module_imports(&modules);
+
+        let module_a = modules.get(&module_a_path).unwrap();
+        let is_used = |name: &str| {
+            module_a
+                .get_export(&ExportName::named(name))
+                .unwrap()
+                .is_used()
+        };
+
+        assert!(is_used("Api"));
+        assert!(is_used("Api.get"));
+        assert!(!is_used("Api.post"), "Api.post is never accessed");
+        assert!(is_used("Other.get"), "Other is used as a whole");
+    }
+
+    #[test]
+    fn default_export_never_imported() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        let mut module_a = module_with_exports("a.ts", &["Button"]);
+        module_a.add_export(test_export(ExportName::Default, ExportKind::Value));
+        modules.insert(module_a_path.clone(), module_a);
+
+        for importer in ["b.ts", "c.ts"] {
+            let mut module = test_module(importer, ModuleKind::TS);
+            module
+                .imports_mut(module_a_path.clone())
+                .push(ImportName::named("Button"));
+            modules.insert(module.path.normalized.clone(), module);
+        }
+
+        resolve_module_imports(&modules);
+
+        let mismatches = find_default_export_mismatches(&modules, &Config::default());
+
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].module, PathBuf::from("a.ts"));
+        assert_eq!(mismatches[0].unused, UnusedExportStyle::Default);
+        assert_eq!(mismatches[0].importer_count, 2);
+    }
+
+    #[test]
+    fn unreachable_import_cycle() {
+        let mut modules = HashMap::new();
+
+        let graph = [
+            ("main", ModuleKind::TS, vec!["a"]),
+            ("a", ModuleKind::TS, vec![]),
+            ("b", ModuleKind::TS, vec!["c"]),
+            ("c", ModuleKind::TS, vec!["b"]),
+            ("d", ModuleKind::TS, vec![]),
+            ("globals", ModuleKind::DTS, vec![]),
+        ];
+
+        for (name, kind, imports) in graph {
+            let path = NormalizedModulePath::new(name);
+            let mut module = test_module(&format!("{}.ts", name), kind);
+            module.line_count = 10;
+
+            for import in imports {
+                module
+                    .imports_mut(NormalizedModulePath::new(import))
+                    .push(ImportName::named("foo"));
+            }
+
+            modules.insert(path, module);
+        }
+
+        let config = Config {
+            entry_points: vec![String::from("main.ts")],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            find_unreachable_modules(&modules, &config).unwrap(),
+            vec![
+                PathBuf::from("b.ts"),
+                PathBuf::from("c.ts"),
+                PathBuf::from("d.ts")
+            ]
+        );
+
+        let islands = find_unreachable_islands(&modules, &config).unwrap();
+
+        assert_eq!(islands.len(), 2);
+        assert_eq!(
+            islands[0].modules,
+            vec![PathBuf::from("b.ts"), PathBuf::from("c.ts")]
+        );
+        assert_eq!(islands[0].line_count, 20);
+        assert_eq!(islands[1].modules, vec![PathBuf::from("d.ts")]);
+    }
+
+    #[test]
+    fn unused_exports_in_folder() {
+        let mut modules = HashMap::new();
+
+        for path in ["src/ui/button", "src/ui/forms/input", "src/app"] {
+            modules.insert(
+                NormalizedModulePath::new(path),
+                module_with_exports(path, &["unused"]),
+            );
+        }
+
+        let config = Config::default();
+        let exports = unused_exports_under(&modules, Path::new("src/ui"), &config);
+
+        assert_eq!(exports.len(), 2);
+        assert!(unused_exports_under(&modules, Path::new("lib"), &config).is_empty());
+    }
+
+    #[test]
+    fn ignored_export_names() {
+        let path = NormalizedModulePath::new("hooks");
+        let mut modules = HashMap::new();
+        modules.insert(
+            path.clone(),
+            module_with_exports("hooks", &["useTheme", "ButtonProps", "user"]),
+        );
+
+        let config = Config {
+            ignored_export_names: vec![
+                regex::Regex::new("^use[A-Z]").unwrap(),
+                regex::Regex::new("Props$").unwrap(),
+            ],
+            ..Config::default()
+        };
+
+        let results = find_unused_exports(modules, &config);
+
+        assert_eq!(results.sorted_exports.len(), 1);
+        assert_eq!(results.sorted_exports[0].name, ExportName::named("user"));
+    }
+
+    #[test]
+    fn usages_in_unreachable_modules() {
+        let a_path = NormalizedModulePath::new("a");
+        let mut modules = HashMap::new();
+        modules.insert(a_path.clone(), module_with_exports("a", &["live", "dead"]));
+
+        for (importer, name) in [("main", "live"), ("unused", "dead")] {
+            let path = NormalizedModulePath::new(importer);
+            let mut module = test_module(importer, ModuleKind::TS);
+            module
+                .imports_mut(a_path.clone())
+                .push(ImportName::named(name));
+            modules.insert(path, module);
+        }
+
+        let config = Config {
+            entry_points: vec![String::from("main")],
+            ignore_unreachable_usages: true,
+            ..Config::default()
+        };
+
+        let stats = resolve_reachable_module_imports(&modules, &config).unwrap();
+        assert_eq!(stats.resolved, 1);
+
+        let a = &modules[&a_path];
+        assert!(a.get_export(&ExportName::named("live")).unwrap().is_used());
+        assert!(!a.get_export(&ExportName::named("dead")).unwrap().is_used());
+    }
+
+    #[test]
+    fn explain_imported_export() {
+        let mut modules = HashMap::new();
+
+        let module_a_path = NormalizedModulePath::new("a");
+        modules.insert(module_a_path.clone(), module_with_exports("a.ts", &["foo"]));
+
+        let module_b_path = NormalizedModulePath::new("b");
+        let mut module_b = test_module("b.ts", ModuleKind::TS);
+        module_b
+            .imports_mut(module_a_path.clone())
+            .push(ImportName::named("foo"));
+        modules.insert(module_b_path, module_b);
+
+        resolve_module_imports(&modules);
+
+        let config = Config::default();
+        let explanation = explain_export(
+            &modules,
+            &config,
+            Path::new("a.ts"),
+            &ExportName::named("foo"),
+        )
+        .unwrap();
+
+        assert!(explanation.usage.used_externally);
+        assert_eq!(
+            explanation.importers,
+            vec![(PathBuf::from("b.ts"), ImportName::named("foo"))]
+        );
+
+        assert!(explain_export(
+            &modules,
+            &config,
+            Path::new("a.ts"),
+            &ExportName::named("bar")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn ignored_kinds_are_not_reported() {
+        let mut module = test_module("a.d.ts", ModuleKind::DTS);
+
+        for (name, kind) in [("Foo", ExportKind::Type), ("Bar", ExportKind::Enum)] {
+            module.add_export(test_export(ExportName::named(name), kind));
+        }
+
+        let mut modules = HashMap::new();
+        modules.insert(module.path.normalized.clone(), module);
+
+        let config = Config {
+            ignored_kinds: vec![KindFilter {
+                export_kind: Some(ExportKind::Type),
+                module_kind: Some(ModuleKind::DTS),
+            }],
+            ..Config::default()
+        };
+
+        let results = find_unused_exports(modules, &config);
+        let names = results
+            .sorted_exports
+            .iter()
+            .map(|export| export.name.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![ExportName::named("Bar")]);
+    }
+
+    #[test]
+    fn ignored_kinds_without_matches_are_unused_suppressions() {
+        let mut module = test_module("a.d.ts", ModuleKind::DTS);
+        module.add_export(test_export(ExportName::named("Foo"), ExportKind::Type));
+
+        let mut modules = HashMap::new();
+        modules.insert(module.path.normalized.clone(), module);
+
+        let matching = KindFilter {
+            export_kind: Some(ExportKind::Type),
+            module_kind: None,
+        };
+        let stale = KindFilter {
+            export_kind: Some(ExportKind::Enum),
+            module_kind: None,
+        };
+
+        let config = Config {
+            ignored_kinds: vec![matching, stale],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            find_unused_suppressions(&modules, &config),
+            vec![UnusedSuppression::IgnoredKind(stale)]
+        );
+    }
+
+    #[test]
+    fn asset_paths_are_compared_without_root() {
+        let root = Path::new("./project");
+
+        assert_eq!(
+            without_root(root, Path::new("./project/./assets/logo.png")),
+            PathBuf::from("assets/logo.png")
+        );
+        assert_eq!(
+            without_root(root, Path::new("assets/logo.png")),
+            PathBuf::from("assets/logo.png")
+        );
+    }
+
+    #[test]
+    fn polyfills_are_not_builtins() {
+        let package_json =
+            serde_json::from_str::<PackageJson>(r#"{ "dependencies": { "buffer": "^6.0.0" } }"#)
+                .unwrap();
+        let config = Config {
+            polyfilled_builtins: vec![String::from("process")],
+            ..Config::default()
+        };
+
+        assert!(is_node_builtin("node:buffer", &package_json, &config));
+        assert!(is_node_builtin("fs", &package_json, &config));
+        assert!(!is_node_builtin("buffer", &package_json, &config));
+        assert!(!is_node_builtin("process", &package_json, &config));
+        assert!(!is_node_builtin("lodash", &package_json, &config));
     }
 }
@@ -1,8 +1,149 @@
-use swc_ecma_ast::{Ident, TsEntityName, TsQualifiedName};
+use std::collections::HashMap;
+
+use swc_ecma_ast::{
+    AssignPat, BinExpr, BinaryOp, Decl, Expr, ExprOrSuper, Ident, Lit, MemberExpr, Module,
+    ModuleDecl, ModuleItem, NamedExport, Pat, TsEntityName, TsQualifiedName, TsTypeAnn, UnaryExpr,
+    UnaryOp,
+};
 
 pub fn walk_ts_qualified_name(qualified_name: &TsQualifiedName) -> &Ident {
     match &qualified_name.left {
-        TsEntityName::TsQualifiedName(name) => walk_ts_qualified_name(&name),
+        TsEntityName::TsQualifiedName(name) => walk_ts_qualified_name(name),
         TsEntityName::Ident(ident) => ident,
     }
 }
+
+/// Whether the declaration is ambient, like `declare class A {}` or `declare const a: number`.
+/// It only describes something which is defined outside the module.
+pub fn is_ambient_decl(decl: &Decl) -> bool {
+    match decl {
+        Decl::Class(class_decl) => class_decl.declare,
+        Decl::Fn(fn_decl) => fn_decl.declare,
+        Decl::Var(var_decl) => var_decl.declare,
+        Decl::TsInterface(interface_decl) => interface_decl.declare,
+        Decl::TsTypeAlias(type_alias_decl) => type_alias_decl.declare,
+        Decl::TsEnum(enum_decl) => enum_decl.declare,
+        Decl::TsModule(module_decl) => module_decl.declare,
+    }
+}
+
+/// Whether a JSX element name refers to an intrinsic element like `div` or `my-element`, instead of a component.
+/// Like in the compiler, names starting with a lowercase letter are intrinsic.
+pub fn is_intrinsic_element(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase()) || name.contains('-')
+}
+
+/// The type annotation of a parameter or a variable pattern, e.g. `A` of `a: A = b` or `{ a }: A`.
+pub fn pat_type_ann(pat: &Pat) -> Option<&TsTypeAnn> {
+    match pat {
+        Pat::Ident(ident) => ident.type_ann.as_ref(),
+        Pat::Array(array) => array.type_ann.as_ref(),
+        Pat::Rest(rest) => rest.type_ann.as_ref(),
+        Pat::Object(object) => object.type_ann.as_ref(),
+        Pat::Assign(assign) => assign_pat_type_ann(assign),
+        Pat::Invalid(_) | Pat::Expr(_) => None,
+    }
+}
+
+pub fn assign_pat_type_ann(assign: &AssignPat) -> Option<&TsTypeAnn> {
+    assign
+        .type_ann
+        .as_ref()
+        .or_else(|| pat_type_ann(&assign.left))
+}
+
+/// Whether every statement of the module is a named re-export, like `export { a } from './a'`.
+/// Type-only re-exports are not counted, since they can't be rewritten as value re-exports.
+pub fn is_reexport_only_module(module: &Module) -> bool {
+    module.body.iter().all(|item| {
+        matches!(
+            item,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                src: Some(_),
+                type_only: false,
+                ..
+            }))
+        )
+    })
+}
+
+/// Returns the dotted name of an identifier or a non-computed member expression, e.g. `process.env.NODE_ENV`.
+pub fn static_member_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Ident(ident) => Some(ident.sym.to_string()),
+        Expr::Member(MemberExpr {
+            obj: ExprOrSuper::Expr(obj),
+            prop,
+            computed: false,
+            ..
+        }) => match &**prop {
+            Expr::Ident(prop) => Some(format!("{}.{}", static_member_name(obj)?, prop.sym)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn evaluate_constant(expr: &Expr, defines: &HashMap<String, String>) -> Option<String> {
+    match expr {
+        Expr::Lit(Lit::Str(string)) => Some(string.value.to_string()),
+        Expr::Lit(Lit::Bool(boolean)) => Some(boolean.value.to_string()),
+        Expr::Paren(paren) => evaluate_constant(&paren.expr, defines),
+        _ => defines.get(&static_member_name(expr)?).cloned(),
+    }
+}
+
+fn is_truthy_define(value: &str) -> bool {
+    !matches!(value, "" | "0" | "false" | "null" | "undefined")
+}
+
+/// Evaluates simple guard conditions such as `process.env.NODE_ENV === "production"` or `!__DEV__`
+/// using the configured constant definitions. Returns `None` if the condition can't be statically evaluated.
+pub fn evaluate_guard(expr: &Expr, defines: &HashMap<String, String>) -> Option<bool> {
+    if defines.is_empty() {
+        return None;
+    }
+
+    match expr {
+        Expr::Paren(paren) => evaluate_guard(&paren.expr, defines),
+        Expr::Unary(UnaryExpr {
+            op: UnaryOp::Bang,
+            arg,
+            ..
+        }) => evaluate_guard(arg, defines).map(|value| !value),
+        Expr::Bin(BinExpr {
+            op, left, right, ..
+        }) => match op {
+            BinaryOp::EqEqEq | BinaryOp::EqEq => {
+                Some(evaluate_constant(left, defines)? == evaluate_constant(right, defines)?)
+            }
+            BinaryOp::NotEqEq | BinaryOp::NotEq => {
+                Some(evaluate_constant(left, defines)? != evaluate_constant(right, defines)?)
+            }
+            BinaryOp::LogicalAnd => {
+                match (
+                    evaluate_guard(left, defines),
+                    evaluate_guard(right, defines),
+                ) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            BinaryOp::LogicalOr => {
+                match (
+                    evaluate_guard(left, defines),
+                    evaluate_guard(right, defines),
+                ) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => defines
+            .get(&static_member_name(expr)?)
+            .map(|value| is_truthy_define(value)),
+    }
+}
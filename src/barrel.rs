@@ -0,0 +1,409 @@
+//! Keeps barrel modules (`index.ts` files which only re-export) in sync with how their folder is used.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use swc_common::{BytePos, Spanned};
+use swc_ecma_ast::{ExportSpecifier, ModuleDecl, ModuleItem};
+
+use crate::{
+    config::Config,
+    dependency_graph::{ImportName, Module, NormalizedModulePath},
+    parsing::module_from_file,
+};
+
+/// A single re-export of a barrel, e.g. `export { Button as default } from './Button'`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct BarrelReexport {
+    pub source: String,
+    pub imported: String,
+    pub exported: String,
+}
+
+impl Display for BarrelReexport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.imported == self.exported {
+            write!(f, "{} from '{}'", self.exported, self.source)
+        } else {
+            write!(
+                f,
+                "{} as {} from '{}'",
+                self.imported, self.exported, self.source
+            )
+        }
+    }
+}
+
+/// A barrel whose re-exports differ from the exports used outside its folder.
+pub struct BarrelUpdate {
+    pub path: PathBuf,
+    /// Re-exports which nothing imports from the barrel.
+    pub removed: Vec<BarrelReexport>,
+    /// Exports which are imported directly from modules in the barrel's folder, bypassing the barrel.
+    pub added: Vec<BarrelReexport>,
+    /// The updated barrel. Kept re-exports keep their formatting, see `splice_barrel`.
+    pub contents: String,
+}
+
+/// Compares each barrel to the exports which are used outside its folder. Only out of date barrels are returned.
+///
+/// A barrel is an `index.ts` or `index.tsx` module which consists only of named re-exports.
+/// Barrels with other statements, like `export *` or local declarations, are never changed.
+/// Imports must have been resolved with `resolve_module_imports` first.
+pub fn find_barrel_updates(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    _config: &Config,
+) -> Vec<BarrelUpdate> {
+    let mut updates = modules
+        .values()
+        .filter(|module| is_barrel(module))
+        .filter_map(|barrel| find_barrel_update(barrel, modules))
+        .collect::<Vec<_>>();
+
+    updates.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+    updates
+}
+
+/// Whether the barrel on disk differs from its updated contents, which `barrel --check` reports without writing.
+/// A barrel which can't be read is stale.
+pub fn is_stale(update: &BarrelUpdate) -> bool {
+    fs::read_to_string(&update.path).map_or(true, |contents| contents != update.contents)
+}
+
+fn is_barrel(module: &Module) -> bool {
+    module
+        .path
+        .normalized
+        .file_name()
+        .is_some_and(|name| name == "index")
+        && module.only_reexports
+        && !module.exports.is_empty()
+        && module
+            .exports
+            .iter()
+            .all(|export| export.reexported_from.is_some())
+}
+
+fn find_barrel_update(
+    barrel: &Module,
+    modules: &HashMap<NormalizedModulePath, Module>,
+) -> Option<BarrelUpdate> {
+    let folder = barrel.path.normalized.parent()?;
+
+    let mut current = BTreeSet::new();
+    let mut desired = BTreeSet::new();
+
+    for export in &barrel.exports {
+        let (source, imported_name) = export.reexported_from.as_ref()?;
+
+        let reexport = BarrelReexport {
+            source: source.clone(),
+            imported: imported_name.to_string(),
+            exported: export.name.to_string(),
+        };
+
+        if export.usage.get().used_externally || barrel.is_wildcard_imported() {
+            desired.insert(reexport.clone());
+        }
+
+        current.insert(reexport);
+    }
+
+    let exported_names = current
+        .iter()
+        .map(|reexport| reexport.exported.clone())
+        .collect::<BTreeSet<_>>();
+
+    for importer in modules.values() {
+        if importer.path.normalized.starts_with(folder) {
+            continue;
+        }
+
+        for (import_path, imports) in &importer.imported_modules {
+            if !import_path.starts_with(folder) || *import_path == barrel.path.normalized {
+                continue;
+            }
+
+            for import in imports {
+                // Default and namespace imports don't have a name which could be re-exported
+                let name = match import {
+                    ImportName::Named(name) => name.to_string(),
+                    _ => continue,
+                };
+
+                if exported_names.contains(&name) {
+                    continue;
+                }
+
+                desired.insert(BarrelReexport {
+                    source: relative_import_source(folder, import_path),
+                    imported: name.clone(),
+                    exported: name,
+                });
+            }
+        }
+    }
+
+    if current == desired {
+        return None;
+    }
+
+    let removed = current.difference(&desired).cloned().collect::<Vec<_>>();
+    let added = desired.difference(&current).cloned().collect::<Vec<_>>();
+
+    // If the barrel can't be read again, it's regenerated from scratch
+    let contents = module_from_file(&barrel.path.root_relative, barrel.kind)
+        .map(|(source_map, module)| {
+            let file = source_map.lookup_char_pos(module.span.lo()).file;
+            splice_barrel(&file.src, file.start_pos, &module, &removed, &added)
+        })
+        .unwrap_or_else(|_| render_barrel(&desired));
+
+    Some(BarrelUpdate {
+        path: (*barrel.path.root_relative).clone(),
+        removed,
+        added,
+        contents,
+    })
+}
+
+/// Applies an update to the source of a barrel without reformatting it, so that the diff stays minimal.
+/// Removed re-exports are cut out of their statements, along with statements which become empty.
+/// Added re-exports are appended as new statements. `start_pos` is the position of the source in the source map.
+pub fn splice_barrel(
+    source: &str,
+    start_pos: BytePos,
+    module: &swc_ecma_ast::Module,
+    removed: &[BarrelReexport],
+    added: &[BarrelReexport],
+) -> String {
+    let offset = |pos: BytePos| (pos.0 - start_pos.0) as usize;
+    let mut cuts: Vec<Range<usize>> = Vec::new();
+
+    for item in &module.body {
+        let (named_export, import_source) = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named_export)) => {
+                match &named_export.src {
+                    Some(src) => (named_export, src.value.to_string()),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        let specifiers = named_export
+            .specifiers
+            .iter()
+            .map(|specifier| {
+                let is_removed = match specifier {
+                    ExportSpecifier::Named(named) => {
+                        let exported = named.exported.as_ref().unwrap_or(&named.orig);
+
+                        removed.iter().any(|reexport| {
+                            reexport.source == import_source
+                                && reexport.imported == *named.orig.sym
+                                && reexport.exported == *exported.sym
+                        })
+                    }
+                    _ => false,
+                };
+
+                (specifier.span(), is_removed)
+            })
+            .collect::<Vec<_>>();
+
+        if specifiers.iter().all(|(_, is_removed)| *is_removed) {
+            let start = offset(named_export.span.lo());
+            let mut end = offset(named_export.span.hi());
+
+            // The statement is removed with its semicolon and line break
+            for suffix in [";", "\r\n", "\n"] {
+                if source[end..].starts_with(suffix) {
+                    end += suffix.len();
+                }
+            }
+
+            cuts.push(start..end);
+            continue;
+        }
+
+        // Each run of removed specifiers is cut with the comma between it and a kept specifier
+        for (is_removed, run) in &specifiers
+            .iter()
+            .enumerate()
+            .group_by(|(_, (_, is_removed))| *is_removed)
+        {
+            if !is_removed {
+                continue;
+            }
+
+            let run = run.map(|(index, _)| index).collect::<Vec<_>>();
+            let (first, last) = (run[0], run[run.len() - 1]);
+
+            match specifiers.get(last + 1) {
+                Some((next, _)) => {
+                    cuts.push(offset(specifiers[first].0.lo())..offset(next.lo()));
+                }
+                None => {
+                    cuts.push(
+                        offset(specifiers[first - 1].0.hi())..offset(specifiers[last].0.hi()),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut contents = String::with_capacity(source.len());
+    let mut position = 0;
+
+    for cut in cuts {
+        contents.push_str(&source[position..cut.start]);
+        position = cut.end;
+    }
+
+    contents.push_str(&source[position..]);
+
+    if !added.is_empty() {
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+
+        contents.push_str(&render_barrel(&added.iter().cloned().collect()));
+    }
+
+    contents
+}
+
+/// Builds an import source like `./components/Button` from the barrel's folder to a module in it.
+fn relative_import_source(folder: &Path, module: &Path) -> String {
+    let relative = module.strip_prefix(folder).unwrap_or(module);
+    let relative = match relative.file_name() {
+        Some(name) if name == "index" => relative.parent().unwrap_or(relative),
+        _ => relative,
+    };
+
+    let components = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+
+    if components.is_empty() {
+        String::from(".")
+    } else {
+        format!("./{}", components.join("/"))
+    }
+}
+
+/// Renders one export statement per source.
+pub fn render_barrel(reexports: &BTreeSet<BarrelReexport>) -> String {
+    let mut contents = String::new();
+
+    for (source, reexports) in &reexports.iter().group_by(|reexport| &reexport.source) {
+        contents.push_str(&format!(
+            "export {{ {} }} from '{}';\n",
+            reexports.map(render_specifier).join(", "),
+            source
+        ));
+    }
+
+    contents
+}
+
+fn render_specifier(reexport: &BarrelReexport) -> String {
+    if reexport.imported == reexport.exported {
+        reexport.exported.clone()
+    } else {
+        format!("{} as {}", reexport.imported, reexport.exported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dependency_graph::ModuleKind, parsing::module_from_source};
+
+    fn reexport(source: &str, imported: &str, exported: &str) -> BarrelReexport {
+        BarrelReexport {
+            source: source.to_string(),
+            imported: imported.to_string(),
+            exported: exported.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_groups_by_source() {
+        let reexports = vec![
+            reexport("./Button", "Button", "Button"),
+            reexport("./Button", "ButtonProps", "ButtonProps"),
+            reexport("./Input", "default", "Input"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            render_barrel(&reexports),
+            "export { Button, ButtonProps } from './Button';\nexport { default as Input } from './Input';\n"
+        );
+    }
+
+    #[test]
+    fn splice_keeps_formatting() {
+        let source = "export { Button, ButtonProps } from './Button';\nexport {\n  Input,\n  InputProps,\n} from './Input';\nexport { Old } from './Old';\n";
+        let (source_map, module) =
+            module_from_source(String::from(source), ModuleKind::TS).unwrap();
+        let start_pos = source_map.lookup_char_pos(module.span.lo()).file.start_pos;
+
+        let removed = vec![
+            reexport("./Button", "ButtonProps", "ButtonProps"),
+            reexport("./Input", "Input", "Input"),
+            reexport("./Old", "Old", "Old"),
+        ];
+        let added = vec![reexport("./Select", "Select", "Select")];
+
+        assert_eq!(
+            splice_barrel(source, start_pos, &module, &removed, &added),
+            "export { Button } from './Button';\nexport {\n  InputProps,\n} from './Input';\nexport { Select } from './Select';\n"
+        );
+    }
+
+    #[test]
+    fn stale_barrels_differ_from_their_update() {
+        let path = std::env::temp_dir().join(format!("customs-barrel-{}.ts", std::process::id()));
+        let contents = "export { Button } from './Button';\n";
+        std::fs::write(&path, contents).unwrap();
+
+        let update = |contents: &str| BarrelUpdate {
+            path: path.clone(),
+            removed: Vec::new(),
+            added: vec![reexport("./Button", "Button", "Button")],
+            contents: contents.to_string(),
+        };
+
+        assert!(!is_stale(&update(contents)));
+        assert!(is_stale(&update(
+            "export { Button } from './Button';\nexport { Input } from './Input';\n"
+        )));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(is_stale(&update(contents)));
+    }
+
+    #[test]
+    fn import_source_of_nested_index() {
+        assert_eq!(
+            relative_import_source(Path::new("src/ui"), Path::new("src/ui/forms/index")),
+            "./forms"
+        );
+        assert_eq!(
+            relative_import_source(Path::new("src/ui"), Path::new("src/ui/Button")),
+            "./Button"
+        );
+    }
+}
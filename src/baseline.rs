@@ -0,0 +1,219 @@
+//! Known unused exports, so that a large codebase can adopt the analysis and only fail on new findings.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{analysis::UnusedExportsResults, file_writes::write_files};
+
+/// Name of the baseline file, in the root of the analyzed project.
+pub const BASELINE_FILE_NAME: &str = ".customs-baseline.json";
+
+/// The contents of the baseline file. Exports are identified by their file and name instead of line numbers,
+/// so that unrelated changes to a file don't bring its known exports back.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Baseline {
+    #[serde(default)]
+    pub unused_exports: Vec<BaselineExport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BaselineExport {
+    /// Relative to the project root, with forward slashes.
+    pub file: String,
+    pub name: String,
+}
+
+/// Formats the path of a module relative to the root, so that the baseline is the same on every platform.
+fn baseline_file(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl Baseline {
+    pub fn from_results(results: &UnusedExportsResults, root: &Path) -> Self {
+        let mut unused_exports = results
+            .sorted_exports
+            .iter()
+            .map(|export| BaselineExport {
+                file: baseline_file(root, export.location.path()),
+                name: export.name.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        unused_exports.sort();
+        unused_exports.dedup();
+
+        Baseline { unused_exports }
+    }
+
+    /// The entries which match no unused export, because the export has since been used or removed.
+    pub fn stale_entries(
+        &self,
+        results: &UnusedExportsResults,
+        root: &Path,
+    ) -> Vec<BaselineExport> {
+        let current = results
+            .sorted_exports
+            .iter()
+            .map(|export| {
+                (
+                    baseline_file(root, export.location.path()),
+                    export.name.to_string(),
+                )
+            })
+            .collect::<HashSet<_>>();
+
+        self.unused_exports
+            .iter()
+            .filter(|export| !current.contains(&(export.file.clone(), export.name.clone())))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the unused exports which are already in the baseline.
+    pub fn retain_new_exports(&self, results: &mut UnusedExportsResults, root: &Path) {
+        let known = self
+            .unused_exports
+            .iter()
+            .map(|export| (export.file.as_str(), export.name.as_str()))
+            .collect::<HashSet<_>>();
+
+        results.sorted_exports.retain(|export| {
+            let file = baseline_file(root, export.location.path());
+            !known.contains(&(file.as_str(), export.name.to_string().as_str()))
+        });
+    }
+}
+
+pub fn read_baseline(root: &Path) -> anyhow::Result<Baseline> {
+    let path = root.join(BASELINE_FILE_NAME);
+
+    let contents = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Failed to read {}. It can be created with --update-baseline.",
+            path.display()
+        )
+    })?;
+
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn write_baseline(root: &Path, baseline: &Baseline) -> anyhow::Result<()> {
+    let path = root.join(BASELINE_FILE_NAME);
+    let contents = serde_json::to_string_pretty(baseline)? + "\n";
+
+    if let Some((_, err)) = write_files(&[(path.as_path(), contents.as_str())])
+        .into_iter()
+        .next()
+    {
+        return Err(err);
+    }
+
+    info!(
+        "Wrote {} unused exports to {}",
+        baseline.unused_exports.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use swc_common::DUMMY_SP;
+
+    use super::*;
+    use crate::{
+        analysis::UnusedExport,
+        dependency_graph::{ExportKind, ExportName, ModuleSourceAndLine},
+    };
+
+    fn unused_export(path: &str, line: usize, name: &str) -> UnusedExport {
+        let location = ModuleSourceAndLine::new(Arc::new(PathBuf::from(path)), line);
+
+        UnusedExport {
+            name: ExportName::named(name),
+            statement: location.clone(),
+            statement_span: DUMMY_SP,
+            location,
+            kind: ExportKind::Value,
+            usage: Default::default(),
+            line_count: 1,
+            statement_export_count: 1,
+        }
+    }
+
+    #[test]
+    fn only_new_exports_are_kept() {
+        let root = Path::new("./project");
+        let baseline = Baseline::from_results(
+            &UnusedExportsResults {
+                sorted_exports: vec![unused_export("./project/./src/a.ts", 3, "old")],
+            },
+            root,
+        );
+
+        assert_eq!(
+            baseline.unused_exports,
+            vec![BaselineExport {
+                file: String::from("src/a.ts"),
+                name: String::from("old"),
+            }]
+        );
+
+        // The known export has moved, which doesn't make it new
+        let mut results = UnusedExportsResults {
+            sorted_exports: vec![
+                unused_export("./project/./src/a.ts", 10, "old"),
+                unused_export("./project/./src/a.ts", 12, "new"),
+            ],
+        };
+        baseline.retain_new_exports(&mut results, root);
+
+        assert_eq!(results.sorted_exports.len(), 1);
+        assert_eq!(results.sorted_exports[0].name, ExportName::named("new"));
+    }
+
+    #[test]
+    fn entries_without_findings_are_stale() {
+        let root = Path::new("./project");
+        let baseline = Baseline {
+            unused_exports: vec![
+                BaselineExport {
+                    file: String::from("src/a.ts"),
+                    name: String::from("old"),
+                },
+                BaselineExport {
+                    file: String::from("src/a.ts"),
+                    name: String::from("removed"),
+                },
+            ],
+        };
+        let results = UnusedExportsResults {
+            sorted_exports: vec![unused_export("./project/src/a.ts", 3, "old")],
+        };
+
+        assert_eq!(
+            baseline.stale_entries(&results, root),
+            vec![BaselineExport {
+                file: String::from("src/a.ts"),
+                name: String::from("removed"),
+            }]
+        );
+    }
+}
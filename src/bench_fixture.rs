@@ -0,0 +1,181 @@
+//! Generator for large synthetic TypeScript projects, used for benchmarking.
+
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FixtureOptions {
+    pub module_count: usize,
+    pub exports_per_module: usize,
+    /// How many other modules each module imports from.
+    pub import_fan_out: usize,
+    pub seed: u64,
+}
+
+impl Default for FixtureOptions {
+    fn default() -> Self {
+        FixtureOptions {
+            module_count: 1000,
+            exports_per_module: 20,
+            import_fan_out: 5,
+            seed: 0x5eed,
+        }
+    }
+}
+
+/// A tiny xorshift PRNG, so that fixtures are reproducible without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+fn export_name(module: usize, export: usize) -> String {
+    match export % 6 {
+        0 => format!("value{}_{}", module, export),
+        1 => format!("fn{}_{}", module, export),
+        2 => format!("Interface{}_{}", module, export),
+        3 => format!("Type{}_{}", module, export),
+        4 => format!("Class{}_{}", module, export),
+        _ => format!("Enum{}_{}", module, export),
+    }
+}
+
+fn module_name(module: usize) -> String {
+    format!("module_{}", module)
+}
+
+fn generate_module(module: usize, options: &FixtureOptions, rng: &mut Rng) -> String {
+    let mut source = String::new();
+
+    // Only import from modules with a smaller index to keep the import graph acyclic
+    let mut used_names = Vec::new();
+
+    if module > 0 && options.exports_per_module > 0 {
+        for _ in 0..options.import_fan_out {
+            let source_module = rng.below(module);
+            let export = rng.below(options.exports_per_module);
+            let name = export_name(source_module, export);
+
+            if used_names.iter().any(|(_, used)| *used == name) {
+                continue;
+            }
+
+            writeln!(
+                source,
+                "import {{ {} }} from \"./{}\";",
+                name,
+                module_name(source_module)
+            )
+            .unwrap();
+
+            used_names.push((export, name));
+        }
+    }
+
+    writeln!(source).unwrap();
+
+    for export in 0..options.exports_per_module {
+        let name = export_name(module, export);
+
+        let declaration = match export % 6 {
+            0 => format!("export const {} = {{ a: {}, b: \"{}\" }};", name, export, name),
+            1 => format!(
+                "export function {}<T>(x: T, y: number): T {{\n  const local = y * 2;\n  if (local > 10) {{\n    return x;\n  }}\n  return x;\n}}",
+                name
+            ),
+            2 => format!(
+                "export interface {} {{\n  id: number;\n  name: string;\n  tags: string[];\n}}",
+                name
+            ),
+            3 => format!("export type {} = {{ kind: \"{}\"; value: number }};", name, name),
+            4 => format!(
+                "export class {} {{\n  private count = 0;\n  increment(by: number) {{\n    this.count += by;\n  }}\n}}",
+                name
+            ),
+            _ => format!("export enum {} {{\n  A,\n  B,\n  C,\n}}", name),
+        };
+
+        writeln!(source, "{}\n", declaration).unwrap();
+    }
+
+    // Reference the imported items, as either values or types
+    for (i, (export, name)) in used_names.iter().enumerate() {
+        let usage = match export % 6 {
+            0 => format!("const usage{} = {};", i, name),
+            1 => format!("const usage{} = {}(\"x\", {});", i, name, i),
+            2 | 3 => format!("let usage{}: {} | undefined;", i, name),
+            4 => format!("const usage{} = new {}();", i, name),
+            _ => format!("const usage{} = {}.A;", i, name),
+        };
+
+        writeln!(source, "{}", usage).unwrap();
+    }
+
+    source
+}
+
+/// Generates the files of a synthetic project as (root relative path, contents) pairs.
+pub fn generate_fixture(options: &FixtureOptions) -> Vec<(PathBuf, String)> {
+    let mut rng = Rng::new(options.seed);
+    let mut files = Vec::with_capacity(options.module_count + 3);
+
+    for module in 0..options.module_count {
+        let path = PathBuf::from("src").join(format!("{}.ts", module_name(module)));
+        files.push((path, generate_module(module, options, &mut rng)));
+    }
+
+    let mut index = String::new();
+    for module in (0..options.module_count).rev().take(options.import_fan_out) {
+        writeln!(index, "export * from \"./{}\";", module_name(module)).unwrap();
+    }
+    files.push((PathBuf::from("src/index.ts"), index));
+
+    files.push((
+        PathBuf::from("package.json"),
+        String::from("{\n  \"name\": \"bench-fixture\",\n  \"main\": \"src/index.ts\",\n  \"dependencies\": {}\n}\n"),
+    ));
+
+    files.push((
+        PathBuf::from("tsconfig.json"),
+        String::from("{\n  \"compilerOptions\": {\n    \"strict\": true\n  }\n}\n"),
+    ));
+
+    files
+}
+
+pub fn write_fixture(target_dir: &Path, options: &FixtureOptions) -> anyhow::Result<usize> {
+    let files = generate_fixture(options);
+
+    for (path, contents) in &files {
+        let path = target_dir.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(files.len())
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use customs_analysis::bench_fixture::{write_fixture, FixtureOptions};
+
+/// Generates a synthetic TypeScript project for benchmarking.
+#[derive(StructOpt)]
+#[structopt(version = "0.1", author = "Paavo Huhtala <paavo.huhtala@gmail.com>")]
+struct Args {
+    target_dir: PathBuf,
+
+    #[structopt(long, default_value = "1000")]
+    modules: usize,
+
+    #[structopt(long, default_value = "20")]
+    exports: usize,
+
+    /// Number of imports per module
+    #[structopt(long, default_value = "5")]
+    fan_out: usize,
+
+    #[structopt(long, default_value = "24301")]
+    seed: u64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+
+    let options = FixtureOptions {
+        module_count: args.modules,
+        exports_per_module: args.exports,
+        import_fan_out: args.fan_out,
+        seed: args.seed,
+    };
+
+    let file_count = write_fixture(&args.target_dir, &options)?;
+
+    println!(
+        "Wrote {} files to {}",
+        file_count,
+        args.target_dir.display()
+    );
+
+    Ok(())
+}
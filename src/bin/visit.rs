@@ -1,15 +1,40 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
+use anyhow::anyhow;
 use structopt::StructOpt;
 
 use customs_analysis::module_visitor::ModuleVisitor;
 use customs_analysis::parsing::module_from_file;
+use customs_analysis::visitor_dump::{visitor_to_dot, visitor_to_json};
 use swc_ecma_visit::Visit;
 
+enum VisitFormat {
+    Debug,
+    Json,
+    Dot,
+}
+
+impl FromStr for VisitFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "debug" => Ok(Self::Debug),
+            "json" => Ok(Self::Json),
+            "dot" => Ok(Self::Dot),
+            _ => Err(anyhow!("Unknown output format: {}", s)),
+        }
+    }
+}
+
 #[derive(StructOpt)]
 #[structopt(version = "0.1", author = "Paavo Huhtala <paavo.huhtala@gmail.com>")]
 struct Args {
     target_file: PathBuf,
+
+    /// Output format: "debug" prints the AST and the visitor state, "json" prints the scope tree, exports and imports, "dot" renders the scope tree as a Graphviz graph.
+    #[structopt(long, default_value = "debug", possible_values = &["debug", "json", "dot"])]
+    format: VisitFormat,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -20,12 +45,21 @@ fn main() -> anyhow::Result<()> {
         customs_analysis::dependency_graph::ModuleKind::TS,
     )?;
 
-    println!("{:#?}", module);
+    if let VisitFormat::Debug = args.format {
+        println!("{:#?}", module);
+    }
 
-    let mut analyzer = ModuleVisitor::new(PathBuf::from("unknown"), source_map);
+    let mut analyzer = ModuleVisitor::new(args.target_file.clone(), source_map);
     analyzer.visit_module(&module, &module);
 
-    println!("{:#?}", analyzer);
+    match args.format {
+        VisitFormat::Debug => println!("{:#?}", analyzer),
+        VisitFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&visitor_to_json(&analyzer))?
+        ),
+        VisitFormat::Dot => print!("{}", visitor_to_dot(&analyzer)),
+    }
 
     Ok(())
 }
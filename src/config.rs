@@ -1,15 +1,34 @@
-use std::{path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::anyhow;
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cancellation::CancellationToken,
+    dependency_graph::{ExportKind, ExportName, ModuleKind},
+    metrics::Metrics,
+    package_json::PackageJsonIndex,
+    tsconfig::ProjectCompilerOptions,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum OutputFormat {
     Text,
     Json,
+    Sarif,
 }
 
 impl OutputFormat {
-    pub const ALL_FORMATS: &'static [&'static str] = &["text", "json"];
+    pub const ALL_FORMATS: &'static [&'static str] = &["text", "json", "sarif"];
 }
 
 impl FromStr for OutputFormat {
@@ -19,27 +38,172 @@ impl FromStr for OutputFormat {
         match s {
             "text" => Ok(Self::Text),
             "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
             _ => Err(anyhow!("Unknown output format: {}", s)),
         }
     }
 }
 
+/// A report format and where it's written, e.g. `json=report.json`. Without a path, the report is printed to stdout.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutputTarget {
+    pub format: OutputFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl OutputTarget {
+    pub fn stdout(format: OutputFormat) -> Self {
+        OutputTarget { format, path: None }
+    }
+}
+
+impl FromStr for OutputTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((format, path)) if !path.is_empty() => Ok(OutputTarget {
+                format: format.parse()?,
+                path: Some(PathBuf::from(path)),
+            }),
+            Some(_) => Err(anyhow!("Expected a path after '=': {}", s)),
+            None => Ok(OutputTarget::stdout(s.parse()?)),
+        }
+    }
+}
+
+/// How file locations in the text report are turned into OSC 8 terminal hyperlinks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HyperlinkStyle {
+    Off,
+    /// `file://` URLs, which open the file in the default application.
+    File,
+    /// `vscode://file/...` URLs, which open the file in VS Code at the line.
+    Vscode,
+}
+
+impl HyperlinkStyle {
+    pub const ALL_STYLES: &'static [&'static str] = &["off", "file", "vscode"];
+}
+
+impl FromStr for HyperlinkStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "file" => Ok(Self::File),
+            "vscode" => Ok(Self::Vscode),
+            _ => Err(anyhow!("Unknown hyperlink style: {}", s)),
+        }
+    }
+}
+
+/// Whether the text report is colored with ANSI escape codes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ColorChoice {
+    /// Colors when the report is printed to a terminal and `NO_COLOR` isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub const ALL_CHOICES: &'static [&'static str] = &["auto", "always", "never"];
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(anyhow!("Unknown color choice: {}", s)),
+        }
+    }
+}
+
+/// Decides whether the text report is colored. With `auto`, a report written to a file isn't colored, and neither is
+/// one printed to stdout if it's not a terminal or the `NO_COLOR` environment variable is set (see https://no-color.org).
+pub fn use_colors(
+    choice: ColorChoice,
+    outputs: &[OutputTarget],
+    no_color: bool,
+    stdout_is_terminal: bool,
+) -> bool {
+    let prints_text = outputs
+        .iter()
+        .any(|output| output.format == OutputFormat::Text && output.path.is_none());
+
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => prints_text && stdout_is_terminal && !no_color,
+    }
+}
+
+/// How the paths of findings are written in reports.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputPaths {
+    /// Paths as they were found, relative to the folder the analysis was started in or to the project root.
+    Relative,
+    Absolute,
+    /// Relative to the git repository which contains the file, or absolute outside of a repository.
+    RepoRoot,
+}
+
+impl OutputPaths {
+    pub const ALL_STYLES: &'static [&'static str] = &["relative", "absolute", "repo-root"];
+}
+
+impl FromStr for OutputPaths {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute),
+            "repo-root" => Ok(Self::RepoRoot),
+            _ => Err(anyhow!("Unknown path style: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum AnalyzeTarget {
     Types,
     Values,
     All,
+    /// Only the dependency rules. Imports between modules are not resolved, which saves time in large projects.
+    Dependencies,
 }
 
 impl AnalyzeTarget {
-    pub const ALL_TARGETS: &'static [&'static str] = &["types", "values", "all"];
+    pub const ALL_TARGETS: &'static [&'static str] = &["types", "values", "all", "dependencies"];
 
-    #[allow(dead_code)]
     pub fn as_str(self) -> &'static str {
         match self {
             AnalyzeTarget::Types => "types",
             AnalyzeTarget::Values => "values",
             AnalyzeTarget::All => "all",
+            AnalyzeTarget::Dependencies => "dependencies",
+        }
+    }
+
+    /// Whether exports are analyzed, which requires resolving the imports between modules.
+    pub fn analyzes_exports(self) -> bool {
+        self != AnalyzeTarget::Dependencies
+    }
+
+    /// Combines two targets. Dependencies are checked with every target, so they only add to each other.
+    fn combine(self, other: AnalyzeTarget) -> AnalyzeTarget {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (AnalyzeTarget::Dependencies, target) | (target, AnalyzeTarget::Dependencies) => target,
+            _ => AnalyzeTarget::All,
         }
     }
 }
@@ -47,20 +211,873 @@ impl AnalyzeTarget {
 impl FromStr for AnalyzeTarget {
     type Err = anyhow::Error;
 
+    /// Parses a target, or several separated by commas, like `types,dependencies`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "types" => Ok(Self::Types),
-            "values" => Ok(Self::Values),
-            "all" => Ok(Self::All),
-            _ => Err(anyhow!("Unknown analyze target: {}", s)),
+        s.split(',')
+            .map(|target| match target.trim() {
+                "types" => Ok(Self::Types),
+                "values" => Ok(Self::Values),
+                "all" => Ok(Self::All),
+                "dependencies" => Ok(Self::Dependencies),
+                _ => Err(anyhow!("Unknown analyze target: {}", target)),
+            })
+            .reduce(|a, b| Ok(a?.combine(b?)))
+            .unwrap_or_else(|| Err(anyhow!("Expected an analyze target")))
+    }
+}
+
+/// A class of findings which can be configured separately.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Rule {
+    UnusedExports,
+    UnusedDependencies,
+    UnusedTypeParameters,
+    UnusedAssets,
+    MisplacedDependencies,
+    LockfileConsistency,
+    DefaultExportMismatches,
+    UnreachableModules,
+}
+
+impl Rule {
+    pub const ALL_RULES: [Rule; 8] = [
+        Rule::UnusedExports,
+        Rule::UnusedDependencies,
+        Rule::UnusedTypeParameters,
+        Rule::UnusedAssets,
+        Rule::MisplacedDependencies,
+        Rule::LockfileConsistency,
+        Rule::DefaultExportMismatches,
+        Rule::UnreachableModules,
+    ];
+
+    /// Whether the rule checks package.json, instead of the exports and modules of the project.
+    pub fn is_dependency_rule(self) -> bool {
+        matches!(
+            self,
+            Rule::UnusedDependencies | Rule::MisplacedDependencies | Rule::LockfileConsistency
+        )
+    }
+
+    /// The name of the rule on the command line, e.g. `unused-exports`.
+    pub fn cli_name(self) -> &'static str {
+        match self {
+            Rule::UnusedExports => "unused-exports",
+            Rule::UnusedDependencies => "unused-dependencies",
+            Rule::UnusedTypeParameters => "unused-type-parameters",
+            Rule::UnusedAssets => "unused-assets",
+            Rule::MisplacedDependencies => "misplaced-dependencies",
+            Rule::LockfileConsistency => "lockfile-consistency",
+            Rule::DefaultExportMismatches => "default-export-mismatches",
+            Rule::UnreachableModules => "unreachable-modules",
         }
     }
 }
 
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL_RULES
+            .iter()
+            .copied()
+            .find(|rule| rule.cli_name() == s)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown rule: {}. The rules are {}.",
+                    s,
+                    Rule::ALL_RULES
+                        .iter()
+                        .map(|rule| rule.cli_name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The findings are not reported.
+    Off,
+    /// The findings are reported.
+    Warn,
+    /// The findings are reported, and the process exits with a non-zero exit code if there are any.
+    Error,
+}
+
+/// Matches exports by their kind and the kind of the module they are in. Omitted kinds match everything.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct KindFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_kind: Option<ExportKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module_kind: Option<ModuleKind>,
+}
+
+impl KindFilter {
+    pub fn matches(&self, export_kind: ExportKind, module_kind: ModuleKind) -> bool {
+        self.export_kind.is_none_or(|kind| kind == export_kind)
+            && self.module_kind.is_none_or(|kind| kind == module_kind)
+    }
+}
+
+/// A file name suffix which is analyzed as the given module kind, e.g. `.web.tsx` as TSX.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ModuleExtension {
+    /// The suffix including the leading dot.
+    pub suffix: String,
+    pub kind: ModuleKind,
+}
+
+impl FromStr for ModuleExtension {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (suffix, kind) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected extension in SUFFIX=KIND format: {}", s))?;
+
+        let suffix = if suffix.starts_with('.') {
+            suffix.to_string()
+        } else {
+            format!(".{}", suffix)
+        };
+
+        Ok(ModuleExtension {
+            suffix,
+            kind: kind.parse()?,
+        })
+    }
+}
+
+pub const BYTES_PER_MEGABYTE: usize = 1024 * 1024;
+
+/// Converts the `--max-memory` budget from megabytes to bytes.
+pub fn max_memory_bytes(megabytes: usize) -> anyhow::Result<usize> {
+    megabytes
+        .checked_mul(BYTES_PER_MEGABYTE)
+        .ok_or_else(|| anyhow!("--max-memory {} MB is too large", megabytes))
+}
+
+#[derive(Clone)]
 pub struct Config {
     pub root: Arc<PathBuf>,
-    pub format: OutputFormat,
+    /// The reports to produce. Only one of them can be printed to stdout.
+    pub outputs: Vec<OutputTarget>,
+    /// A template which renders the text report instead of the built-in reporters, see `template.rs`.
+    pub text_template: Option<PathBuf>,
+    /// Makes file locations in the text report clickable in terminals which support OSC 8 hyperlinks.
+    pub hyperlinks: HyperlinkStyle,
+    /// Whether the text report uses ANSI colors, decided with `use_colors`.
+    pub colors: bool,
+    /// How the paths of findings are written in all report formats.
+    pub output_paths: OutputPaths,
 
     pub analyze_target: AnalyzeTarget,
+    /// Whether the analyze target was given on the command line, in which case customs.json doesn't change it.
+    pub analyze_target_from_cli: bool,
+    /// Folders under the root which are searched for modules. If empty, the whole root is searched.
+    pub source_roots: Vec<PathBuf>,
     pub ignored_folders: Vec<PathBuf>,
+    /// Gitignore-style glob patterns for files and folders which are not analyzed, relative to the root.
+    pub ignore_patterns: Vec<String>,
+    /// Glob patterns for the files which are analyzed, relative to the root. If empty, every module is analyzed.
+    pub include_patterns: Vec<String>,
+    /// Whether files ignored by git (.gitignore, .git/info/exclude and the global gitignore) are skipped.
+    pub respect_gitignore: bool,
+    /// Names of gitignore-style files which are read in addition to `.customsignore`, e.g. `.eslintignore`.
+    pub ignore_file_names: Vec<String>,
+    /// Whether hidden files and folders, whose names start with a dot, are searched.
+    pub include_hidden: bool,
+    /// Glob patterns for modules whose exports are used from outside the project, relative to the root.
+    pub entry_points: Vec<String>,
+    /// Imports in modules which can't be reached from the entry points don't count as usage.
+    pub ignore_unreachable_usages: bool,
+    /// Glob patterns for modules whose exports are partly created at runtime, relative to the root.
+    pub dynamic_exports: Vec<String>,
+    /// Folders (relative to the root) containing images, stylesheets and other files which should be imported by some module.
+    pub asset_folders: Vec<String>,
+    /// Glob patterns for tests, stories and other modules which only need devDependencies.
+    pub dev_files: Vec<String>,
+    /// Node builtin module names which refer to an installed polyfill package instead of the builtin.
+    pub polyfilled_builtins: Vec<String>,
+
+    /// Additional file suffixes to analyze, checked before the built-in TypeScript extensions.
+    pub extensions: Vec<ModuleExtension>,
+
+    /// Extensions (without the leading dot) probed in order when resolving extensionless imports.
+    pub resolve_extensions: Vec<String>,
+
+    /// Options from tsconfig.json which affect parsing and import resolution.
+    pub compiler_options: ProjectCompilerOptions,
+
+    /// Packages whose type declarations are parsed to classify re-exports from them.
+    pub analyzed_packages: Vec<String>,
+
+    /// Constant values for expressions like `process.env.NODE_ENV` or `__DEV__`.
+    /// When non-empty, branches guarded by conditions which can never be true are not analyzed.
+    pub defines: Arc<HashMap<String, String>>,
+
+    /// Read the installed package.json of each reported dependency from node_modules, to show its version and description.
+    pub describe_dependencies: bool,
+
+    /// Report unused dependencies which are also overridden (`overrides`, `resolutions`) in their own section,
+    /// instead of leaving them out.
+    pub list_overridden_dependencies: bool,
+
+    /// Approximate limit for the memory retained by parsed modules, in bytes.
+    pub max_memory: Option<usize>,
+
+    /// Only report unused exports whose line hasn't been changed in git within this duration.
+    pub min_age: Option<Duration>,
+
+    /// Only report unused exports declared in files which git sees as changed since this revision, e.g. `HEAD`
+    /// or `origin/main`.
+    pub changed_since: Option<String>,
+
+    /// Don't report unused exports which are listed in the baseline file of the project.
+    pub use_baseline: bool,
+
+    /// Write the unused exports to the baseline file of the project, replacing its contents.
+    pub update_baseline: bool,
+
+    /// Exports of these kinds are never reported as unused.
+    pub ignored_kinds: Vec<KindFilter>,
+
+    /// Exports whose name matches one of these are never reported as unused, e.g. `^use[A-Z]` or `Props$`.
+    pub ignored_export_names: Vec<Regex>,
+
+    /// Report suppressions (like `ignoreKinds` entries) which no longer hide any finding.
+    pub report_unused_suppressions: bool,
+
+    /// Include how each used export is imported in the machine-readable reports.
+    pub report_export_usage: bool,
+
+    /// Severities of rules which differ from the default (warn).
+    pub rule_severities: HashMap<Rule, Severity>,
+
+    /// If not empty, only these rules are checked, as if the others were off.
+    pub only_rules: Vec<Rule>,
+
+    /// If not empty, these rules have the error severity and the other rules which are checked have the warn
+    /// severity, overriding `rule_severities`.
+    pub fail_on_rules: Vec<Rule>,
+
+    /// The exit code used when a rule with the error severity has findings.
+    pub findings_exit_code: i32,
+
+    /// Whether modules with a generated code header (`@generated` or `Code generated ... DO NOT EDIT`) are left out
+    /// of the findings.
+    pub detect_generated: bool,
+
+    /// Fail if a module can't be parsed, instead of analyzing the project without it.
+    pub strict: bool,
+
+    /// Findings of rules with the warn severity are treated like errors if there are more of them than this.
+    pub max_warnings: Option<usize>,
+
+    /// Checked between files and analysis phases, so that the analysis can be stopped early.
+    pub cancellation: CancellationToken,
+
+    /// Collects performance metrics if `metrics_path` or `timings` is set, or timings are logged with `-v`.
+    pub metrics: Option<Arc<Metrics>>,
+
+    /// Where the performance metrics are written as JSON at the end of the run.
+    pub metrics_path: Option<PathBuf>,
+
+    /// Prints the performance metrics as JSON to stderr at the end of the run.
+    pub timings: bool,
+
+    /// Log periodic progress while modules are parsed and imports are resolved.
+    pub progress: bool,
+
+    /// How many threads parse modules. All cores are used by default.
+    pub threads: Option<NonZeroUsize>,
+
+    /// The package.json files of the whole tree, shared by the analyses of workspace packages.
+    /// When `None`, package.json is searched for on disk.
+    pub package_jsons: Option<Arc<PackageJsonIndex>>,
+}
+
+impl Config {
+    pub const DEFAULT_RESOLVE_EXTENSIONS: &'static [&'static str] = &["d.ts", "ts", "tsx"];
+
+    pub const DEFAULT_DEV_FILES: &'static [&'static str] = &[
+        "**/*.{test,spec}.{ts,tsx}",
+        "**/__tests__/**",
+        "**/*.stories.{ts,tsx}",
+        "**/*.config.ts",
+    ];
+
+    pub fn default_dev_files() -> Vec<String> {
+        Config::DEFAULT_DEV_FILES
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect()
+    }
+
+    pub fn is_kind_ignored(&self, export_kind: ExportKind, module_kind: ModuleKind) -> bool {
+        self.ignored_kinds
+            .iter()
+            .any(|filter| filter.matches(export_kind, module_kind))
+    }
+
+    pub fn is_export_name_ignored(&self, name: &ExportName) -> bool {
+        if self.ignored_export_names.is_empty() {
+            return false;
+        }
+
+        let name = name.to_string();
+        self.ignored_export_names
+            .iter()
+            .any(|pattern| pattern.is_match(&name))
+    }
+
+    /// A walker for the files under `path`, skipping the files ignored by `.customsignore`, the configured ignore
+    /// files and git, and hidden files unless they're included.
+    pub fn walk_builder(&self, path: &Path) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(path);
+        builder
+            .standard_filters(true)
+            .hidden(!self.include_hidden)
+            .git_ignore(self.respect_gitignore)
+            .git_global(self.respect_gitignore)
+            .git_exclude(self.respect_gitignore)
+            .add_custom_ignore_filename(".customsignore");
+
+        for file_name in &self.ignore_file_names {
+            builder.add_custom_ignore_filename(file_name);
+        }
+
+        builder
+    }
+
+    /// Whether the human-readable report is printed.
+    pub fn reports_text(&self) -> bool {
+        self.outputs
+            .iter()
+            .any(|output| output.format == OutputFormat::Text)
+    }
+
+    /// Writes the path of a finding in the style chosen with `output_paths`. Like the paths of parsed modules,
+    /// the path may either include the root or be relative to it.
+    pub fn output_path(&self, path: &Path) -> PathBuf {
+        if self.output_paths == OutputPaths::Relative {
+            return path.to_owned();
+        }
+
+        let absolute = absolute_path(&self.root, path);
+
+        if self.output_paths == OutputPaths::RepoRoot {
+            let repo_root = absolute
+                .ancestors()
+                .skip(1)
+                .find(|folder| folder.join(".git").exists());
+
+            if let Some(relative) = repo_root.and_then(|root| absolute.strip_prefix(root).ok()) {
+                return relative.to_owned();
+            }
+        }
+
+        absolute
+    }
+
+    /// Whether the number of warnings is above `--max-warnings`.
+    pub fn exceeds_max_warnings(&self, warning_count: usize) -> bool {
+        self.max_warnings
+            .is_some_and(|max_warnings| warning_count > max_warnings)
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        if !self.only_rules.is_empty() && !self.only_rules.contains(&rule) {
+            return Severity::Off;
+        }
+
+        if !self.analyze_target.analyzes_exports() && !rule.is_dependency_rule() {
+            return Severity::Off;
+        }
+
+        let severity = self
+            .rule_severities
+            .get(&rule)
+            .copied()
+            .unwrap_or(Severity::Warn);
+
+        match severity {
+            _ if self.fail_on_rules.contains(&rule) => Severity::Error,
+            Severity::Error if !self.fail_on_rules.is_empty() => Severity::Warn,
+            _ => severity,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            root: Arc::new(PathBuf::new()),
+            outputs: vec![OutputTarget::stdout(OutputFormat::Text)],
+            text_template: None,
+            hyperlinks: HyperlinkStyle::Off,
+            colors: false,
+            output_paths: OutputPaths::Relative,
+            analyze_target: AnalyzeTarget::All,
+            analyze_target_from_cli: false,
+            source_roots: Vec::new(),
+            ignored_folders: Vec::new(),
+            ignore_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            respect_gitignore: true,
+            ignore_file_names: Vec::new(),
+            include_hidden: false,
+            entry_points: Vec::new(),
+            ignore_unreachable_usages: false,
+            dynamic_exports: Vec::new(),
+            asset_folders: Vec::new(),
+            dev_files: Config::default_dev_files(),
+            polyfilled_builtins: Vec::new(),
+            extensions: Vec::new(),
+            resolve_extensions: Config::DEFAULT_RESOLVE_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            compiler_options: ProjectCompilerOptions::default(),
+            analyzed_packages: Vec::new(),
+            defines: Arc::default(),
+            describe_dependencies: false,
+            list_overridden_dependencies: false,
+            max_memory: None,
+            min_age: None,
+            changed_since: None,
+            use_baseline: false,
+            update_baseline: false,
+            ignored_kinds: Vec::new(),
+            ignored_export_names: Vec::new(),
+            report_unused_suppressions: false,
+            report_export_usage: false,
+            rule_severities: HashMap::new(),
+            only_rules: Vec::new(),
+            fail_on_rules: Vec::new(),
+            findings_exit_code: 1,
+            detect_generated: true,
+            strict: false,
+            max_warnings: None,
+            cancellation: CancellationToken::new(),
+            metrics: None,
+            metrics_path: None,
+            timings: false,
+            progress: false,
+            threads: None,
+            package_jsons: None,
+        }
+    }
+}
+
+pub fn parse_define(s: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected define in KEY=VALUE format: {}", s))?;
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Parses a duration like `30d`, `2w` or `12h`.
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    const HOUR: u64 = 60 * 60;
+
+    let split_at = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("Expected a unit (h, d or w) in duration: {}", s))?;
+    let (amount, unit) = s.split_at(split_at);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Expected a number in duration: {}", s))?;
+
+    let unit_seconds = match unit {
+        "h" => HOUR,
+        "d" => 24 * HOUR,
+        "w" => 7 * 24 * HOUR,
+        _ => return Err(anyhow!("Unknown unit in duration: {}", s)),
+    };
+
+    Ok(Duration::from_secs(amount * unit_seconds))
+}
+
+/// Combines the `--format` and `--output` options. A format other than text without its own path is written
+/// to `output`, and without any formats, the format of `output` is inferred from its extension. Defaults to text on stdout.
+pub fn resolve_outputs(
+    formats: Vec<OutputTarget>,
+    output: Option<PathBuf>,
+) -> anyhow::Result<Vec<OutputTarget>> {
+    let mut outputs = match (formats.is_empty(), &output) {
+        (true, Some(path)) => {
+            let format = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("txt") => Ok(OutputFormat::Text),
+                extension => extension.unwrap_or_default().parse::<OutputFormat>(),
+            }
+            .map_err(|_| {
+                anyhow!(
+                    "Can't infer the format of {}, use e.g. --format json",
+                    path.display()
+                )
+            })?;
+
+            vec![OutputTarget::stdout(format)]
+        }
+        (true, None) => vec![OutputTarget::stdout(OutputFormat::Text)],
+        (false, _) => formats,
+    };
+
+    if let Some(output) = output {
+        // A machine-readable format is preferred, so that `--format text,json --output report.json` keeps
+        // printing the text report
+        let without_path = outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, target)| target.path.is_none())
+            .map(|(index, target)| (index, target.format))
+            .collect::<Vec<_>>();
+        let mut machine_readable = without_path
+            .iter()
+            .filter(|(_, format)| *format != OutputFormat::Text);
+
+        let index = match (machine_readable.next(), machine_readable.next()) {
+            (Some((index, _)), None) => *index,
+            (Some(_), Some(_)) => {
+                return Err(anyhow!(
+                    "--output can only be used with a single format without a path"
+                ))
+            }
+            (None, _) => match without_path.first() {
+                Some((index, _)) => *index,
+                None => {
+                    return Err(anyhow!(
+                        "--output requires a format without a path, e.g. --format json"
+                    ))
+                }
+            },
+        };
+
+        outputs[index].path = Some(output);
+    }
+
+    if outputs
+        .iter()
+        .filter(|target| target.path.is_none())
+        .count()
+        > 1
+    {
+        return Err(anyhow!(
+            "Only one format can be printed to stdout, give the others a path, e.g. --format json=report.json"
+        ));
+    }
+
+    Ok(outputs)
+}
+
+/// The components of a path without `.`, since "./src" and "src" are the same folder.
+fn components(path: &Path) -> Vec<Component<'_>> {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Makes the path of an analyzed file absolute, dropping `.` components.
+fn absolute_path(root: &Path, path: &Path) -> PathBuf {
+    let root = components(root).into_iter().collect::<PathBuf>();
+    let path = components(path).into_iter().collect::<PathBuf>();
+
+    let path = if path.is_absolute() || path.starts_with(&root) {
+        path
+    } else {
+        root.join(path)
+    };
+
+    match std::env::current_dir() {
+        Ok(current_dir) if path.is_relative() => current_dir.join(path),
+        _ => path,
+    }
+}
+
+/// Finds the project root for the target folders given on the command line, which is their closest common folder.
+/// With several targets, they are returned as source roots under the project root, so that imports between
+/// them resolve. A single target is the root itself.
+pub fn resolve_source_roots(targets: Vec<PathBuf>) -> anyhow::Result<(PathBuf, Vec<PathBuf>)> {
+    if targets.len() <= 1 {
+        let root = targets
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| PathBuf::from("."));
+        return Ok((root, Vec::new()));
+    }
+
+    if targets.iter().any(|target| target.is_absolute())
+        && !targets.iter().all(|target| target.is_absolute())
+    {
+        return Err(anyhow!(
+            "Target folders must be either all relative or all absolute"
+        ));
+    }
+
+    let mut common = components(&targets[0]);
+
+    for target in &targets[1..] {
+        let shared = common
+            .iter()
+            .zip(components(target))
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        common.truncate(shared);
+    }
+
+    let root = if common.is_empty() {
+        PathBuf::from(".")
+    } else {
+        common.iter().collect()
+    };
+
+    let relative_targets = targets
+        .iter()
+        .map(|target| {
+            components(target)
+                .into_iter()
+                .skip(common.len())
+                .collect::<PathBuf>()
+        })
+        .collect::<Vec<_>>();
+
+    // If one of the targets is the root, everything is analyzed anyway
+    if relative_targets
+        .iter()
+        .any(|target| target.as_os_str().is_empty())
+    {
+        return Ok((root, Vec::new()));
+    }
+
+    let source_roots = relative_targets
+        .into_iter()
+        .map(|target| root.join(target))
+        .collect();
+
+    Ok((root, source_roots))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(format: OutputFormat, path: Option<&str>) -> OutputTarget {
+        OutputTarget {
+            format,
+            path: path.map(PathBuf::from),
+        }
+    }
+
+    fn parse_targets(formats: &[&str]) -> Vec<OutputTarget> {
+        formats
+            .iter()
+            .map(|format| format.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn text_and_files() {
+        let outputs = resolve_outputs(
+            parse_targets(&["text", "json=report.json", "sarif=report.sarif"]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            outputs,
+            vec![
+                target(OutputFormat::Text, None),
+                target(OutputFormat::Json, Some("report.json")),
+                target(OutputFormat::Sarif, Some("report.sarif")),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_path_for_format_without_path() {
+        let outputs = resolve_outputs(
+            parse_targets(&["text", "json"]),
+            Some(PathBuf::from("out.json")),
+        )
+        .unwrap();
+
+        // Text is always printed to stdout
+        assert_eq!(
+            outputs,
+            vec![
+                target(OutputFormat::Text, None),
+                target(OutputFormat::Json, Some("out.json")),
+            ]
+        );
+
+        let outputs = resolve_outputs(
+            parse_targets(&["json", "sarif"]),
+            Some(PathBuf::from("out.json")),
+        );
+        assert!(outputs.is_err());
+    }
+
+    #[test]
+    fn format_inferred_from_output() {
+        let outputs = resolve_outputs(Vec::new(), Some(PathBuf::from("report.json"))).unwrap();
+        assert_eq!(
+            outputs,
+            vec![target(OutputFormat::Json, Some("report.json"))]
+        );
+
+        let outputs = resolve_outputs(Vec::new(), Some(PathBuf::from("report.txt"))).unwrap();
+        assert_eq!(
+            outputs,
+            vec![target(OutputFormat::Text, Some("report.txt"))]
+        );
+
+        assert!(resolve_outputs(Vec::new(), Some(PathBuf::from("report.xml"))).is_err());
+    }
+
+    #[test]
+    fn single_stdout_format() {
+        assert!(resolve_outputs(parse_targets(&["text", "json"]), None).is_err());
+
+        let outputs = resolve_outputs(parse_targets(&["text=report.txt", "json"]), None).unwrap();
+        assert_eq!(
+            outputs,
+            vec![
+                target(OutputFormat::Text, Some("report.txt")),
+                target(OutputFormat::Json, None)
+            ]
+        );
+
+        let outputs = resolve_outputs(
+            parse_targets(&["text", "json=report.json"]),
+            Some(PathBuf::from("report.txt")),
+        )
+        .unwrap();
+        assert_eq!(outputs[0], target(OutputFormat::Text, Some("report.txt")));
+    }
+
+    #[test]
+    fn max_memory_overflow() {
+        assert_eq!(max_memory_bytes(512).unwrap(), 512 * 1024 * 1024);
+        assert!(max_memory_bytes(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn colors_only_in_terminals() {
+        let stdout = [target(OutputFormat::Text, None)];
+        let file = [target(OutputFormat::Text, Some("report.txt"))];
+
+        assert!(use_colors(ColorChoice::Auto, &stdout, false, true));
+        assert!(!use_colors(ColorChoice::Auto, &stdout, true, true));
+        assert!(!use_colors(ColorChoice::Auto, &stdout, false, false));
+        assert!(!use_colors(ColorChoice::Auto, &file, false, true));
+        assert!(use_colors(ColorChoice::Always, &file, true, false));
+        assert!(!use_colors(ColorChoice::Never, &stdout, false, true));
+    }
+
+    #[test]
+    fn combined_analyze_targets() {
+        let parse = |s: &str| s.parse::<AnalyzeTarget>().unwrap();
+
+        assert_eq!(parse("dependencies"), AnalyzeTarget::Dependencies);
+        assert_eq!(parse("types,dependencies"), AnalyzeTarget::Types);
+        assert_eq!(parse("types, values"), AnalyzeTarget::All);
+        assert!("types,classes".parse::<AnalyzeTarget>().is_err());
+
+        let config = Config {
+            analyze_target: AnalyzeTarget::Dependencies,
+            ..Config::default()
+        };
+
+        assert_eq!(config.severity(Rule::UnusedExports), Severity::Off);
+        assert_eq!(config.severity(Rule::UnusedDependencies), Severity::Warn);
+    }
+
+    #[test]
+    fn only_and_fail_on_rules() {
+        let config = Config {
+            rule_severities: [
+                (Rule::UnusedAssets, Severity::Error),
+                (Rule::UnusedDependencies, Severity::Off),
+            ]
+            .into_iter()
+            .collect(),
+            fail_on_rules: vec!["unused-dependencies".parse().unwrap()],
+            ..Config::default()
+        };
+
+        assert_eq!(config.severity(Rule::UnusedDependencies), Severity::Error);
+        assert_eq!(config.severity(Rule::UnusedAssets), Severity::Warn);
+        assert_eq!(config.severity(Rule::UnusedExports), Severity::Warn);
+
+        let config = Config {
+            only_rules: vec![Rule::UnusedDependencies],
+            ..config
+        };
+
+        assert_eq!(config.severity(Rule::UnusedExports), Severity::Off);
+        assert!("unused-things".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn absolute_output_paths() {
+        let config = Config {
+            root: Arc::new(PathBuf::from("/repo/project")),
+            output_paths: OutputPaths::Absolute,
+            ..Config::default()
+        };
+
+        // Module paths include the root, asset paths are relative to it
+        assert_eq!(
+            config.output_path(Path::new("/repo/project/./src/a.ts")),
+            PathBuf::from("/repo/project/src/a.ts")
+        );
+        assert_eq!(
+            config.output_path(Path::new("assets/logo.png")),
+            PathBuf::from("/repo/project/assets/logo.png")
+        );
+
+        let config = Config {
+            output_paths: OutputPaths::Relative,
+            ..config
+        };
+        assert_eq!(
+            config.output_path(Path::new("assets/logo.png")),
+            PathBuf::from("assets/logo.png")
+        );
+    }
+
+    #[test]
+    fn source_roots_under_common_folder() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            resolve_source_roots(paths(&["project"])).unwrap(),
+            (PathBuf::from("project"), Vec::new())
+        );
+        assert_eq!(
+            resolve_source_roots(paths(&["project/src", "./project/scripts"])).unwrap(),
+            (
+                PathBuf::from("project"),
+                paths(&["project/src", "project/scripts"])
+            )
+        );
+        assert_eq!(
+            resolve_source_roots(paths(&["src", "tools"])).unwrap(),
+            (PathBuf::from("."), paths(&["./src", "./tools"]))
+        );
+        assert_eq!(
+            resolve_source_roots(paths(&["project", "project/src"])).unwrap(),
+            (PathBuf::from("project"), Vec::new())
+        );
+        assert!(resolve_source_roots(paths(&["/project/src", "scripts"])).is_err());
+    }
 }
@@ -0,0 +1,305 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use ignore::overrides::Override;
+use regex::Regex;
+
+use crate::{
+    config::Config,
+    customs_config::{
+        build_glob_matcher, build_ignore_overrides, read_customs_config, CustomsConfig,
+    },
+    json_config::{find_and_read_config, JsonConfig},
+    package_json::PackageJson,
+    tsconfig::TsConfig,
+};
+
+/// A problem found in one of the configuration files.
+pub struct ConfigProblem {
+    pub file: PathBuf,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(file: &Path, message: impl Into<String>) -> Self {
+        ConfigProblem {
+            file: file.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Loads customs.json, tsconfig.json and package.json for the analyzed folder and validates them without running the analysis.
+pub fn check_project_config(config: &Config) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+
+    check_customs_config(config, &mut problems);
+    check_tsconfig(config, &mut problems);
+    check_package_json(config, &mut problems);
+
+    problems
+}
+
+fn check_customs_config(config: &Config, problems: &mut Vec<ConfigProblem>) {
+    let config_path = config.root.join(CustomsConfig::file_name());
+
+    let customs_config = match read_customs_config(&config.root) {
+        Ok(Some((_, customs_config))) => customs_config,
+        Ok(None) => return,
+        Err(err) => {
+            problems.push(ConfigProblem::new(&config_path, format!("{:#}", err)));
+            return;
+        }
+    };
+
+    let mut seen_patterns = HashSet::new();
+    let mut valid_patterns = Vec::new();
+
+    for pattern in &customs_config.ignore {
+        if !seen_patterns.insert(pattern) {
+            problems.push(ConfigProblem::new(
+                &config_path,
+                format!("Ignore pattern {} is listed more than once", pattern),
+            ));
+            continue;
+        }
+
+        match build_ignore_overrides(&config.root, std::slice::from_ref(pattern)) {
+            Ok(overrides) => valid_patterns.push((pattern, overrides)),
+            Err(err) => problems.push(ConfigProblem::new(&config_path, format!("{:#}", err))),
+        }
+    }
+
+    // The walk settings of customs.json aren't applied to the config yet
+    let walk_config = Config {
+        root: config.root.clone(),
+        respect_gitignore: customs_config.respect_gitignore.unwrap_or(true),
+        ignore_file_names: customs_config.ignore_files.clone(),
+        include_hidden: customs_config.include_hidden.unwrap_or(false),
+        ..Config::default()
+    };
+    let files = walk_project_files(&walk_config);
+
+    let glob_settings = [
+        ("Include", &customs_config.include),
+        ("Entry point", &customs_config.entry_points),
+        ("Dynamic exports", &customs_config.dynamic_exports),
+    ];
+
+    for (setting, patterns) in glob_settings {
+        for pattern in patterns {
+            match build_glob_matcher(&config.root, std::slice::from_ref(pattern)) {
+                Ok(matcher) => {
+                    if !files
+                        .iter()
+                        .any(|file| matcher.matched(file, false).is_whitelist())
+                    {
+                        problems.push(ConfigProblem::new(
+                            &config_path,
+                            format!("{} pattern {} does not match any files", setting, pattern),
+                        ));
+                    }
+                }
+                Err(err) => problems.push(ConfigProblem::new(&config_path, format!("{:#}", err))),
+            }
+        }
+    }
+
+    for pattern in &customs_config.ignore_exports {
+        if let Err(err) = Regex::new(pattern) {
+            problems.push(ConfigProblem::new(
+                &config_path,
+                format!("Invalid ignoreExports pattern {}: {}", pattern, err),
+            ));
+        }
+    }
+
+    if let Err(err) = customs_config.outputs(&config.root) {
+        problems.push(ConfigProblem::new(&config_path, format!("{:#}", err)));
+    }
+
+    for folder in &customs_config.asset_folders {
+        if !config.root.join(folder).is_dir() {
+            problems.push(ConfigProblem::new(
+                &config_path,
+                format!("Asset folder {} does not exist", folder),
+            ));
+        }
+    }
+
+    let covered_files = valid_patterns
+        .iter()
+        .map(|(_, overrides)| {
+            files
+                .iter()
+                .filter(|file| is_covered_by(&config.root, file, overrides))
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for (i, (pattern, _)) in valid_patterns.iter().enumerate() {
+        if covered_files[i].is_empty() {
+            problems.push(ConfigProblem::new(
+                &config_path,
+                format!("Ignore pattern {} does not match any files", pattern),
+            ));
+            continue;
+        }
+
+        // When two patterns match exactly the same files, only the latter one is reported
+        let overlapping_pattern = valid_patterns.iter().enumerate().find(|(j, _)| {
+            *j != i
+                && covered_files[i].is_subset(&covered_files[*j])
+                && (covered_files[i] != covered_files[*j] || *j < i)
+        });
+
+        if let Some((_, (other_pattern, _))) = overlapping_pattern {
+            problems.push(ConfigProblem::new(
+                &config_path,
+                format!(
+                    "Ignore pattern {} only matches files already ignored by {}",
+                    pattern, other_pattern
+                ),
+            ));
+        }
+    }
+}
+
+/// Lists all files under the root which would be analyzed without any ignore patterns.
+fn walk_project_files(config: &Config) -> Vec<PathBuf> {
+    config
+        .walk_builder(&config.root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Checks whether the file or one of its parent folders (under the root) is ignored.
+fn is_covered_by(root: &Path, file: &Path, overrides: &Override) -> bool {
+    if overrides.matched(file, false).is_ignore() {
+        return true;
+    }
+
+    file.ancestors()
+        .skip(1)
+        .take_while(|folder| folder.starts_with(root) && *folder != root)
+        .any(|folder| overrides.matched(folder, true).is_ignore())
+}
+
+fn check_tsconfig(config: &Config, problems: &mut Vec<ConfigProblem>) {
+    let (tsconfig_path, tsconfig) = match find_and_read_config::<TsConfig>(&config.root) {
+        Ok(Some(tsconfig)) => tsconfig,
+        Ok(None) => return,
+        Err(err) => {
+            let path = config.root.join(TsConfig::file_name());
+            problems.push(ConfigProblem::new(&path, format!("{:#}", err)));
+            return;
+        }
+    };
+
+    for type_root in tsconfig.type_roots(&tsconfig_path) {
+        if !type_root.exists() {
+            problems.push(ConfigProblem::new(
+                &tsconfig_path,
+                format!("Type root {} does not exist", type_root.display()),
+            ));
+        }
+    }
+
+    if let Some(base_url) = tsconfig.base_url(&tsconfig_path) {
+        if !base_url.is_dir() {
+            problems.push(ConfigProblem::new(
+                &tsconfig_path,
+                format!("Base URL {} is not a folder", base_url.display()),
+            ));
+        }
+    }
+
+    for (alias, targets) in tsconfig.path_aliases(&tsconfig_path) {
+        for target in targets {
+            if !alias_target_exists(&target, config) {
+                problems.push(ConfigProblem::new(
+                    &tsconfig_path,
+                    format!(
+                        "Target {} of path alias {} does not exist",
+                        target.display(),
+                        alias
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+fn alias_target_exists(target: &Path, config: &Config) -> bool {
+    let target_str = target.to_string_lossy();
+
+    // For wildcard aliases like src/components/*, the folder containing the wildcard must exist
+    if let Some(prefix) = target_str.strip_suffix('*') {
+        let prefix = Path::new(prefix);
+        return prefix.is_dir() || prefix.parent().is_some_and(Path::is_dir);
+    }
+
+    target.exists()
+        || config.resolve_extensions.iter().any(|ext| {
+            let mut with_ext = target.as_os_str().to_owned();
+            with_ext.push(".");
+            with_ext.push(ext);
+            Path::new(&with_ext).is_file()
+        })
+}
+
+fn check_package_json(config: &Config, problems: &mut Vec<ConfigProblem>) {
+    let (package_json_path, package_json) = match find_and_read_config::<PackageJson>(&config.root)
+    {
+        Ok(Some(package_json)) => package_json,
+        Ok(None) => {
+            let path = config.root.join(PackageJson::file_name());
+            problems.push(ConfigProblem::new(
+                &path,
+                "No package.json found, dependency analysis will be skipped",
+            ));
+            return;
+        }
+        Err(err) => {
+            let path = config.root.join(PackageJson::file_name());
+            problems.push(ConfigProblem::new(&path, format!("{:#}", err)));
+            return;
+        }
+    };
+
+    let mut duplicated = package_json
+        .dependencies
+        .keys()
+        .filter(|name| package_json.dev_dependencies.contains_key(*name))
+        .collect::<Vec<_>>();
+    duplicated.sort_unstable();
+
+    for name in package_json.bundled_dependency_names() {
+        if !package_json.dependencies.contains_key(name)
+            && !package_json.optional_dependencies.contains_key(name)
+        {
+            problems.push(ConfigProblem::new(
+                &package_json_path,
+                format!(
+                    "Bundled dependency {} is not listed in dependencies or optionalDependencies",
+                    name
+                ),
+            ));
+        }
+    }
+
+    for name in duplicated {
+        problems.push(ConfigProblem::new(
+            &package_json_path,
+            format!(
+                "{} is listed in both dependencies and devDependencies",
+                name
+            ),
+        ));
+    }
+}
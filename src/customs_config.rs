@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use ignore::overrides::{Override, OverrideBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{resolve_outputs, AnalyzeTarget, Config, KindFilter, OutputTarget, Rule, Severity},
+    json_config::{read_config, JsonConfig},
+};
+
+/// Project specific settings, read from customs.json in the analyzed folder.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CustomsConfig {
+    /// Gitignore-style glob patterns (relative to the analyzed folder) for files and folders which are not analyzed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Glob patterns (relative to the analyzed folder) for the files which are analyzed, e.g. `src/**`.
+    /// If empty, every file which isn't ignored is analyzed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Whether files ignored by git are skipped. Defaults to true; turn it off for projects which keep sources
+    /// in folders excluded from git, e.g. generated code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_gitignore: Option<bool>,
+    /// Names of gitignore-style files which are read in addition to `.customsignore`, e.g. `.eslintignore`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_files: Vec<String>,
+    /// Whether hidden files and folders are searched for modules. Defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_hidden: Option<bool>,
+    /// Glob patterns for modules which are used from outside the project, like application entry points,
+    /// framework pages and tests. Their exports are never reported as unused.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_points: Vec<String>,
+    /// Glob patterns for modules whose exports are partly created at runtime. Importing names they don't declare
+    /// is not reported. A `customs-dynamic-exports` comment in the module does the same.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_exports: Vec<String>,
+    /// Export and module kind combinations which are never reported, e.g. `{ "exportKind": "enum" }`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_kinds: Vec<KindFilter>,
+    /// Regular expressions for export names which are never reported as unused, e.g. `^use[A-Z]` or `Props$`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_exports: Vec<String>,
+    /// Folders containing images, stylesheets and other assets. Files in them which no module imports are reported.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asset_folders: Vec<String>,
+    /// Glob patterns for tests, stories and other modules which only need devDependencies.
+    /// Replaces the defaults, which cover test, spec, story and config files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dev_files: Vec<String>,
+    /// Packages installed as browser polyfills of Node builtin modules, e.g. `buffer` or `process`.
+    /// Imports of them refer to the package even if it's not declared in package.json.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub polyfilled_builtins: Vec<String>,
+    /// The analyze target, unless one is given with `--analyze` or set by the selected profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyze: Option<AnalyzeTarget>,
+    /// Report formats in the syntax of `--format`, e.g. `json=customs-report.json`. Paths are relative to the
+    /// analyzed folder. Only used when neither `--format` nor `--output` is given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub format: Vec<String>,
+    /// Named sets of settings, selected with `--profile`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Settings for running the analysis in a specific context, e.g. `ci` or `cleanup`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Profile {
+    pub analyze: Option<AnalyzeTarget>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rules: HashMap<Rule, Severity>,
+    /// Ignore patterns in addition to the top level ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Entry point patterns in addition to the top level ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_points: Vec<String>,
+    /// Ignored kinds in addition to the top level ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_kinds: Vec<KindFilter>,
+}
+
+impl Profile {
+    pub fn apply_to(&self, config: &mut Config) {
+        if let Some(analyze_target) = self.analyze {
+            config.analyze_target = analyze_target;
+        }
+
+        config
+            .rule_severities
+            .extend(self.rules.iter().map(|(k, v)| (*k, *v)));
+        config.ignore_patterns.extend(self.ignore.iter().cloned());
+        config
+            .entry_points
+            .extend(self.entry_points.iter().cloned());
+        config
+            .ignored_kinds
+            .extend(self.ignore_kinds.iter().copied());
+    }
+}
+
+impl CustomsConfig {
+    /// Parses the configured report formats, or returns `None` if there are none.
+    pub fn outputs(&self, root: &Path) -> anyhow::Result<Option<Vec<OutputTarget>>> {
+        if self.format.is_empty() {
+            return Ok(None);
+        }
+
+        let targets = self
+            .format
+            .iter()
+            .map(|format| {
+                let mut target = format.parse::<OutputTarget>()?;
+                target.path = target.path.map(|path| root.join(path));
+                Ok(target)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        resolve_outputs(targets, None).map(Some)
+    }
+
+    pub fn profile(&self, name: &str) -> anyhow::Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let mut names = self.profiles.keys().map(String::as_str).collect::<Vec<_>>();
+            names.sort_unstable();
+
+            anyhow!(
+                "Unknown profile {}. Available profiles: {}",
+                name,
+                if names.is_empty() {
+                    String::from("none")
+                } else {
+                    names.join(", ")
+                }
+            )
+        })
+    }
+}
+
+impl JsonConfig for CustomsConfig {
+    fn file_name() -> &'static str {
+        "customs.json"
+    }
+}
+
+/// Unlike tsconfig.json and package.json, the config is not searched from parent folders,
+/// because the ignore patterns are relative to the analyzed folder.
+pub fn read_customs_config(root: &Path) -> anyhow::Result<Option<(PathBuf, CustomsConfig)>> {
+    let path = root.join(CustomsConfig::file_name());
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let config = read_config(&path)?;
+    Ok(Some((path, config)))
+}
+
+/// Builds a matcher for module paths, where matching paths are whitelisted.
+pub fn build_glob_matcher(root: &Path, patterns: &[String]) -> anyhow::Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+
+    for pattern in patterns {
+        builder
+            .add(pattern)
+            .with_context(|| format!("Invalid glob pattern: {}", pattern))?;
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Builds a walker override which excludes every path matching one of the ignore patterns.
+pub fn build_ignore_overrides(root: &Path, patterns: &[String]) -> anyhow::Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+
+    for pattern in patterns {
+        // Override globs are whitelists by default, ! turns them into ignores
+        builder
+            .add(&format!("!{}", pattern))
+            .with_context(|| format!("Invalid ignore pattern: {}", pattern))?;
+    }
+
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OutputFormat;
+
+    #[test]
+    fn apply_profile() {
+        let customs_config: CustomsConfig = serde_json::from_str(
+            r#"{
+                "ignore": ["generated/"],
+                "profiles": {
+                    "ci": {
+                        "analyze": "values",
+                        "rules": { "unusedExports": "error", "unusedTypeParameters": "off" },
+                        "ignore": ["stories/"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        customs_config.profile("ci").unwrap().apply_to(&mut config);
+
+        assert_eq!(config.analyze_target, AnalyzeTarget::Values);
+        assert_eq!(config.severity(Rule::UnusedExports), Severity::Error);
+        assert_eq!(config.severity(Rule::UnusedDependencies), Severity::Warn);
+        assert_eq!(config.severity(Rule::UnusedTypeParameters), Severity::Off);
+        assert_eq!(config.ignore_patterns, vec![String::from("stories/")]);
+
+        assert!(customs_config.profile("strict").is_err());
+    }
+
+    #[test]
+    fn configured_outputs() {
+        let parse = |json| serde_json::from_str::<CustomsConfig>(json).unwrap();
+        let root = Path::new("project");
+
+        assert!(parse("{}").outputs(root).unwrap().is_none());
+
+        let outputs = parse(r#"{ "format": ["text", "json=report.json"] }"#)
+            .outputs(root)
+            .unwrap()
+            .unwrap();
+        assert_eq!(outputs[0].format, OutputFormat::Text);
+        assert_eq!(outputs[0].path, None);
+        assert_eq!(outputs[1].format, OutputFormat::Json);
+        assert_eq!(outputs[1].path, Some(root.join("report.json")));
+
+        assert!(parse(r#"{ "format": ["json", "sarif"] }"#)
+            .outputs(root)
+            .is_err());
+    }
+}
@@ -1,17 +1,27 @@
 use std::{
-    cell::Cell,
     collections::{HashMap, HashSet},
     fmt::Display,
+    mem::size_of,
     ops::Deref,
     path::{Path, PathBuf},
-    sync::Arc,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc,
+    },
 };
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use relative_path::RelativePath;
+use serde::{Deserialize, Serialize};
 use swc_atoms::JsWord;
+use swc_common::{Span, DUMMY_SP};
 
-use crate::config::AnalyzeTarget;
+use crate::{
+    config::{AnalyzeTarget, Config},
+    json_config::{read_config, JsonConfig},
+    package_json::PackageJson,
+};
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone)]
 pub struct NormalizedModulePath(PathBuf);
@@ -42,6 +52,17 @@ impl ExportName {
     }
 }
 
+impl FromStr for ExportName {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(ExportName::Default),
+            _ => Ok(ExportName::named(s)),
+        }
+    }
+}
+
 impl Display for ExportName {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -51,7 +72,7 @@ impl Display for ExportName {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ModuleSourceAndLine {
     path: Arc<PathBuf>,
     zero_based_line: usize,
@@ -85,38 +106,193 @@ impl Display for ModuleSourceAndLine {
     }
 }
 
+/// Index of an export within its module.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct ExportId(usize);
+
+impl ExportId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
 #[derive(Debug)]
 pub struct Export {
-    pub usage: Cell<Usage>,
+    pub name: ExportName,
+    pub usage: AtomicUsage,
     pub kind: ExportKind,
     pub visibility: Visibility,
     pub location: ModuleSourceAndLine,
+    /// The import source and imported name, if this export is a re-export from another module.
+    /// Exported imports like `import { a as b } from './a'; export { b as c }` are re-exports as well.
+    pub reexported_from: Option<(String, ImportName)>,
+    /// The module and name `reexported_from` resolves to, if it's an analyzed module.
+    pub forwards_to: Option<(NormalizedModulePath, ImportName)>,
+    /// The number of lines in the exported statement, for estimating how much code could be deleted.
+    pub line_count: usize,
+    /// The statement which declares the export, shared by all bindings it exports.
+    pub statement: ModuleSourceAndLine,
+    /// The span of the statement, which tells apart statements on the same line.
+    pub statement_span: Span,
+    /// Declared with `declare`, so the value is defined outside the module and not finding any usages doesn't mean
+    /// the export could be removed.
+    pub is_ambient: bool,
 }
 
 impl Export {
-    pub fn new(kind: ExportKind, visibility: Visibility, location: ModuleSourceAndLine) -> Self {
+    pub fn new(
+        name: ExportName,
+        kind: ExportKind,
+        visibility: Visibility,
+        location: ModuleSourceAndLine,
+    ) -> Self {
         Export {
-            usage: Cell::default(),
+            name,
+            usage: AtomicUsage::default(),
             kind,
             visibility,
+            statement: location.clone(),
+            statement_span: DUMMY_SP,
             location,
+            reexported_from: None,
+            forwards_to: None,
+            line_count: 1,
+            is_ambient: false,
         }
     }
 
     pub fn is_used(&self) -> bool {
         self.usage.get().is_used()
     }
+
+    /// The zero-based line of the `customs-ignore` comment which suppresses the export, if any. A comment
+    /// suppresses the exports of the statement on the same line or on the next line.
+    pub fn ignore_comment(&self, ignore_comments: &[usize]) -> Option<usize> {
+        let statement_line = self.statement.line() - 1;
+
+        ignore_comments
+            .iter()
+            .copied()
+            .find(|&comment| comment == statement_line || comment + 1 == statement_line)
+    }
+}
+
+/// How an imported name is used by the importing module.
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+pub enum ImportKind {
+    /// Imported and referenced as a value.
+    Value,
+    /// Imported with `import type`, or not referenced as a value, so the import is erased at runtime.
+    TypeOnly,
+    /// Re-exported, e.g. `export { a } from './a'`.
+    Reexport,
+    /// Accessed as a member of an imported namespace, e.g. `Api.get()`.
+    NamespaceMember,
+}
+
+/// The set of ways a name is imported.
+#[derive(PartialEq, Eq, Hash, Debug, Default, Copy, Clone)]
+pub struct ImportKinds {
+    pub value: bool,
+    pub type_only: bool,
+    pub reexport: bool,
+    pub namespace_member: bool,
+}
+
+impl ImportKinds {
+    pub fn insert(&mut self, kind: ImportKind) {
+        match kind {
+            ImportKind::Value => self.value = true,
+            ImportKind::TypeOnly => self.type_only = true,
+            ImportKind::Reexport => self.reexport = true,
+            ImportKind::NamespaceMember => self.namespace_member = true,
+        }
+    }
+
+    /// Whether the name is only used as a type, so it isn't needed at runtime.
+    pub fn is_type_only(self) -> bool {
+        self.type_only && !self.value && !self.reexport && !self.namespace_member
+    }
+}
+
+impl From<ImportKind> for ImportKinds {
+    fn from(kind: ImportKind) -> Self {
+        let mut kinds = ImportKinds::default();
+        kinds.insert(kind);
+        kinds
+    }
 }
 
 #[derive(PartialEq, Eq, Hash, Debug, Default, Copy, Clone)]
 pub struct Usage {
     pub used_locally: bool,
     pub used_externally: bool,
+    /// The module uses `eval` or `with`, so the export could be referenced in ways which can't be analyzed.
+    pub used_dynamically: bool,
+    /// How other modules import the export. Empty for exports which are only used by entry points.
+    pub external_kinds: ImportKinds,
 }
 
 impl Usage {
     pub fn is_used(self) -> bool {
-        self.used_locally || self.used_externally
+        self.used_locally || self.used_externally || self.used_dynamically
+    }
+}
+
+/// Thread-safe usage flags of an export, which allows marking exports as used through a shared reference.
+#[derive(Debug, Default)]
+pub struct AtomicUsage(AtomicU8);
+
+impl AtomicUsage {
+    const USED_LOCALLY: u8 = 1;
+    const USED_EXTERNALLY: u8 = 1 << 1;
+    const USED_DYNAMICALLY: u8 = 1 << 2;
+    const IMPORTED_AS_VALUE: u8 = 1 << 3;
+    const IMPORTED_AS_TYPE: u8 = 1 << 4;
+    const REEXPORTED: u8 = 1 << 5;
+    const ACCESSED_AS_MEMBER: u8 = 1 << 6;
+
+    pub fn get(&self) -> Usage {
+        let flags = self.0.load(Ordering::Relaxed);
+
+        Usage {
+            used_locally: flags & Self::USED_LOCALLY != 0,
+            used_externally: flags & Self::USED_EXTERNALLY != 0,
+            used_dynamically: flags & Self::USED_DYNAMICALLY != 0,
+            external_kinds: ImportKinds {
+                value: flags & Self::IMPORTED_AS_VALUE != 0,
+                type_only: flags & Self::IMPORTED_AS_TYPE != 0,
+                reexport: flags & Self::REEXPORTED != 0,
+                namespace_member: flags & Self::ACCESSED_AS_MEMBER != 0,
+            },
+        }
+    }
+
+    pub fn mark_used_locally(&self) {
+        self.0.fetch_or(Self::USED_LOCALLY, Ordering::Relaxed);
+    }
+
+    pub fn mark_used_externally(&self) {
+        self.0.fetch_or(Self::USED_EXTERNALLY, Ordering::Relaxed);
+    }
+
+    /// Marks the export used by another module, which imports it in the given ways.
+    pub fn mark_imported(&self, kinds: ImportKinds) {
+        let flags = [
+            (kinds.value, Self::IMPORTED_AS_VALUE),
+            (kinds.type_only, Self::IMPORTED_AS_TYPE),
+            (kinds.reexport, Self::REEXPORTED),
+            (kinds.namespace_member, Self::ACCESSED_AS_MEMBER),
+        ]
+        .iter()
+        .filter(|(is_set, _)| *is_set)
+        .fold(Self::USED_EXTERNALLY, |flags, (_, flag)| flags | flag);
+
+        self.0.fetch_or(flags, Ordering::Relaxed);
+    }
+
+    pub fn mark_used_dynamically(&self) {
+        self.0.fetch_or(Self::USED_DYNAMICALLY, Ordering::Relaxed);
     }
 }
 
@@ -125,6 +301,8 @@ pub enum ImportName {
     Named(JsWord),
     Default,
     Wildcard,
+    /// A named import which is only used for accessing a member, e.g. `Api.get()` after `import { Api } from './api'`.
+    Member(JsWord, JsWord),
 }
 
 impl ImportName {
@@ -133,6 +311,39 @@ impl ImportName {
     }
 }
 
+/// The export name of a member of an exported namespace, e.g. `Api.get`.
+pub fn namespace_member_name(namespace: &str, member: &str) -> ExportName {
+    ExportName::Named(JsWord::from(format!("{}.{}", namespace, member)))
+}
+
+impl Display for ImportName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImportName::Named(name) => write!(f, "{}", name),
+            ImportName::Default => write!(f, "default"),
+            ImportName::Wildcard => write!(f, "*"),
+            ImportName::Member(namespace, member) => write!(f, "{}.{}", namespace, member),
+        }
+    }
+}
+
+/// A type parameter of an exported generic declaration which is never referenced in the declaration.
+#[derive(Debug, Clone)]
+pub struct UnusedTypeParameter {
+    pub declaration: JsWord,
+    pub name: JsWord,
+    pub location: ModuleSourceAndLine,
+}
+
+/// An import or re-export of a package, e.g. `import type { Foo } from 'foo'`.
+#[derive(Debug, Clone)]
+pub struct PackageUsage {
+    pub package: String,
+    /// Whether the imported names are only used as types, so the package isn't needed at runtime.
+    pub type_only: bool,
+    pub location: ModuleSourceAndLine,
+}
+
 pub struct ModulePath {
     pub root: Arc<PathBuf>,
     pub root_relative: Arc<PathBuf>,
@@ -142,10 +353,33 @@ pub struct ModulePath {
 pub struct Module {
     pub path: ModulePath,
     pub kind: ModuleKind,
-    pub exports: HashMap<ExportName, Export>,
+    /// Exports indexed by `ExportId`.
+    pub exports: Vec<Export>,
+    export_ids: HashMap<ExportName, ExportId>,
     pub imported_modules: HashMap<NormalizedModulePath, Vec<ImportName>>,
+    /// How each name in `imported_modules` is used. Names without an entry are treated as value imports.
+    pub import_kinds: HashMap<NormalizedModulePath, HashMap<ImportName, ImportKinds>>,
     pub imported_packages: HashSet<String>,
-    is_wildcard_imported: Cell<bool>,
+    /// Names imported from packages, keyed by the full import source (e.g. `lodash/fp`).
+    pub package_imports: HashMap<String, Vec<ImportName>>,
+    /// Every statement which imports or re-exports a package.
+    pub package_usages: Vec<PackageUsage>,
+    /// Local files which are imported but not analyzed, e.g. files outside the root or stylesheets.
+    pub imported_external_modules: HashSet<PathBuf>,
+    pub unresolved_imports: HashSet<PathBuf>,
+    pub unused_type_parameters: Vec<UnusedTypeParameter>,
+    /// Whether some exports are created at runtime, so importing names the module doesn't declare is expected.
+    pub has_dynamic_exports: bool,
+    /// Whether the module has a generated code header, like `@generated`. Its imports are resolved as usual,
+    /// but it has no findings of its own.
+    pub is_generated: bool,
+    /// Whether the module consists only of named re-exports, like `export { a } from './a'`.
+    pub only_reexports: bool,
+    /// The number of lines in the module, used for estimating how much code could be deleted.
+    pub line_count: usize,
+    /// Zero-based lines of the `customs-ignore` comments in the module.
+    pub ignore_comments: Vec<usize>,
+    is_wildcard_imported: AtomicBool,
 }
 
 impl Module {
@@ -153,36 +387,163 @@ impl Module {
         Module {
             path,
             kind,
-            exports: HashMap::new(),
+            exports: Vec::new(),
+            export_ids: HashMap::new(),
             imported_modules: HashMap::new(),
+            import_kinds: HashMap::new(),
             imported_packages: HashSet::new(),
-            is_wildcard_imported: Cell::default(),
+            package_imports: HashMap::new(),
+            package_usages: Vec::new(),
+            imported_external_modules: HashSet::new(),
+            unresolved_imports: HashSet::new(),
+            unused_type_parameters: Vec::new(),
+            has_dynamic_exports: false,
+            is_generated: false,
+            only_reexports: false,
+            line_count: 0,
+            ignore_comments: Vec::new(),
+            is_wildcard_imported: AtomicBool::default(),
         }
     }
 
+    /// How the module uses a name it imports from another module.
+    pub fn import_kinds(
+        &self,
+        module_path: &NormalizedModulePath,
+        name: &ImportName,
+    ) -> ImportKinds {
+        self.import_kinds
+            .get(module_path)
+            .and_then(|kinds| kinds.get(name))
+            .copied()
+            .unwrap_or_else(|| ImportKind::Value.into())
+    }
+
     pub fn is_wildcard_imported(&self) -> bool {
-        self.is_wildcard_imported.get()
+        self.is_wildcard_imported.load(Ordering::Relaxed)
     }
 
     pub fn mark_wildcard_imported(&self) {
-        self.is_wildcard_imported.set(true)
+        self.is_wildcard_imported.store(true, Ordering::Relaxed)
+    }
+
+    /// Adds an export to the module. An existing export with the same name is replaced, but keeps its id.
+    pub fn add_export(&mut self, export: Export) -> ExportId {
+        match self.export_ids.get(&export.name) {
+            Some(&id) => {
+                self.exports[id.0] = export;
+                id
+            }
+            None => {
+                let id = ExportId(self.exports.len());
+                self.export_ids.insert(export.name.clone(), id);
+                self.exports.push(export);
+                id
+            }
+        }
+    }
+
+    pub fn export_id(&self, name: &ExportName) -> Option<ExportId> {
+        self.export_ids.get(name).copied()
+    }
+
+    pub fn export(&self, id: ExportId) -> &Export {
+        &self.exports[id.0]
+    }
+
+    pub fn get_export(&self, name: &ExportName) -> Option<&Export> {
+        self.export_id(name).map(|id| self.export(id))
     }
 
-    pub fn add_export(&mut self, name: ExportName, export: Export) {
-        self.exports.insert(name, export);
+    /// The exported members of an exported namespace, including members of nested namespaces.
+    pub fn namespace_members<'a>(
+        &'a self,
+        namespace: &ExportName,
+    ) -> impl Iterator<Item = &'a Export> + 'a {
+        let prefix = match namespace {
+            ExportName::Named(name) => Some(format!("{}.", name)),
+            ExportName::Default => None,
+        };
+
+        self.exports
+            .iter()
+            .filter(move |export| match (&export.name, &prefix) {
+                (ExportName::Named(name), Some(prefix)) => name.starts_with(prefix.as_str()),
+                _ => false,
+            })
+    }
+
+    /// A rough estimate of the memory retained by the module, used for enforcing the memory budget.
+    /// Interned strings are not counted, since they are shared between modules.
+    pub fn approximate_size(&self) -> usize {
+        let exports = self.exports.capacity() * size_of::<Export>()
+            + self.export_ids.capacity() * size_of::<(ExportName, ExportId)>();
+
+        let imports = self
+            .imported_modules
+            .iter()
+            .map(|(path, names)| {
+                size_of::<(NormalizedModulePath, Vec<ImportName>)>()
+                    + path.as_os_str().len()
+                    + names.capacity() * size_of::<ImportName>()
+            })
+            .sum::<usize>()
+            + self
+                .import_kinds
+                .values()
+                .map(|kinds| {
+                    size_of::<(NormalizedModulePath, HashMap<ImportName, ImportKinds>)>()
+                        + kinds.capacity() * size_of::<(ImportName, ImportKinds)>()
+                })
+                .sum::<usize>();
+
+        let packages = self
+            .imported_packages
+            .iter()
+            .map(|package| size_of::<String>() + package.capacity())
+            .sum::<usize>()
+            + self
+                .package_imports
+                .iter()
+                .map(|(source, names)| {
+                    size_of::<(String, Vec<ImportName>)>()
+                        + source.capacity()
+                        + names.capacity() * size_of::<ImportName>()
+                })
+                .sum::<usize>()
+            + self
+                .package_usages
+                .iter()
+                .map(|usage| size_of::<PackageUsage>() + usage.package.capacity())
+                .sum::<usize>();
+
+        let paths = self
+            .imported_external_modules
+            .iter()
+            .chain(self.unresolved_imports.iter())
+            .map(|path| size_of::<PathBuf>() + path.as_os_str().len())
+            .sum::<usize>();
+
+        size_of::<Module>()
+            + exports
+            + imports
+            + packages
+            + paths
+            + self.unused_type_parameters.capacity() * size_of::<UnusedTypeParameter>()
     }
 
     pub fn imports_mut(&mut self, module_path: NormalizedModulePath) -> &mut Vec<ImportName> {
-        self.imported_modules
-            .entry(module_path)
-            .or_insert_with(Vec::new)
+        self.imported_modules.entry(module_path).or_default()
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ModuleKind {
+    #[serde(rename = "ts")]
     TS,
+    #[serde(rename = "tsx")]
     TSX,
+    #[serde(rename = "d.ts", alias = "dts")]
     DTS,
 }
 
@@ -192,7 +553,21 @@ impl ModuleKind {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+impl FromStr for ModuleKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ts" => Ok(Self::TS),
+            "tsx" => Ok(Self::TSX),
+            "d.ts" | "dts" => Ok(Self::DTS),
+            _ => Err(anyhow!("Unknown module kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ExportKind {
     Type,
     Value,
@@ -203,13 +578,14 @@ pub enum ExportKind {
 
 impl ExportKind {
     pub fn matches_analyze_target(self, target: AnalyzeTarget) -> bool {
-        matches!(
-            (self, target),
-            (_, AnalyzeTarget::All)
-                | (ExportKind::Class | ExportKind::Enum, _)
-                | (ExportKind::Type, AnalyzeTarget::Types)
-                | (ExportKind::Value, AnalyzeTarget::Values)
-        )
+        target.analyzes_exports()
+            && matches!(
+                (self, target),
+                (_, AnalyzeTarget::All)
+                    | (ExportKind::Class | ExportKind::Enum, _)
+                    | (ExportKind::Type, AnalyzeTarget::Types)
+                    | (ExportKind::Value, AnalyzeTarget::Values)
+            )
     }
 }
 
@@ -251,29 +627,484 @@ pub fn normalize_module_path(
 }
 
 pub enum NormalizedImportSource {
+    /// A module under the project root.
     Local(NormalizedModulePath),
+    /// A file which exists, but won't be analyzed because it's outside the project root or not a module.
+    ExternalLocal(PathBuf),
+    /// A relative import which doesn't point to any file.
+    Unresolved(PathBuf),
+    /// A file imported with a query or hash suffix, like Vite's `?raw` or `?url`.
+    /// The file is used as an asset rather than as a module, so none of its exports are used.
+    AssetVariant(PathBuf),
     Global(String),
 }
 
+fn with_appended_extension(path: &Path, extension: &str) -> PathBuf {
+    // Path::with_extension would replace everything after the last dot, which breaks imports like ./foo.styles
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Human readable log of the steps taken while resolving an import, used by `customs resolve`.
+pub type ResolutionTrace = Vec<String>;
+
+fn trace_step(trace: &mut Option<&mut ResolutionTrace>, step: impl FnOnce() -> String) {
+    if let Some(trace) = trace {
+        trace.push(step());
+    }
+}
+
+fn probe_extensions(
+    path: &Path,
+    extensions: &[&str],
+    trace: &mut Option<&mut ResolutionTrace>,
+) -> Option<PathBuf> {
+    extensions
+        .iter()
+        .map(|ext| with_appended_extension(path, ext))
+        .find(|with_ext| {
+            let found = with_ext.is_file();
+
+            trace_step(trace, || {
+                format!(
+                    "probe {}: {}",
+                    with_ext.display(),
+                    if found { "found" } else { "not found" }
+                )
+            });
+
+            found
+        })
+}
+
+/// The TypeScript extensions an import with a JavaScript extension refers to, like `./a.js` in ESM projects,
+/// where the import names the compiled file.
+const TS_EXTENSIONS_OF_JS: &[(&str, &[&str])] = &[
+    ("js", &["ts", "tsx", "d.ts"]),
+    ("jsx", &["tsx"]),
+    ("mjs", &["mts", "d.mts"]),
+    ("cjs", &["cts", "d.cts"]),
+];
+
+/// Resolves an import which already has a module extension, e.g. `./a.js` or `./a.ts`. A JavaScript extension
+/// is first mapped to the matching TypeScript extensions, and then the path itself is tried.
+fn probe_module_extension(
+    path: &Path,
+    extensions: &[&str],
+    trace: &mut Option<&mut ResolutionTrace>,
+) -> Option<PathBuf> {
+    let extension = path.extension()?.to_str()?;
+
+    if let Some((_, ts_extensions)) = TS_EXTENSIONS_OF_JS
+        .iter()
+        .find(|(js_extension, _)| *js_extension == extension)
+    {
+        if let Some(found) = probe_extensions(&path.with_extension(""), ts_extensions, trace) {
+            return Some(found);
+        }
+    }
+
+    if !has_any_extension(path, extensions) {
+        return None;
+    }
+
+    let found = path.is_file();
+
+    trace_step(trace, || {
+        format!(
+            "probe {}: {}",
+            path.display(),
+            if found { "found" } else { "not found" }
+        )
+    });
+
+    found.then(|| path.to_owned())
+}
+
+fn has_any_extension(path: &Path, extensions: &[&str]) -> bool {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return false,
+    };
+
+    extensions
+        .iter()
+        .any(|ext| file_name.ends_with(&format!(".{}", ext)))
+}
+
+/// Resolves the entry point of a directory which contains its own package.json, as is common in component libraries.
+fn resolve_directory_package(
+    directory: &Path,
+    extensions: &[&str],
+    trace: &mut Option<&mut ResolutionTrace>,
+) -> Option<PathBuf> {
+    let package_json_path = directory.join(PackageJson::file_name());
+
+    if !package_json_path.is_file() {
+        trace_step(trace, || {
+            format!(
+                "no directory package: {} not found",
+                package_json_path.display()
+            )
+        });
+        return None;
+    }
+
+    let package_json = read_config::<PackageJson>(&package_json_path).ok()?;
+
+    let entry = package_json
+        .types
+        .as_ref()
+        .or(package_json.typings.as_ref())
+        .or(package_json.main.as_ref())?;
+
+    let entry_path = RelativePath::new(entry).to_logical_path(directory);
+
+    trace_step(trace, || {
+        format!(
+            "directory package {} has entry point {}",
+            package_json_path.display(),
+            entry_path.display()
+        )
+    });
+
+    if entry_path.is_file() && has_any_extension(&entry_path, extensions) {
+        return Some(entry_path);
+    }
+
+    // The entry is often extensionless, or points to compiled JavaScript next to the TypeScript source
+    probe_extensions(&entry_path, extensions, trace)
+        .or_else(|| probe_extensions(&entry_path.with_extension(""), extensions, trace))
+}
+
 pub fn resolve_import_source(
     project_root: &Path,
     current_folder: &Path,
     import_source: &str,
+    config: &Config,
+) -> anyhow::Result<NormalizedImportSource> {
+    resolve_import_source_with_trace(project_root, current_folder, import_source, config, None)
+}
+
+/// Splits a query (like Vite's `?raw` or `?url`) or a hash fragment from the end of an import source.
+/// A leading `#` is kept, since it starts a subpath import like `#internal/utils`.
+fn split_import_suffix(import_source: &str) -> (&str, Option<&str>) {
+    match import_source
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| *c == '?' || *c == '#')
+    {
+        Some((index, _)) => (&import_source[..index], Some(&import_source[index..])),
+        None => (import_source, None),
+    }
+}
+
+/// Like `resolve_import_source`, but records every step taken into `trace`, if given.
+pub fn resolve_import_source_with_trace(
+    project_root: &Path,
+    current_folder: &Path,
+    import_source: &str,
+    config: &Config,
+    mut trace: Option<&mut ResolutionTrace>,
 ) -> anyhow::Result<NormalizedImportSource> {
+    let full_import_source = import_source;
+    let (import_source, suffix) = split_import_suffix(import_source);
+
+    if let Some(suffix) = suffix {
+        trace_step(&mut trace, || {
+            format!(
+                "{} has the suffix {}, resolving {} as an asset",
+                full_import_source, suffix, import_source
+            )
+        });
+    }
+
+    // Directory imports like ./components/ resolve like ./components
+    let import_source = match import_source.trim_end_matches('/') {
+        "" => import_source,
+        trimmed => trimmed,
+    };
+
     if !import_source.starts_with('.') {
+        if let Some(resolved) =
+            resolve_non_relative_import(project_root, import_source, suffix, config, &mut trace)?
+        {
+            return Ok(resolved);
+        }
+
+        trace_step(&mut trace, || {
+            format!(
+                "{} is not a relative import, treating it as a package",
+                import_source
+            )
+        });
         return Ok(NormalizedImportSource::Global(String::from(import_source)));
     }
 
-    let mut absolute_path = RelativePath::new(import_source).to_logical_path(current_folder);
+    let absolute_path = RelativePath::new(import_source).to_logical_path(current_folder);
+
+    trace_step(&mut trace, || {
+        format!(
+            "{} relative to {} is {}",
+            import_source,
+            current_folder.display(),
+            absolute_path.display()
+        )
+    });
 
-    for ext in ["d.ts", "ts", "tsx"] {
-        let with_ext = absolute_path.clone().with_extension(ext);
-        if with_ext.is_file() {
-            return normalize_module_path(project_root, &with_ext)
-                .map(NormalizedImportSource::Local);
+    resolve_path(project_root, absolute_path, suffix, config, &mut trace)
+}
+
+/// Resolves a non-relative import through the `paths` aliases and the `baseUrl` of tsconfig.json.
+/// Returns `None` if neither has a matching file, so the import refers to a package.
+fn resolve_non_relative_import(
+    project_root: &Path,
+    import_source: &str,
+    suffix: Option<&str>,
+    config: &Config,
+    trace: &mut Option<&mut ResolutionTrace>,
+) -> anyhow::Result<Option<NormalizedImportSource>> {
+    let compiler_options = &config.compiler_options;
+
+    let candidates = compiler_options
+        .aliased_paths(import_source)
+        .into_iter()
+        .chain(
+            compiler_options
+                .base_url
+                .as_ref()
+                .map(|base_url| RelativePath::new(import_source).to_logical_path(base_url)),
+        );
+
+    for candidate in candidates {
+        trace_step(trace, || {
+            format!("{} may refer to {}", import_source, candidate.display())
+        });
+
+        match resolve_path(project_root, candidate, suffix, config, trace)? {
+            NormalizedImportSource::Unresolved(_) => continue,
+            resolved => return Ok(Some(resolved)),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Resolves an import to a module by probing extensions, directory packages and index modules.
+/// Imports with a query or hash `suffix` resolve to the file as an asset variant.
+fn resolve_path(
+    project_root: &Path,
+    absolute_path: PathBuf,
+    suffix: Option<&str>,
+    config: &Config,
+    trace: &mut Option<&mut ResolutionTrace>,
+) -> anyhow::Result<NormalizedImportSource> {
+    // Custom extensions (e.g. platform suffixes like .web.tsx) take precedence over the configured resolution order
+    let extensions = config
+        .extensions
+        .iter()
+        .map(|extension| extension.suffix.trim_start_matches('.'))
+        .chain(config.resolve_extensions.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+
+    let resolved_path = probe_module_extension(&absolute_path, &extensions, trace)
+        .or_else(|| probe_extensions(&absolute_path, &extensions, trace))
+        .or_else(|| resolve_directory_package(&absolute_path, &extensions, trace))
+        .or_else(|| probe_extensions(&absolute_path.join("index"), &extensions, trace));
+
+    let resolved_path = match resolved_path {
+        Some(path) => path,
+        None if suffix.is_some() && absolute_path.is_file() => {
+            return Ok(NormalizedImportSource::AssetVariant(absolute_path));
+        }
+        // Non-code imports like stylesheets and images
+        None if absolute_path.is_file() => {
+            trace_step(trace, || {
+                format!("{} exists, but is not a module", absolute_path.display())
+            });
+            return Ok(NormalizedImportSource::ExternalLocal(absolute_path));
         }
+        None => return Ok(NormalizedImportSource::Unresolved(absolute_path)),
+    };
+
+    if suffix.is_some() {
+        trace_step(trace, || {
+            format!("{} is imported as an asset", resolved_path.display())
+        });
+        return Ok(NormalizedImportSource::AssetVariant(resolved_path));
     }
 
-    absolute_path.push("index.ts");
-    normalize_module_path(project_root, &absolute_path).map(NormalizedImportSource::Local)
+    if !resolved_path.starts_with(project_root) {
+        trace_step(trace, || {
+            format!(
+                "{} is outside the project root {}",
+                resolved_path.display(),
+                project_root.display()
+            )
+        });
+        return Ok(NormalizedImportSource::ExternalLocal(resolved_path));
+    }
+
+    normalize_module_path(project_root, &resolved_path).map(NormalizedImportSource::Local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::utils::{test_export, test_module},
+        tsconfig::ProjectCompilerOptions,
+    };
+
+    #[test]
+    fn appended_extension_keeps_existing_dots() {
+        assert_eq!(
+            PathBuf::from("src/foo.styles.ts"),
+            with_appended_extension(Path::new("src/foo.styles"), "ts")
+        );
+        assert_eq!(
+            PathBuf::from("src/Button.web.tsx"),
+            with_appended_extension(Path::new("src/Button"), "web.tsx")
+        );
+    }
+
+    #[test]
+    fn replaced_exports_keep_their_id() {
+        let mut module = test_module("a.ts", ModuleKind::TS);
+        let foo = module.add_export(test_export(ExportName::named("foo"), ExportKind::Value));
+        let bar = module.add_export(test_export(ExportName::named("bar"), ExportKind::Value));
+
+        assert_ne!(foo, bar);
+        assert_eq!(
+            module.add_export(test_export(ExportName::named("foo"), ExportKind::Type)),
+            foo
+        );
+        assert_eq!(module.exports.len(), 2);
+        assert_eq!(module.export_id(&ExportName::named("bar")), Some(bar));
+        assert_eq!(module.export(foo).kind, ExportKind::Type);
+        assert!(module.get_export(&ExportName::named("baz")).is_none());
+    }
+
+    #[test]
+    fn trace_unresolved_import() {
+        let mut trace = ResolutionTrace::new();
+        let resolved = resolve_import_source_with_trace(
+            Path::new("project"),
+            Path::new("project/src"),
+            "./missing",
+            &Config::default(),
+            Some(&mut trace),
+        )
+        .unwrap();
+
+        assert!(matches!(resolved, NormalizedImportSource::Unresolved(_)));
+        assert_eq!(
+            trace[0],
+            "./missing relative to project/src is project/src/missing"
+        );
+        assert!(trace
+            .iter()
+            .any(|step| step == "probe project/src/missing.d.ts: not found"));
+        assert!(trace
+            .iter()
+            .any(|step| step == "probe project/src/missing/index.tsx: not found"));
+    }
+
+    #[test]
+    fn import_suffixes() {
+        assert_eq!(
+            split_import_suffix("./shader.glsl?raw"),
+            ("./shader.glsl", Some("?raw"))
+        );
+        assert_eq!(
+            split_import_suffix("./icons.svg#close"),
+            ("./icons.svg", Some("#close"))
+        );
+        assert_eq!(
+            split_import_suffix("#internal/utils"),
+            ("#internal/utils", None)
+        );
+
+        let resolve = |import_source| {
+            resolve_import_source(
+                Path::new("project"),
+                Path::new("project/src"),
+                import_source,
+                &Config::default(),
+            )
+            .unwrap()
+        };
+
+        assert!(
+            matches!(resolve("lodash/?url"), NormalizedImportSource::Global(name) if name == "lodash")
+        );
+        assert!(matches!(
+            resolve("./missing/"),
+            NormalizedImportSource::Unresolved(path) if path == Path::new("project/src/missing")
+        ));
+    }
+
+    #[test]
+    fn specifiers_with_module_extensions() {
+        let root = std::env::temp_dir().join(format!("customs-extensions-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        for file in ["src/a.ts", "src/b.tsx", "src/c.mts", "src/d.js"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let resolve = |import_source| {
+            resolve_import_source(&root, &root.join("src"), import_source, &Config::default())
+                .unwrap()
+        };
+        let resolves_to = |import_source, expected: &str| {
+            matches!(
+                resolve(import_source),
+                NormalizedImportSource::Local(path) if path == NormalizedModulePath::new(expected)
+            )
+        };
+
+        assert!(resolves_to("./a.ts", "src/a"));
+        assert!(resolves_to("./a.js", "src/a"));
+        assert!(resolves_to("./b.js", "src/b"));
+        assert!(resolves_to("./b.jsx", "src/b"));
+        assert!(resolves_to("./c.mjs", "src/c.mts"));
+        // JavaScript modules aren't analyzed without allowJs
+        assert!(matches!(
+            resolve("./d.js"),
+            NormalizedImportSource::ExternalLocal(path) if path == root.join("src/d.js")
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn unmatched_alias_falls_back_to_package() {
+        let config = Config {
+            compiler_options: ProjectCompilerOptions {
+                base_url: Some(PathBuf::from("project/src")),
+                path_aliases: vec![(String::from("@/*"), vec![PathBuf::from("project/src/*")])],
+                ..ProjectCompilerOptions::default()
+            },
+            ..Config::default()
+        };
+
+        let mut trace = ResolutionTrace::new();
+        let resolved = resolve_import_source_with_trace(
+            Path::new("project"),
+            Path::new("project/src"),
+            "@/missing",
+            &config,
+            Some(&mut trace),
+        )
+        .unwrap();
+
+        assert!(matches!(resolved, NormalizedImportSource::Global(name) if name == "@/missing"));
+        assert_eq!(trace[0], "@/missing may refer to project/src/missing");
+        assert!(trace
+            .iter()
+            .any(|step| step == "@/missing may refer to project/src/@/missing"));
+    }
 }
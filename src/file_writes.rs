@@ -0,0 +1,160 @@
+//! Writes rewritten files, so that a failure never leaves a file half written.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use rayon::prelude::*;
+
+/// The temporary file is in the same folder, so that renaming it over the original is atomic.
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{}.customs-{}.tmp", file_name, std::process::id()))
+}
+
+fn write_temp(path: &Path, contents: &[u8]) -> anyhow::Result<PathBuf> {
+    let temp = temp_path(path);
+    fs::write(&temp, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(temp)
+}
+
+/// Replaces the file with the contents, so that it has either the old or the new contents.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let temp = write_temp(path, contents)?;
+
+    fs::rename(&temp, path).map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        anyhow::Error::new(err).context(format!("Failed to replace {}", path.display()))
+    })
+}
+
+/// Writes the files in parallel. Each file is replaced atomically, so it has either the old or the new contents.
+/// Returns the files which couldn't be written.
+pub fn write_files(writes: &[(&Path, &str)]) -> Vec<(PathBuf, anyhow::Error)> {
+    writes
+        .par_iter()
+        .filter_map(|(path, contents)| {
+            write_atomically(path, contents.as_bytes())
+                .err()
+                .map(|err| (path.to_path_buf(), err))
+        })
+        .collect()
+}
+
+/// Writes all of the files or none of them. The new contents are written to temporary files in parallel, which
+/// then replace the originals. If a file can't be replaced, the already replaced files are restored.
+pub fn write_files_transactionally(writes: &[(&Path, &str)]) -> anyhow::Result<()> {
+    // `None` for files which don't exist yet
+    let originals = writes
+        .par_iter()
+        .map(|(path, _)| match fs::read(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => {
+                Err(anyhow::Error::new(err).context(format!("Failed to read {}", path.display())))
+            }
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let temps = writes
+        .par_iter()
+        .map(|(path, contents)| write_temp(path, contents.as_bytes()))
+        .collect::<Vec<_>>();
+
+    if temps.iter().any(Result::is_err) {
+        let mut first_error = None;
+
+        for temp in temps {
+            match temp {
+                Ok(temp) => {
+                    let _ = fs::remove_file(temp);
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        return Err(first_error.unwrap().context("No files were changed"));
+    }
+
+    let temps = temps.into_iter().flatten().collect::<Vec<_>>();
+
+    // Renaming is sequential, so that it's known which files have to be restored
+    for (index, ((path, _), temp)) in writes.iter().zip(&temps).enumerate() {
+        if let Err(err) = fs::rename(temp, path) {
+            for temp in &temps[index..] {
+                let _ = fs::remove_file(temp);
+            }
+
+            for ((path, _), original) in writes[..index].iter().zip(&originals) {
+                let _ = match original {
+                    Some(contents) => write_atomically(path, contents),
+                    None => fs::remove_file(path).map_err(anyhow::Error::new),
+                };
+            }
+
+            return Err(anyhow::Error::new(err).context(format!(
+                "Failed to replace {}, so the other files were restored",
+                path.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_replaces_file() {
+        let folder = std::env::temp_dir().join(format!("customs-atomic-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("report.json");
+
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 1);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn transactional_write_changes_nothing_on_failure() {
+        let root = std::env::temp_dir().join(format!("customs-writes-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        let existing = root.join("index.ts");
+        let new = root.join("new.ts");
+        let missing_folder = root.join("missing/index.ts");
+        let (existing, new, missing_folder) = (&*existing, &*new, &*missing_folder);
+        fs::write(existing, "old").unwrap();
+
+        let result = write_files_transactionally(&[
+            (existing, "new"),
+            (new, "new"),
+            (missing_folder, "new"),
+        ]);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(existing).unwrap(), "old");
+        assert!(!new.exists());
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+
+        write_files_transactionally(&[(existing, "new"), (new, "new")]).unwrap();
+        assert_eq!(fs::read_to_string(existing).unwrap(), "new");
+        assert_eq!(fs::read_to_string(new).unwrap(), "new");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+//! Reads line history from git, so that recently changed exports can be left out of reports.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use tracing::warn;
+
+use crate::analysis::UnusedExportsResults;
+
+/// Runs git in the folder and returns its output.
+fn git_output(folder: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed in {}: {}",
+            args.join(" "),
+            folder.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Commit times (seconds since the Unix epoch) of each line in a file, indexed by zero-based line number.
+/// Lines which haven't been committed yet are `None`.
+pub fn line_commit_times(file: &Path) -> anyhow::Result<Vec<Option<u64>>> {
+    let folder = file
+        .parent()
+        .filter(|folder| !folder.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let file_name = file
+        .file_name()
+        .ok_or_else(|| anyhow!("Expected a file: {}", file.display()))?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(file_name)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git blame failed for {}: {}",
+            file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(parse_line_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Uncommitted lines are attributed to this commit by git blame.
+const UNCOMMITTED_HASH: &str = "0000000000000000000000000000000000000000";
+
+fn parse_line_porcelain(output: &str) -> Vec<Option<u64>> {
+    let mut times = Vec::new();
+    let mut is_header = true;
+    let mut is_uncommitted = false;
+    let mut committer_time = None;
+
+    for line in output.lines() {
+        if is_header {
+            // Each entry starts with the commit hash, followed by the line numbers
+            is_uncommitted = line.starts_with(UNCOMMITTED_HASH);
+            is_header = false;
+        } else if line.starts_with('\t') {
+            // The content of the line ends each entry
+            times.push(committer_time.filter(|_| !is_uncommitted));
+            committer_time = None;
+            is_header = true;
+        } else if let Some(time) = line.strip_prefix("committer-time ") {
+            committer_time = time.parse().ok();
+        }
+    }
+
+    times
+}
+
+/// Files changed since the commit where the working tree branched off `base`, including staged, unstaged and
+/// untracked files. With `HEAD` as the base, these are the files which a commit would include.
+/// Paths are canonical, and deleted files are left out.
+pub fn changed_files(folder: &Path, base: &str) -> anyhow::Result<HashSet<PathBuf>> {
+    let toplevel = PathBuf::from(git_output(folder, &["rev-parse", "--show-toplevel"])?.trim());
+    let merge_base = git_output(folder, &["merge-base", base, "HEAD"])?;
+    let merge_base = merge_base.trim();
+
+    let changed = git_output(folder, &["diff", "--name-only", "-z", merge_base, "--"])?;
+    let untracked = git_output(
+        folder,
+        &[
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--full-name",
+            "-z",
+        ],
+    )?;
+
+    Ok(changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| !path.is_empty())
+        .filter_map(|path| toplevel.join(path).canonicalize().ok())
+        .collect())
+}
+
+/// Removes unused exports which aren't declared in a file changed since `base`, see `changed_files`.
+/// Usage is still resolved across the whole project, so this only narrows down the report.
+pub fn retain_exports_in_changed_files(
+    results: &mut UnusedExportsResults,
+    folder: &Path,
+    base: &str,
+) -> anyhow::Result<()> {
+    let changed = changed_files(folder, base)?;
+    let mut files = HashMap::<PathBuf, bool>::new();
+
+    results.sorted_exports.retain(|export| {
+        let path = export.location.path();
+
+        *files.entry(path.to_owned()).or_insert_with(|| {
+            path.canonicalize()
+                .is_ok_and(|path| changed.contains(&path))
+        })
+    });
+
+    Ok(())
+}
+
+/// Removes unused exports whose line has been committed within `min_age`, or not committed at all.
+/// If git history can't be read for a file, its exports are kept and a warning is printed.
+pub fn retain_exports_older_than(results: &mut UnusedExportsResults, min_age: Duration) {
+    let cutoff = SystemTime::now()
+        .checked_sub(min_age)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_secs());
+
+    let mut files = HashMap::<PathBuf, Option<Vec<Option<u64>>>>::new();
+
+    results.sorted_exports.retain(|export| {
+        let location = &export.location;
+        let times = files.entry(location.path().to_owned()).or_insert_with(|| {
+            match line_commit_times(location.path()) {
+                Ok(times) => Some(times),
+                Err(err) => {
+                    warn!(
+                        "Failed to read git history of {}: {:#}",
+                        location.path().display(),
+                        err
+                    );
+                    None
+                }
+            }
+        });
+
+        match times {
+            Some(times) => match times.get(location.line() - 1) {
+                Some(Some(time)) => *time <= cutoff,
+                _ => false,
+            },
+            None => true,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_blame_output() {
+        let output = "\
+1111111111111111111111111111111111111111 1 1 1
+author A
+committer-time 1600000000
+filename a.ts
+\texport const a = 1;
+0000000000000000000000000000000000000000 2 2 1
+author Not Committed Yet
+committer-time 1700000000
+filename a.ts
+\texport const b = 2;
+";
+
+        assert_eq!(parse_line_porcelain(output), vec![Some(1600000000), None]);
+    }
+
+    #[test]
+    fn changed_files_include_modified_and_untracked_files() {
+        let root = std::env::temp_dir().join(format!("customs-git-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        for file in ["unchanged.ts", "modified.ts"] {
+            std::fs::write(root.join(file), "export const a = 1;").unwrap();
+        }
+
+        let git = |args: &[&str]| git_output(&root, args).unwrap();
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=customs",
+            "-c",
+            "user.email=customs@example.com",
+            "commit",
+            "--quiet",
+            "--no-verify",
+            "-m",
+            "Initial commit",
+        ]);
+
+        std::fs::write(root.join("modified.ts"), "export const b = 2;").unwrap();
+        std::fs::write(root.join("untracked.ts"), "export const c = 3;").unwrap();
+
+        let changed = changed_files(&root, "HEAD").unwrap();
+        let expected = ["modified.ts", "untracked.ts"]
+            .iter()
+            .map(|file| root.join(file).canonicalize().unwrap())
+            .collect::<HashSet<_>>();
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(changed, expected);
+    }
+}
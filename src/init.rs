@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use crate::{
+    customs_config::CustomsConfig,
+    json_config::{read_config, JsonConfig},
+    package_json::PackageJson,
+};
+
+/// A starter configuration, along with the reasons for each setting.
+pub struct InitialConfig {
+    pub config: CustomsConfig,
+    pub notes: Vec<String>,
+}
+
+const BUILD_OUTPUT_FOLDERS: &[&str] = &["dist", "build", "out", "coverage", "storybook-static"];
+
+/// Inspects the project (frameworks, test runners, workspaces and build folders) and suggests a starter config.
+pub fn detect_initial_config(root: &Path) -> anyhow::Result<InitialConfig> {
+    let mut config = CustomsConfig::default();
+    let mut notes = Vec::new();
+
+    let package_json_path = root.join(PackageJson::file_name());
+    let package_json = if package_json_path.is_file() {
+        Some(read_config::<PackageJson>(&package_json_path)?)
+    } else {
+        notes.push(String::from(
+            "No package.json found, so frameworks and test runners could not be detected.",
+        ));
+        None
+    };
+
+    if let Some(package_json) = &package_json {
+        detect_frameworks(package_json, &mut config, &mut notes);
+        detect_test_runners(package_json, &mut config, &mut notes);
+
+        if let Some(workspaces) = &package_json.workspaces {
+            for pattern in workspaces.patterns() {
+                let pattern = pattern.trim_end_matches('/');
+                config
+                    .entry_points
+                    .push(format!("{}/src/index.{{ts,tsx}}", pattern));
+                config
+                    .entry_points
+                    .push(format!("{}/index.{{ts,tsx}}", pattern));
+            }
+
+            notes.push(format!(
+                "Monorepo with {} workspace patterns: the index module of each package is an entry point.",
+                workspaces.patterns().len()
+            ));
+        } else if let Some(entry) = package_json.types.as_ref().or(package_json.main.as_ref()) {
+            if entry.ends_with(".ts") || entry.ends_with(".tsx") {
+                config
+                    .entry_points
+                    .push(entry.trim_start_matches("./").to_string());
+                notes.push(format!("{} is the package entry point.", entry));
+            }
+        }
+    }
+
+    for folder in BUILD_OUTPUT_FOLDERS {
+        if root.join(folder).is_dir() {
+            config.ignore.push(format!("{}/", folder));
+            notes.push(format!(
+                "{} looks like build output, so it is ignored.",
+                folder
+            ));
+        }
+    }
+
+    if config.entry_points.is_empty() {
+        notes.push(String::from(
+            "No entry points were detected. Add the modules which are used outside the project to entryPoints.",
+        ));
+    }
+
+    config.ignore.dedup();
+    config.entry_points.dedup();
+
+    Ok(InitialConfig { config, notes })
+}
+
+fn detect_frameworks(
+    package_json: &PackageJson,
+    config: &mut CustomsConfig,
+    notes: &mut Vec<String>,
+) {
+    if package_json.has_dependency("next") {
+        config.entry_points.extend([
+            String::from("pages/**/*.{ts,tsx}"),
+            String::from("src/pages/**/*.{ts,tsx}"),
+            String::from("app/**/{page,layout,loading,error,not-found,route}.{ts,tsx}"),
+            String::from("src/app/**/{page,layout,loading,error,not-found,route}.{ts,tsx}"),
+            String::from("next.config.ts"),
+            String::from("middleware.ts"),
+        ]);
+        config.ignore.push(String::from(".next/"));
+        notes.push(String::from(
+            "Next.js: pages, app router files, middleware and the config are entry points.",
+        ));
+    }
+
+    if package_json.has_dependency("react-scripts") {
+        config.entry_points.extend([
+            String::from("src/index.tsx"),
+            String::from("src/setupTests.ts"),
+        ]);
+        notes.push(String::from(
+            "Create React App: src/index.tsx is the entry point.",
+        ));
+    }
+
+    if package_json.has_dependency("vite") {
+        config.entry_points.extend([
+            String::from("src/main.{ts,tsx}"),
+            String::from("vite.config.ts"),
+        ]);
+        notes.push(String::from(
+            "Vite: src/main and the Vite config are entry points.",
+        ));
+    }
+
+    if package_json.has_dependency_with_prefix("@storybook/") {
+        config.entry_points.extend([
+            String::from("**/*.stories.{ts,tsx}"),
+            String::from(".storybook/**"),
+        ]);
+        notes.push(String::from("Storybook: stories are entry points."));
+    }
+}
+
+fn detect_test_runners(
+    package_json: &PackageJson,
+    config: &mut CustomsConfig,
+    notes: &mut Vec<String>,
+) {
+    let runners = [
+        "jest",
+        "vitest",
+        "mocha",
+        "ava",
+        "@playwright/test",
+        "cypress",
+    ]
+    .iter()
+    .filter(|runner| package_json.has_dependency(runner))
+    .collect::<Vec<_>>();
+
+    if runners.is_empty() {
+        return;
+    }
+
+    config.entry_points.extend([
+        String::from("**/*.{test,spec}.{ts,tsx}"),
+        String::from("**/__tests__/**"),
+    ]);
+
+    notes.push(format!(
+        "Test runner ({}): test files are entry points.",
+        runners
+            .iter()
+            .map(|runner| **runner)
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package_json_with_dev_dependencies(names: &[&str]) -> PackageJson {
+        PackageJson {
+            dev_dependencies: names
+                .iter()
+                .map(|name| (name.to_string(), String::from("*")))
+                .collect(),
+            ..PackageJson::default()
+        }
+    }
+
+    #[test]
+    fn detects_next_and_jest() {
+        let package_json = package_json_with_dev_dependencies(&["next", "jest"]);
+        let mut config = CustomsConfig::default();
+        let mut notes = Vec::new();
+
+        detect_frameworks(&package_json, &mut config, &mut notes);
+        detect_test_runners(&package_json, &mut config, &mut notes);
+
+        assert!(config
+            .entry_points
+            .contains(&String::from("pages/**/*.{ts,tsx}")));
+        assert!(config
+            .entry_points
+            .contains(&String::from("**/*.{test,spec}.{ts,tsx}")));
+        assert_eq!(config.ignore, vec![String::from(".next/")]);
+        assert_eq!(notes.len(), 2);
+    }
+}
@@ -1,6 +1,5 @@
 use std::{
-    fs::File,
-    io::BufReader,
+    fs,
     path::{Path, PathBuf},
 };
 
@@ -9,6 +8,11 @@ use serde::Deserialize;
 
 pub trait JsonConfig {
     fn file_name() -> &'static str;
+
+    /// Whether the file is JSONC, which allows comments and trailing commas like tsconfig.json.
+    fn allows_comments() -> bool {
+        false
+    }
 }
 
 fn find_config_path<Config: JsonConfig>(folder: &Path) -> Option<PathBuf> {
@@ -25,24 +29,125 @@ fn find_config_path<Config: JsonConfig>(folder: &Path) -> Option<PathBuf> {
     }
 }
 
-fn read_config<Config: JsonConfig>(package_json_path: &Path) -> anyhow::Result<Config>
+pub fn read_config<Config>(package_json_path: &Path) -> anyhow::Result<Config>
 where
-    for<'a> Config: Deserialize<'a>,
+    for<'a> Config: JsonConfig + Deserialize<'a>,
 {
-    let file = File::open(package_json_path)
+    let contents = fs::read_to_string(package_json_path)
         .with_context(|| format!("Failed to open {}", package_json_path.display()))?;
-    let reader = BufReader::new(file);
-    let manifest: Config = serde_json::from_reader(reader)
+
+    let contents = if Config::allows_comments() {
+        strip_jsonc(&contents)
+    } else {
+        contents
+    };
+
+    let manifest: Config = serde_json::from_str(&contents)
         .with_context(|| format!("Failed to parse {}", package_json_path.display()))?;
 
     Ok(manifest)
 }
 
-pub fn find_and_read_config<Config: JsonConfig>(
-    root: &Path,
-) -> anyhow::Result<Option<(PathBuf, Config)>>
+/// Replaces comments and trailing commas with whitespace, so that JSONC can be parsed as JSON.
+/// Line breaks are kept, so parse errors still point to the right line and column.
+pub fn strip_jsonc(contents: &str) -> String {
+    let mut output = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut in_string = false;
+
+    let blank = |c: char| if c == '\n' { '\n' } else { ' ' };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match (c, chars.peek().copied()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                output.push(' ');
+
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+
+                    output.push(blank(next));
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                output.push(' ');
+                output.push(blank(chars.next().unwrap_or(' ')));
+
+                let mut previous = ' ';
+
+                for next in chars.by_ref() {
+                    output.push(blank(next));
+
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+
+                    previous = next;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    remove_trailing_commas(&output)
+}
+
+/// Replaces commas which are followed by `}` or `]` with a space. Comments must have been removed first.
+fn remove_trailing_commas(contents: &str) -> String {
+    let mut output = String::with_capacity(contents.len());
+    let mut in_string = false;
+    let mut is_escaped = false;
+    // Index of the last comma in `output` which has only been followed by whitespace.
+    let mut pending_comma = None;
+
+    for c in contents.chars() {
+        if in_string {
+            in_string = is_escaped || c != '"';
+            is_escaped = !is_escaped && c == '\\';
+            output.push(c);
+            continue;
+        }
+
+        if !c.is_whitespace() {
+            if let Some(index) = pending_comma.take() {
+                if matches!(c, '}' | ']') {
+                    output.replace_range(index..index + 1, " ");
+                }
+            }
+
+            if c == ',' {
+                pending_comma = Some(output.len());
+            }
+        }
+
+        in_string = c == '"';
+        output.push(c);
+    }
+
+    output
+}
+
+pub fn find_and_read_config<Config>(root: &Path) -> anyhow::Result<Option<(PathBuf, Config)>>
 where
-    for<'a> Config: Deserialize<'a>,
+    for<'a> Config: JsonConfig + Deserialize<'a>,
 {
     let package_json_path = find_config_path::<Config>(root);
 
@@ -51,3 +156,45 @@ where
         Some(path) => Ok(Some((path.clone(), read_config(&path)?))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jsonc_comments_and_trailing_commas() {
+        let contents = r#"{
+  // Line comment with "quotes"
+  "compilerOptions": {
+    /* Block
+       comment */
+    "baseUrl": "./src", // Trailing comment
+    "paths": { "@/*": ["*"], },
+    "url": "http://example.com/*,]",
+  },
+}"#;
+
+        let stripped = strip_jsonc(contents);
+        assert_eq!(stripped.lines().count(), contents.lines().count());
+
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value["compilerOptions"]["baseUrl"], "./src");
+        assert_eq!(value["compilerOptions"]["paths"]["@/*"][0], "*");
+        assert_eq!(value["compilerOptions"]["url"], "http://example.com/*,]");
+    }
+
+    #[test]
+    fn escaped_quotes_in_strings() {
+        let stripped = strip_jsonc(r#"{ "a": "\"// not a comment", }"#);
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value["a"], "\"// not a comment");
+    }
+
+    #[test]
+    fn only_trailing_commas_are_removed() {
+        assert_eq!(
+            remove_trailing_commas("[1,\n  2 ,\n],{\"a\": \",}\",\n}"),
+            "[1,\n  2  \n],{\"a\": \",}\" \n}"
+        );
+    }
+}
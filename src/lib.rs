@@ -1,13 +1,32 @@
 pub mod analysis;
 pub mod ast_utils;
+pub mod barrel;
+pub mod baseline;
+pub mod bench_fixture;
+pub mod cancellation;
 pub mod config;
+pub mod config_check;
+pub mod customs_config;
 pub mod dependency_graph;
+pub mod file_writes;
+pub mod git;
+pub mod init;
 pub mod json_config;
+pub mod lockfile;
+pub mod metrics;
 pub mod module_visitor;
+pub mod output;
 pub mod package_json;
+pub mod package_types;
 pub mod parsing;
+pub mod progress;
+pub mod project;
 pub mod reporting;
+pub mod repro;
+pub mod template;
 pub mod tsconfig;
+pub mod visitor_dump;
+pub mod workspaces;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,205 @@
+//! Reads the names of the installed packages from npm, Yarn and pnpm lockfiles.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde_json::Value;
+
+/// The packages listed in a lockfile, including transitive dependencies.
+pub struct Lockfile {
+    pub path: PathBuf,
+    pub packages: HashSet<String>,
+}
+
+const LOCKFILE_NAMES: &[&str] = &["package-lock.json", "yarn.lock", "pnpm-lock.yaml"];
+
+/// Finds the closest lockfile from the folder or its parents, since workspaces share the lockfile of the repository root.
+pub fn find_lockfile(folder: &Path) -> anyhow::Result<Option<Lockfile>> {
+    let path = folder.ancestors().find_map(|folder| {
+        LOCKFILE_NAMES
+            .iter()
+            .map(|name| folder.join(name))
+            .find(|path| path.is_file())
+    });
+
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let packages = match path.file_name().and_then(|name| name.to_str()) {
+        Some("package-lock.json") => parse_package_lock(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        Some("yarn.lock") => parse_yarn_lock(&contents),
+        _ => parse_pnpm_lock(&contents),
+    };
+
+    Ok(Some(Lockfile { path, packages }))
+}
+
+fn parse_package_lock(contents: &str) -> anyhow::Result<HashSet<String>> {
+    let lockfile: Value = serde_json::from_str(contents)?;
+    let mut packages = HashSet::new();
+
+    // Lockfile version 2 and later, e.g. "node_modules/a/node_modules/@scope/b"
+    if let Some(Value::Object(entries)) = lockfile.get("packages") {
+        for path in entries.keys() {
+            if let Some(index) = path.rfind("node_modules/") {
+                packages.insert(path[index + "node_modules/".len()..].to_string());
+            }
+        }
+    }
+
+    // Lockfile version 1, where dependencies are nested
+    let mut stack = vec![&lockfile];
+
+    while let Some(entry) = stack.pop() {
+        if let Some(Value::Object(dependencies)) = entry.get("dependencies") {
+            for (name, dependency) in dependencies {
+                packages.insert(name.clone());
+                stack.push(dependency);
+            }
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Parses the package name from a specifier like `@scope/name@^1.0.0` or `name@npm:1.0.0`.
+fn package_name_from_specifier(specifier: &str) -> Option<&str> {
+    let specifier = specifier.trim().trim_matches('"');
+    let version_start = specifier.get(1..)?.find('@')? + 1;
+    Some(&specifier[..version_start])
+}
+
+fn parse_yarn_lock(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with(|c: char| c.is_whitespace() || c == '#'))
+        .filter_map(|line| line.strip_suffix(':'))
+        .flat_map(|entry| entry.split(", "))
+        .filter_map(package_name_from_specifier)
+        .map(String::from)
+        .collect()
+}
+
+fn parse_pnpm_lock(contents: &str) -> HashSet<String> {
+    let mut packages = HashSet::new();
+    let mut in_packages = false;
+
+    for line in contents.lines() {
+        if !line.starts_with(' ') && !line.is_empty() {
+            in_packages = line == "packages:";
+            continue;
+        }
+
+        let key = match line.strip_prefix("  ") {
+            Some(key) if in_packages && !key.starts_with(' ') => key,
+            _ => continue,
+        };
+
+        // Keys are like /name/1.0.0 (v5), /@scope/name@1.0.0 (v6) or name@1.0.0(peer@1.0.0) (v9)
+        let key = key
+            .trim_end_matches(':')
+            .trim_matches('\'')
+            .trim_start_matches('/');
+        let segment_count = if key.starts_with('@') { 2 } else { 1 };
+
+        let name = key
+            .splitn(segment_count + 1, '/')
+            .take(segment_count)
+            .collect::<Vec<_>>()
+            .join("/");
+        let name = match name.get(1..).and_then(|rest| rest.find('@')) {
+            Some(index) => &name[..index + 1],
+            None => &name,
+        };
+
+        if !name.is_empty() {
+            packages.insert(name.to_string());
+        }
+    }
+
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(packages: HashSet<String>) -> Vec<String> {
+        let mut packages = packages.into_iter().collect::<Vec<_>>();
+        packages.sort();
+        packages
+    }
+
+    #[test]
+    fn package_lock() {
+        let contents = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/react": { "version": "18.2.0" },
+                "node_modules/react/node_modules/@scope/inner": { "version": "1.0.0" }
+            }
+        }"#;
+
+        assert_eq!(
+            sorted(parse_package_lock(contents).unwrap()),
+            vec!["@scope/inner", "react"]
+        );
+    }
+
+    #[test]
+    fn yarn_lock() {
+        let contents = r#"# yarn lockfile v1
+
+"@babel/core@^7.0.0", "@babel/core@^7.1.0":
+  version "7.1.0"
+
+lodash@^4.17.21:
+  version "4.17.21"
+  dependencies:
+    foo "^1.0.0"
+"#;
+
+        assert_eq!(
+            sorted(parse_yarn_lock(contents)),
+            vec!["@babel/core", "lodash"]
+        );
+    }
+
+    #[test]
+    fn pnpm_lock() {
+        let contents = r#"lockfileVersion: 5.4
+
+importers:
+  .:
+    specifiers:
+      react: ^18.0.0
+
+packages:
+
+  /react/18.2.0:
+    resolution: {integrity: sha512-abc}
+
+  /@testing-library/react/13.0.0_react@18.2.0:
+    dev: true
+
+  /@types/node@20.0.0(typescript@5.0.0):
+    dev: true
+"#;
+
+        assert_eq!(
+            sorted(parse_pnpm_lock(contents)),
+            vec!["@testing-library/react", "@types/node", "react"]
+        );
+    }
+}
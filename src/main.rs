@@ -1,106 +1,1043 @@
-use std::{path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{stdin, stdout, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::{anyhow, Context};
 use customs_analysis::{
-    analysis::{find_unused_dependencies, find_unused_exports, resolve_module_imports},
-    config::{AnalyzeTarget, Config, OutputFormat},
-    json_config::find_and_read_config,
-    package_json::PackageJson,
-    parsing::parse_all_modules,
-    reporting::{report_unused_dependencies, report_unused_exports},
-    tsconfig::TsConfig,
+    analysis::{
+        classify_package_reexports, explain_export, mark_entry_point_exports,
+        mark_forwarded_exports, resolve_reachable_module_imports,
+    },
+    barrel::{find_barrel_updates, is_stale, BarrelUpdate},
+    cancellation::CancellationToken,
+    config::{
+        max_memory_bytes, parse_define, parse_duration, resolve_outputs, resolve_source_roots,
+        use_colors, AnalyzeTarget, ColorChoice, Config, HyperlinkStyle, ModuleExtension,
+        OutputFormat, OutputPaths, OutputTarget, Rule, Severity,
+    },
+    config_check::check_project_config,
+    customs_config::{read_customs_config, CustomsConfig},
+    dependency_graph::{
+        normalize_module_path, resolve_import_source_with_trace, ExportName, Module,
+        NormalizedModulePath,
+    },
+    file_writes::{write_files, write_files_transactionally},
+    init::{detect_initial_config, InitialConfig},
+    json_config::{find_and_read_config, JsonConfig},
+    metrics::Metrics,
+    output::{
+        module_files_to_json, module_graph_to_json, render_text_template, write_machine_reports,
+        write_text_report, MachineReport,
+    },
+    package_json::{PackageJson, PackageJsonIndex},
+    parsing::{decode_source, find_module_files, parse_all_modules, visit_module_source},
+    project::{analyze_module_graph, load_package_export_kinds, load_project_config},
+    reporting::{
+        report_barrel_updates, report_config_problems, report_export_explanation,
+        report_import_resolution, report_module_files, report_module_graph,
+        report_project_analysis, report_unused_package_exports, report_workspaces,
+    },
+    repro::write_repro_bundle,
+    tsconfig::ProjectCompilerOptions,
+    visitor_dump::visitor_to_json,
+    workspaces::{analyze_workspaces, find_unused_package_exports},
 };
+use regex::Regex;
 use structopt::StructOpt;
+use tracing::{debug, error, info, Level};
+
+#[derive(StructOpt)]
+enum Command {
+    /// Find unused exports, dependencies and the findings of the other enabled rules. This is the default when no
+    /// subcommand is given.
+    Check,
+    /// Only check dependencies: unused and misplaced dependencies and lockfile problems. Like `check` with
+    /// `--analyze dependencies`, which skips resolving imports.
+    Deps,
+    /// Print the import graph: the modules, packages and unresolved files each module imports.
+    Graph {
+        /// Print the graph as JSON.
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Apply the automatic fixes. Currently rewrites out of date barrels, like `barrel --write`.
+    Fix {
+        /// Apply all fixes or none of them. If a file can't be written, the others are restored.
+        #[structopt(long)]
+        transactional: bool,
+        /// Run this command with the rewritten files as arguments afterwards, e.g. `npx prettier --write`.
+        #[structopt(long)]
+        format_command: Option<String>,
+    },
+    /// Explain why an export is considered used or unused.
+    Explain {
+        /// Path of the module relative to the target directory, as shown in reports.
+        file: PathBuf,
+        /// Name of the export, or `default` for the default export.
+        export: ExportName,
+    },
+    /// Print the steps taken to resolve an import, without running the analysis.
+    Resolve {
+        /// The import source, e.g. `./components/Button` or `lodash/fp`.
+        specifier: String,
+        /// Path of the importing module relative to the target directory.
+        #[structopt(long)]
+        from: PathBuf,
+    },
+    /// Validate customs.json, tsconfig.json and package.json without running the analysis.
+    CheckConfig,
+    /// Check that barrel index.ts files re-export exactly the exports used outside their folder, or regenerate them.
+    Barrel {
+        /// Report out of date barrels and exit with an error if there are any.
+        #[structopt(long, conflicts_with = "write", required_unless = "write")]
+        check: bool,
+        /// Rewrite out of date barrels.
+        #[structopt(long)]
+        write: bool,
+        /// With --write, rewrite all barrels or none of them. If one can't be written, the others are restored.
+        #[structopt(long, requires = "write")]
+        transactional: bool,
+        /// With --write, run this command with the rewritten files as arguments afterwards, e.g. `npx prettier --write`.
+        #[structopt(long, requires = "write")]
+        format_command: Option<String>,
+    },
+    /// Bundle a module which customs fails on, along with the settings used to parse it, for a bug report.
+    Repro {
+        /// Path of the module.
+        file: PathBuf,
+        /// Replace identifiers, strings and comments with generated names, and the file name with `module`.
+        #[structopt(long)]
+        strip_identifiers: bool,
+        /// Where to write the bundle.
+        #[structopt(long, default_value = "customs-repro.tar.gz")]
+        archive: PathBuf,
+    },
+    /// Print the files which are analyzed as modules, after ignore rules and include patterns, without parsing them.
+    ListFiles {
+        /// Print the files as JSON, with their module kind and normalized path.
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Inspect the project and write a starter customs.json.
+    Init {
+        /// Overwrite an existing customs.json.
+        #[structopt(long)]
+        force: bool,
+    },
+}
 
 #[derive(StructOpt)]
 #[structopt(version = "0.1", author = "Paavo Huhtala <paavo.huhtala@gmail.com>")]
 struct Opts {
-    target_dir: PathBuf,
+    /// Folders to analyze. Several folders are analyzed as one project rooted at their closest common folder,
+    /// so imports between them resolve. Defaults to the current folder.
+    target_dirs: Vec<PathBuf>,
+
+    /// Read a single module from stdin and print its exports, imports and bindings as JSON, instead of analyzing
+    /// the project. Requires `--stdin-path`.
+    #[structopt(long, requires = "stdin-path")]
+    stdin: bool,
+
+    /// Path of the module read with `--stdin`, e.g. `src/foo.ts`. It picks the module kind and doesn't have to exist.
+    #[structopt(long, requires = "stdin")]
+    stdin_path: Option<PathBuf>,
+
+    /// Report format: text, json or sarif. Append `=PATH` to write the report to a file, e.g. `json=report.json`.
+    /// Can be given several times, but only one format can be printed to stdout. Defaults to the formats in customs.json, or text.
+    #[structopt(short, long = "format")]
+    formats: Vec<OutputTarget>,
+
+    /// Write the format given without a path to this file, preferring a format other than text. Without --format,
+    /// the format is inferred from the extension, e.g. `report.txt` for text.
+    #[structopt(short, long)]
+    output: Option<PathBuf>,
+
+    /// Render the text report with a Mustache-style template, which receives the same data as the JSON report.
+    /// See docs/report_templates.md.
+    #[structopt(long)]
+    template: Option<PathBuf>,
+
+    /// Make file locations in the text report clickable in terminals which support OSC 8 hyperlinks.
+    /// `vscode` links open the location in VS Code.
+    #[structopt(long, default_value = "off", possible_values = HyperlinkStyle::ALL_STYLES)]
+    hyperlinks: HyperlinkStyle,
+
+    /// Color the text report. `auto` colors it when it's printed to a terminal, unless NO_COLOR is set.
+    #[structopt(long, default_value = "auto", possible_values = ColorChoice::ALL_CHOICES)]
+    color: ColorChoice,
+
+    /// How paths are written in all report formats: as found, absolute, or relative to the git repository root.
+    #[structopt(long, default_value = "relative", possible_values = OutputPaths::ALL_STYLES)]
+    output_paths: OutputPaths,
+
+    /// Which exports to analyze: types, values or all. Dependencies are checked with each of them, or alone with
+    /// `dependencies`, which skips resolving imports. Several targets can be combined with commas.
+    /// Defaults to all, unless set by customs.json or the selected profile.
+    #[structopt(short, long)]
+    analyze: Option<AnalyzeTarget>,
+
+    /// Name of a profile in customs.json, which sets the analyze target, rule severities and additional ignores.
+    #[structopt(long)]
+    profile: Option<String>,
+
+    /// An entry point of the project, e.g. `src/index.ts`. Can be given several times. Exports used only by modules
+    /// which can't be reached from the entry points, including those in customs.json, are reported as unused.
+    #[structopt(long = "entry")]
+    entries: Vec<String>,
+
+    /// Only analyze files matching this glob pattern, e.g. `src/**`. Can be given several times.
+    #[structopt(long)]
+    include: Vec<String>,
+
+    /// Don't analyze files matching this gitignore-style pattern, e.g. `**/*.generated.ts`. Can be given several times.
+    #[structopt(long)]
+    exclude: Vec<String>,
+
+    /// Analyze files which are ignored by git.
+    #[structopt(long)]
+    no_gitignore: bool,
+
+    /// Read this gitignore-style file in addition to `.customsignore`, e.g. `.eslintignore`. Can be given several
+    /// times.
+    #[structopt(long = "ignore-file")]
+    ignore_files: Vec<String>,
+
+    /// Search hidden files and folders, whose names start with a dot, for modules.
+    #[structopt(long)]
+    hidden: bool,
+
+    /// Don't report unused exports whose name matches this regular expression, e.g. `^use[A-Z]` or `Props$`.
+    /// Can be given several times.
+    #[structopt(long = "ignore-export")]
+    ignore_exports: Vec<Regex>,
+
+    /// Treat an expression as a constant, e.g. `process.env.NODE_ENV=production` or `__DEV__=false`.
+    /// Code in branches which can never be taken is not counted as usage.
+    #[structopt(long = "define", parse(try_from_str = parse_define))]
+    defines: Vec<(String, String)>,
+
+    /// Analyze files with an additional suffix as the given module kind, e.g. `.web.tsx=tsx` or `.mts=ts`.
+    #[structopt(long = "extension")]
+    extensions: Vec<ModuleExtension>,
+
+    /// Extensions probed in order when resolving imports without an extension.
+    #[structopt(long, use_delimiter = true, default_value = "d.ts,ts,tsx")]
+    resolve_extensions: Vec<String>,
+
+    /// Parse the type declarations of an installed package, so that re-exports from it can be classified as types or values.
+    #[structopt(long = "analyze-package")]
+    analyzed_packages: Vec<String>,
 
-    // Disabled since only one foramt is implemented right now
-    //#[structopt(short, long, default_value = "text", possible_values = OutputFormat::ALL_FORMATS)]
-    //format: OutputFormat,
-    #[structopt(short, long, default_value = "all", possible_values = AnalyzeTarget::ALL_TARGETS)]
-    analyze: AnalyzeTarget,
+    /// Show the installed version, license and description of reported dependencies, read from node_modules.
+    #[structopt(long)]
+    describe_dependencies: bool,
+
+    /// List unused dependencies which are overridden in package.json separately. By default they are not reported.
+    #[structopt(long)]
+    list_overridden: bool,
+
+    /// Stop with an error if parsed modules retain more than approximately this many megabytes of memory.
+    #[structopt(long)]
+    max_memory: Option<usize>,
+
+    /// Only report unused exports whose line hasn't been changed in git within this time, e.g. `30d`, `2w` or `12h`.
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    min_age: Option<Duration>,
+
+    /// Only report unused exports declared in files with staged, unstaged or untracked changes, e.g. in a
+    /// pre-commit hook. Usage is still checked across the whole project.
+    #[structopt(long)]
+    changed_only: bool,
+
+    /// Like `--changed-only`, but also includes files changed in commits since the branch diverged from this
+    /// revision, e.g. `origin/main` in a pull request check.
+    #[structopt(long, conflicts_with = "changed_only")]
+    changed_since: Option<String>,
+
+    /// Don't report unused exports which are listed in .customs-baseline.json, so that only new ones fail the run.
+    #[structopt(long)]
+    baseline: bool,
+
+    /// Write the current unused exports to .customs-baseline.json. Exports in the file are left out of later runs
+    /// with `--baseline`.
+    #[structopt(long, conflicts_with = "baseline")]
+    update_baseline: bool,
+
+    /// Report `ignoreKinds` entries, `customs-ignore` comments and baseline entries which no
+    /// longer hide any unused export.
+    #[structopt(long)]
+    report_unused_suppressions: bool,
+
+    /// Include in the JSON report how each used export is imported: as a value, as a type, re-exported or as a namespace member.
+    #[structopt(long)]
+    export_usage: bool,
+
+    /// Only check these rules, e.g. `unused-dependencies`. Separated by commas.
+    #[structopt(long, use_delimiter = true)]
+    only: Vec<Rule>,
+
+    /// Fail only if these rules have findings, e.g. `unused-exports,unused-dependencies`. The other rules are
+    /// still reported. Overrides the rule severities of customs.json profiles.
+    #[structopt(long, use_delimiter = true)]
+    fail_on: Vec<Rule>,
+
+    /// Exit with this code if a rule with the error severity has findings.
+    #[structopt(long, default_value = "1")]
+    exit_code: i32,
+
+    /// Report findings in modules with a generated code header, like `@generated` or
+    /// `Code generated ... DO NOT EDIT`. By default they are only used for resolving imports.
+    #[structopt(long)]
+    no_detect_generated: bool,
+
+    /// Fail with exit code 3 if a module can't be parsed. By default it is left out of the analysis.
+    #[structopt(long)]
+    strict: bool,
+
+    /// Also fail if rules with the warn severity have more findings than this in total, e.g. `0` in CI
+    /// or the current count while the codebase is cleaned up.
+    #[structopt(long)]
+    max_warnings: Option<usize>,
+
+    /// Write performance metrics of the run to this file as JSON: module counts, phase timings, module parse time
+    /// percentiles and memory use. They stay on this machine.
+    #[structopt(long)]
+    metrics_out: Option<PathBuf>,
+
+    /// Print the same metrics as --metrics-out as JSON to stderr at the end of the run, e.g. to track performance
+    /// across CI runs.
+    #[structopt(long)]
+    timings: bool,
+
+    /// Log how many modules have been parsed and resolved so far, and how many failed, every second.
+    #[structopt(long)]
+    progress: bool,
+
+    /// Parse modules with this many threads, e.g. to leave cores free on a shared CI machine. By default every core
+    /// is used.
+    #[structopt(long)]
+    threads: Option<NonZeroUsize>,
+
+    /// Only log errors. Warnings and progress are left out.
+    #[structopt(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more details: `-v` adds the metrics of --timings and `-vv` traces how imports are resolved.
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+
+    /// Analyze each workspace package listed in package.json separately, and summarize the findings.
+    #[structopt(long)]
+    workspaces: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
 impl Opts {
-    pub fn into_config(self) -> Config {
+    pub fn into_config(self, root: PathBuf, source_roots: Vec<PathBuf>) -> Config {
         Config {
-            root: Arc::new(self.target_dir),
-            format: OutputFormat::Text,
-            analyze_target: self.analyze,
+            root: Arc::new(root),
+            outputs: vec![OutputTarget::stdout(OutputFormat::Text)],
+            text_template: self.template,
+            hyperlinks: self.hyperlinks,
+            colors: false,
+            output_paths: self.output_paths,
+            analyze_target: self.analyze.unwrap_or(AnalyzeTarget::All),
+            analyze_target_from_cli: self.analyze.is_some(),
+            source_roots,
             ignored_folders: Vec::new(),
+            ignore_patterns: self.exclude,
+            include_patterns: self.include,
+            respect_gitignore: !self.no_gitignore,
+            ignore_file_names: self.ignore_files,
+            include_hidden: self.hidden,
+            ignore_unreachable_usages: !self.entries.is_empty(),
+            entry_points: self.entries,
+            dynamic_exports: Vec::new(),
+            asset_folders: Vec::new(),
+            dev_files: Config::default_dev_files(),
+            polyfilled_builtins: Vec::new(),
+            extensions: self.extensions,
+            resolve_extensions: self
+                .resolve_extensions
+                .into_iter()
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .collect(),
+            compiler_options: ProjectCompilerOptions::default(),
+            analyzed_packages: self.analyzed_packages,
+            defines: Arc::new(self.defines.into_iter().collect()),
+            describe_dependencies: self.describe_dependencies,
+            list_overridden_dependencies: self.list_overridden,
+            max_memory: None,
+            min_age: self.min_age,
+            changed_since: self
+                .changed_since
+                .or_else(|| self.changed_only.then(|| String::from("HEAD"))),
+            use_baseline: self.baseline,
+            update_baseline: self.update_baseline,
+            ignored_kinds: Vec::new(),
+            ignored_export_names: self.ignore_exports,
+            report_unused_suppressions: self.report_unused_suppressions,
+            report_export_usage: self.export_usage,
+            rule_severities: HashMap::new(),
+            only_rules: self.only,
+            fail_on_rules: self.fail_on,
+            findings_exit_code: self.exit_code,
+            detect_generated: !self.no_detect_generated,
+            strict: self.strict,
+            max_warnings: self.max_warnings,
+            cancellation: CancellationToken::new(),
+            metrics: (self.metrics_out.is_some() || self.timings || self.verbose > 0)
+                .then(|| Arc::new(Metrics::new())),
+            metrics_path: self.metrics_out,
+            timings: self.timings,
+            progress: self.progress,
+            threads: self.threads,
+            package_jsons: None,
+        }
+    }
+}
+
+/// Exit code used when customs.json, tsconfig.json or the command line options are invalid.
+const EXIT_CONFIG_ERROR: i32 = 2;
+
+/// Exit code used when the analysis fails, including modules which can't be parsed with `--strict`.
+/// Findings exit with `--exit-code`, which defaults to 1.
+const EXIT_INTERNAL_ERROR: i32 = 3;
+
+/// Exit code used when the analysis is interrupted, following the shell convention for SIGINT.
+const EXIT_CANCELLED: i32 = 130;
+
+/// Marks an error as caused by the configuration, so that it exits with `EXIT_CONFIG_ERROR`.
+#[derive(Debug)]
+struct ConfigError;
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid configuration")
+    }
+}
+
+fn config_error(err: anyhow::Error) -> anyhow::Error {
+    err.context(ConfigError)
+}
+
+/// Like `Opts::from_args`, but invalid arguments (like an `--ignore-export` which isn't a valid regex)
+/// exit with `EXIT_CONFIG_ERROR`. `--help` and `--version` still exit successfully.
+fn parse_opts() -> Opts {
+    Opts::from_iter_safe(env::args_os()).unwrap_or_else(|err| {
+        if !err.use_stderr() {
+            err.exit();
         }
+
+        eprintln!("{}", err.message);
+        std::process::exit(EXIT_CONFIG_ERROR);
+    })
+}
+
+fn exit_if_cancelled(config: &Config) {
+    if config.cancellation.is_cancelled() {
+        let _ = stdout().flush();
+        eprintln!("Analysis cancelled.");
+        std::process::exit(EXIT_CANCELLED);
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut config = Opts::from_args().into_config();
+/// Logs go to stderr, so that reports printed to stdout stay machine-readable.
+fn init_logging(quiet: bool, verbose: u8) {
+    let level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
 
-    let _timer = ScopedTimer::new("Total");
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .without_time()
+        .with_ansi(std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none())
+        .with_writer(std::io::stderr)
+        .init();
+}
 
-    let tsconfig = find_and_read_config::<TsConfig>(&config.root)?;
+fn main() {
+    if let Err(err) = run() {
+        let _ = stdout().flush();
+        eprintln!("Error: {:?}", err);
 
-    if let Some((path, tsconfig)) = tsconfig {
-        let mut roots = tsconfig.normalized_type_roots(&path);
-        config.ignored_folders.append(&mut roots);
+        let exit_code = match err.downcast_ref::<ConfigError>() {
+            Some(_) => EXIT_CONFIG_ERROR,
+            None => EXIT_INTERNAL_ERROR,
+        };
+        std::process::exit(exit_code);
     }
+}
 
-    let modules = {
-        let _timer = ScopedTimer::new("Parsing");
-        let modules = parse_all_modules(&config);
-        println!("Parsed {} modules", modules.len());
-        modules
+fn run() -> anyhow::Result<()> {
+    let mut opts = parse_opts();
+    init_logging(opts.quiet, opts.verbose);
+    let command = match opts.command.take() {
+        Some(Command::Deps) => {
+            opts.analyze = Some(AnalyzeTarget::Dependencies);
+            Some(Command::Check)
+        }
+        command => command,
     };
+    let workspaces = opts.workspaces;
+    let profile = opts.profile.take();
+    let cli_analyze_target = opts.analyze;
+    // `--stdin` and `--stdin-path` require each other, so stdin mode always has a path
+    let stdin_path = opts.stdin_path.take().filter(|_| opts.stdin);
+    let color = opts.color;
+    let (root, source_roots) =
+        resolve_source_roots(std::mem::take(&mut opts.target_dirs)).map_err(config_error)?;
+    let outputs = if opts.formats.is_empty() && opts.output.is_none() {
+        configured_outputs(&root)
+    } else {
+        resolve_outputs(std::mem::take(&mut opts.formats), opts.output.take())
+    }
+    .map_err(config_error)?;
+    let max_memory = opts
+        .max_memory
+        .map(max_memory_bytes)
+        .transpose()
+        .map_err(config_error)?;
+    let mut config = opts.into_config(root, source_roots);
+    config.outputs = outputs;
+    config.max_memory = max_memory;
+    config.colors = use_colors(
+        color,
+        &config.outputs,
+        env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        stdout().is_terminal(),
+    );
+
+    let cancellation = config.cancellation.clone();
+    ctrlc::set_handler(move || {
+        // A second Ctrl-C exits immediately
+        if cancellation.is_cancelled() {
+            std::process::exit(EXIT_CANCELLED);
+        }
+
+        cancellation.cancel();
+    })?;
 
-    {
-        let _timer = ScopedTimer::new("Import resolution");
-        resolve_module_imports(&modules);
+    if let Some(stdin_path) = stdin_path {
+        load_project_config(&mut config).map_err(config_error)?;
+        return dump_stdin_module(&stdin_path, &config);
     }
 
-    let unused_dependencies = {
-        let _timer = ScopedTimer::new("Unused dependency analysis");
+    match command {
+        Some(Command::CheckConfig) => {
+            let problems = check_project_config(&config);
+            report_config_problems(&problems, &config);
+
+            if !problems.is_empty() {
+                std::process::exit(EXIT_CONFIG_ERROR);
+            }
+
+            Ok(())
+        }
+        Some(Command::Init { force }) => write_initial_config(&config, force),
+        Some(Command::ListFiles { json }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+
+            let mut files = find_module_files(&config)?
+                .map(|file| {
+                    let normalized_path = normalize_module_path(&config.root, &file.path)?;
+                    Ok((file, normalized_path))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            files.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+
+            if json {
+                let json = module_files_to_json(&files, &config);
+                println!("{}", serde_json::to_string_pretty(&json)?);
+            } else {
+                report_module_files(&files, &config);
+            }
+
+            Ok(())
+        }
+        Some(Command::Repro {
+            file,
+            strip_identifiers,
+            archive,
+        }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            let still_fails = write_repro_bundle(&file, &archive, strip_identifiers, &config)?;
+
+            println!("Wrote {}", archive.display());
+
+            if !still_fails {
+                println!(
+                    "The bundled module is parsed without errors, so it may not reproduce the problem.{}",
+                    if strip_identifiers {
+                        " Try without --strip-identifiers."
+                    } else {
+                        ""
+                    }
+                );
+            }
+
+            Ok(())
+        }
+        Some(Command::Explain { file, export }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            let modules = build_module_graph(&config)?;
+            let explanation = explain_export(&modules, &config, &file, &export)?;
+            report_export_explanation(explanation, &config);
+            Ok(())
+        }
+        Some(Command::Barrel {
+            check,
+            write,
+            transactional,
+            format_command,
+        }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            let modules = build_module_graph(&config)?;
+            let updates = find_barrel_updates(&modules, &config);
+
+            if check {
+                let stale = updates.into_iter().filter(is_stale).collect::<Vec<_>>();
+                report_barrel_updates(&stale, &config);
+                exit_if_errors(!stale.is_empty(), &config);
+                Ok(())
+            } else if write {
+                write_barrels(&updates, transactional, format_command.as_deref())
+            } else {
+                unreachable!("--check or --write is required")
+            }
+        }
+        Some(Command::Fix {
+            transactional,
+            format_command,
+        }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            let modules = build_module_graph(&config)?;
+            let updates = find_barrel_updates(&modules, &config);
+            write_barrels(&updates, transactional, format_command.as_deref())
+        }
+        Some(Command::Graph { json }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            let modules = parse_all_modules(&config)?;
+
+            if json {
+                let json = module_graph_to_json(&modules, &config);
+                println!("{}", serde_json::to_string_pretty(&json)?);
+            } else {
+                report_module_graph(&modules, &config);
+            }
+
+            Ok(())
+        }
+        Some(Command::Deps) => unreachable!("deps is run as check"),
+        Some(Command::Resolve { specifier, from }) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            trace_import_resolution(&specifier, &from, &config)
+        }
+        None | Some(Command::Check) if workspaces => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+
+            {
+                let _timer = ScopedTimer::new("package.json discovery", &config);
+                config.package_jsons = Some(Arc::new(PackageJsonIndex::discover(&config.root)));
+            }
+
+            let analyses = analyze_workspaces(&config)?;
+            exit_if_cancelled(&config);
+
+            let unused_package_exports = find_unused_package_exports(&config)?;
+            exit_if_cancelled(&config);
+
+            let has_errors = analyses.iter().any(|analysis| analysis.has_errors(&config))
+                || (config.severity(Rule::UnusedExports) == Severity::Error
+                    && !unused_package_exports.is_empty());
+
+            let package_export_warnings = match config.severity(Rule::UnusedExports) {
+                Severity::Warn => unused_package_exports.len(),
+                _ => 0,
+            };
+            let warning_count = analyses
+                .iter()
+                .map(|analysis| analysis.warning_count(&config))
+                .sum::<usize>()
+                + package_export_warnings;
+
+            let report = MachineReport {
+                projects: &analyses,
+                unused_package_exports: Some(&unused_package_exports),
+            };
+            write_machine_reports(&report, &config)?;
+            write_metrics(&config)?;
 
-        let package_json = find_and_read_config::<PackageJson>(&config.root)?;
+            match &config.text_template {
+                Some(template) => write_text_report(&config, |out| {
+                    write!(out, "{}", render_text_template(&report, &config, template)?)?;
+                    Ok(())
+                })?,
+                None => write_text_report(&config, |out| {
+                    report_workspaces(analyses, &config, out)?;
+                    writeln!(out)?;
+                    report_unused_package_exports(unused_package_exports, &config, out)
+                })?,
+            }
 
-        if let Some((_, package_json)) = package_json {
-            Some(find_unused_dependencies(&modules, &package_json, &config))
-        } else {
-            println!("WARNING: Failed to find package.json, skipping dependency analysis.");
-            None
+            exit_if_errors(has_errors, &config);
+            exit_if_too_many_warnings(warning_count, &config);
+            Ok(())
         }
+        None | Some(Command::Check) => {
+            load_project_config(&mut config).map_err(config_error)?;
+            apply_profile(&mut config, profile.as_deref(), cli_analyze_target)
+                .map_err(config_error)?;
+            let modules = build_module_graph(&config)?;
+            run_analysis(modules, &config)
+        }
+    }
+}
+
+/// Applies the profile selected with `--profile`. An analyze target given on the command line takes precedence.
+fn apply_profile(
+    config: &mut Config,
+    profile: Option<&str>,
+    cli_analyze_target: Option<AnalyzeTarget>,
+) -> anyhow::Result<()> {
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(()),
+    };
+
+    let (_, customs_config) = read_customs_config(&config.root)?.ok_or_else(|| {
+        anyhow!(
+            "--profile requires a {} in {}",
+            CustomsConfig::file_name(),
+            config.root.display()
+        )
+    })?;
+
+    customs_config.profile(profile)?.apply_to(config);
+
+    if let Some(analyze_target) = cli_analyze_target {
+        config.analyze_target = analyze_target;
+    }
+
+    Ok(())
+}
+
+/// Reads the report formats from customs.json, for when none are given on the command line.
+fn configured_outputs(root: &Path) -> anyhow::Result<Vec<OutputTarget>> {
+    let configured = match read_customs_config(root)? {
+        Some((path, customs_config)) => customs_config
+            .outputs(root)
+            .with_context(|| format!("Invalid format in {}", path.display()))?,
+        None => None,
+    };
+
+    Ok(configured.unwrap_or_else(|| vec![OutputTarget::stdout(OutputFormat::Text)]))
+}
+
+/// Written before the report, since findings end the process with an exit code.
+fn write_metrics(config: &Config) -> anyhow::Result<()> {
+    let metrics = match &config.metrics {
+        Some(metrics) => metrics,
+        None => return Ok(()),
+    };
+
+    // Recorded here rather than with a timer, since the process may exit before a timer would be dropped.
+    metrics.record_total();
+
+    if let Some(path) = &config.metrics_path {
+        metrics
+            .write(path)
+            .with_context(|| format!("Failed to write metrics to {}", path.display()))?;
+    }
+
+    if config.timings {
+        eprintln!("{}", serde_json::to_string_pretty(&metrics.to_json())?);
+    } else {
+        debug!("Timings: {}", metrics.to_json());
+    }
+
+    Ok(())
+}
+
+fn exit_if_errors(has_errors: bool, config: &Config) {
+    if has_errors {
+        let _ = stdout().flush();
+        std::process::exit(config.findings_exit_code);
+    }
+}
+
+/// Fails like errors do if the warnings exceed `--max-warnings`.
+fn exit_if_too_many_warnings(warning_count: usize, config: &Config) {
+    if config.exceeds_max_warnings(warning_count) {
+        eprintln!(
+            "Found {} warnings, which is more than the maximum of {}.",
+            warning_count,
+            config.max_warnings.unwrap_or_default()
+        );
+        exit_if_errors(true, config);
+    }
+}
+
+fn write_initial_config(config: &Config, force: bool) -> anyhow::Result<()> {
+    let path = config.root.join(CustomsConfig::file_name());
+
+    if path.exists() && !force {
+        return Err(anyhow!(
+            "{} already exists, use --force to overwrite it",
+            path.display()
+        ));
+    }
+
+    let InitialConfig {
+        config: customs_config,
+        notes,
+    } = detect_initial_config(&config.root)?;
+
+    for note in notes {
+        println!("{}", note);
+    }
+
+    let json = serde_json::to_string_pretty(&customs_config)?;
+    fs::write(&path, json + "\n").with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+fn write_barrels(
+    updates: &[BarrelUpdate],
+    transactional: bool,
+    format_command: Option<&str>,
+) -> anyhow::Result<()> {
+    if updates.is_empty() {
+        println!("All barrels are up to date.");
+        return Ok(());
+    }
+
+    let writes = updates
+        .iter()
+        .map(|update| (update.path.as_path(), update.contents.as_str()))
+        .collect::<Vec<_>>();
+
+    let failures = if transactional {
+        write_files_transactionally(&writes)?;
+        Vec::new()
+    } else {
+        write_files(&writes)
+    };
+
+    let written = writes
+        .iter()
+        .map(|(path, _)| *path)
+        .filter(|path| !failures.iter().any(|(failed, _)| failed == path))
+        .collect::<Vec<_>>();
+
+    for path in &written {
+        println!("Updated {}", path.display());
+    }
+
+    if let Some(format_command) = format_command {
+        run_format_command(format_command, &written)?;
+    }
+
+    for (path, err) in &failures {
+        error!(path = %path.display(), "Failed to update: {:#}", err);
+    }
+
+    if !failures.is_empty() {
+        return Err(anyhow!("{} barrels could not be updated", failures.len()));
+    }
+
+    Ok(())
+}
+
+/// Runs a formatter like Prettier on the rewritten files, so that they pass formatting checks.
+fn run_format_command(command: &str, paths: &[&Path]) -> anyhow::Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow!("The format command is empty"))?;
+
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .args(paths)
+        .status()
+        .with_context(|| format!("Failed to run {}", command))?;
+
+    if !status.success() {
+        return Err(anyhow!("{} failed with {}", command, status));
+    }
+
+    Ok(())
+}
+
+fn dump_stdin_module(path: &Path, config: &Config) -> anyhow::Result<()> {
+    let mut bytes = Vec::new();
+    stdin()
+        .read_to_end(&mut bytes)
+        .context("Failed to read stdin")?;
+    let source = decode_source(bytes).map_err(|reason| anyhow!("stdin {}", reason))?;
+
+    let visitor = visit_module_source(path, source, config)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&visitor_to_json(&visitor))?
+    );
+
+    Ok(())
+}
+
+fn trace_import_resolution(specifier: &str, from: &Path, config: &Config) -> anyhow::Result<()> {
+    let from = if from.starts_with(&*config.root) {
+        from.to_owned()
+    } else {
+        config.root.join(from)
     };
 
-    let unused_exports = {
-        let _timer = ScopedTimer::new("Unused exports analysis");
-        find_unused_exports(modules, &config)
+    let current_folder = from
+        .parent()
+        .context("The importing file should be in a folder")?;
+
+    let mut trace = Vec::new();
+    let resolved = resolve_import_source_with_trace(
+        &config.root,
+        current_folder,
+        specifier,
+        config,
+        Some(&mut trace),
+    )?;
+
+    let package_json = find_and_read_config::<PackageJson>(&config.root)?;
+
+    report_import_resolution(
+        specifier,
+        trace,
+        resolved,
+        package_json.as_ref().map(|(_, package_json)| package_json),
+        config,
+    );
+
+    Ok(())
+}
+
+/// Parses all modules and resolves their imports, marking imported exports as used.
+fn build_module_graph(config: &Config) -> anyhow::Result<HashMap<NormalizedModulePath, Module>> {
+    let mut modules = {
+        let _timer = ScopedTimer::new("Parsing", config);
+        let modules = parse_all_modules(config)?;
+        info!("Parsed {} modules", modules.len());
+        modules
+    };
+
+    exit_if_cancelled(config);
+
+    if !config.analyzed_packages.is_empty() {
+        let _timer = ScopedTimer::new("Package type declarations", config);
+
+        let package_export_kinds = load_package_export_kinds(config);
+        classify_package_reexports(&mut modules, &package_export_kinds);
+    }
+
+    if config.analyze_target.analyzes_exports() {
+        let _timer = ScopedTimer::new("Import resolution", config);
+        let stats = resolve_reachable_module_imports(&modules, config)?;
+        info!(
+            "Resolved {} module imports ({} to non-analyzed files, {} unresolved)",
+            stats.resolved, stats.external_local, stats.unresolved
+        );
+
+        let entry_points = mark_entry_point_exports(&modules, config)?;
+        mark_forwarded_exports(&modules);
+
+        if entry_points > 0 {
+            info!("Found {} entry point modules", entry_points);
+        }
+    }
+
+    exit_if_cancelled(config);
+
+    Ok(modules)
+}
+
+fn run_analysis(
+    modules: HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let analysis = {
+        let _timer = ScopedTimer::new("Unused exports and dependencies analysis", config);
+        analyze_module_graph(modules, config)?
     };
 
-    report_unused_exports(unused_exports, &config)?;
+    let has_errors = analysis.has_errors(config);
+    let warning_count = analysis.warning_count(config);
 
-    if let Some(dependencies) = unused_dependencies {
-        report_unused_dependencies(dependencies, &config);
+    let report = MachineReport {
+        projects: std::slice::from_ref(&analysis),
+        unused_package_exports: None,
+    };
+    write_machine_reports(&report, config)?;
+    write_metrics(config)?;
+
+    match &config.text_template {
+        Some(template) => write_text_report(config, |out| {
+            write!(out, "{}", render_text_template(&report, config, template)?)?;
+            Ok(())
+        })?,
+        None => write_text_report(config, |out| report_project_analysis(analysis, config, out))?,
     }
 
+    exit_if_errors(has_errors, config);
+    exit_if_too_many_warnings(warning_count, config);
     Ok(())
 }
 
 struct ScopedTimer {
     name: &'static str,
     started_at: Instant,
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl ScopedTimer {
-    pub fn new(name: &'static str) -> Self {
+    pub fn new(name: &'static str, config: &Config) -> Self {
         ScopedTimer {
             name,
             started_at: Instant::now(),
+            metrics: config.metrics.clone(),
         }
     }
 }
 
 impl Drop for ScopedTimer {
     fn drop(&mut self) {
-        println!("{}: {}ms", self.name, self.started_at.elapsed().as_millis());
+        if let Some(metrics) = &self.metrics {
+            metrics.record_phase(self.name, self.started_at.elapsed());
+        }
     }
 }
@@ -0,0 +1,215 @@
+//! Performance metrics of a run, written to a local file with `--metrics-out` or printed to stderr with `--timings`.
+//! Nothing is sent anywhere.
+
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{analysis::ImportResolutionStats, file_writes::write_files};
+
+/// Collects metrics from the analysis phases, which may run in parallel.
+#[derive(Debug)]
+pub struct Metrics {
+    started_at: Instant,
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+    /// How long reading and parsing each module took.
+    module_times: Mutex<Vec<Duration>>,
+    failed_modules: AtomicUsize,
+    retained_bytes: AtomicUsize,
+    /// Modules whose imports were resolved, which are all the parsed modules unless unreachable ones are skipped.
+    resolved_modules: AtomicUsize,
+    resolved_imports: AtomicUsize,
+    non_analyzed_imports: AtomicUsize,
+    unresolved_imports: AtomicUsize,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            started_at: Instant::now(),
+            phases: Mutex::new(Vec::new()),
+            module_times: Mutex::new(Vec::new()),
+            failed_modules: AtomicUsize::new(0),
+            retained_bytes: AtomicUsize::new(0),
+            resolved_modules: AtomicUsize::new(0),
+            resolved_imports: AtomicUsize::new(0),
+            non_analyzed_imports: AtomicUsize::new(0),
+            unresolved_imports: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_phase(&self, name: &'static str, duration: Duration) {
+        self.phases.lock().unwrap().push((name, duration));
+    }
+
+    /// Records the time since the metrics were created, which is when the run started, as the "Total" phase.
+    pub fn record_total(&self) {
+        self.record_phase("Total", self.started_at.elapsed());
+    }
+
+    /// Records a parsed module and the approximate memory its parsed form retains.
+    pub fn record_module(&self, duration: Duration, retained_bytes: usize) {
+        self.module_times.lock().unwrap().push(duration);
+        self.retained_bytes
+            .fetch_add(retained_bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_failed_module(&self) {
+        self.failed_modules.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the imports resolved in a project. Workspace packages add to the same counts.
+    pub fn record_import_resolution(&self, modules: usize, stats: ImportResolutionStats) {
+        self.resolved_modules.fetch_add(modules, Ordering::Relaxed);
+        self.resolved_imports
+            .fetch_add(stats.resolved, Ordering::Relaxed);
+        self.non_analyzed_imports
+            .fetch_add(stats.external_local, Ordering::Relaxed);
+        self.unresolved_imports
+            .fetch_add(stats.unresolved, Ordering::Relaxed);
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut module_times = self.module_times.lock().unwrap().clone();
+        module_times.sort();
+
+        let percentile_ms = |percentile| {
+            nearest_rank(&module_times, percentile).map(|time| time.as_secs_f64() * 1000.0)
+        };
+
+        json!({
+            "totalMs": millis(self.started_at.elapsed()),
+            "phases": self
+                .phases
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, duration)| json!({
+                    "name": name,
+                    "ms": millis(*duration),
+                }))
+                .collect::<Vec<_>>(),
+            "modules": {
+                "parsed": module_times.len(),
+                "failed": self.failed_modules.load(Ordering::Relaxed),
+                "resolved": self.resolved_modules.load(Ordering::Relaxed),
+            },
+            "imports": {
+                "resolved": self.resolved_imports.load(Ordering::Relaxed),
+                "nonAnalyzed": self.non_analyzed_imports.load(Ordering::Relaxed),
+                "unresolved": self.unresolved_imports.load(Ordering::Relaxed),
+            },
+            "moduleParseMs": {
+                "p50": percentile_ms(50),
+                "p90": percentile_ms(90),
+                "p99": percentile_ms(99),
+                "max": percentile_ms(100),
+            },
+            "retainedModuleBytes": self.retained_bytes.load(Ordering::Relaxed),
+            "peakMemoryBytes": peak_memory_bytes(),
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.to_json())? + "\n";
+
+        match write_files(&[(path, contents.as_str())]).into_iter().next() {
+            Some((_, err)) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The duration in whole milliseconds. serde_json can't serialize the `u128` of `Duration::as_millis`.
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// The value below which `percentile` percent of the sorted values are. `None` if there are no values.
+fn nearest_rank(sorted: &[Duration], percentile: usize) -> Option<Duration> {
+    let rank = (percentile * sorted.len()).div_ceil(100);
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// The peak resident memory of the process. Only available on Linux.
+#[cfg(target_os = "linux")]
+fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let times = (1..=10).map(Duration::from_millis).collect::<Vec<_>>();
+
+        assert_eq!(nearest_rank(&times, 50), Some(Duration::from_millis(5)));
+        assert_eq!(nearest_rank(&times, 90), Some(Duration::from_millis(9)));
+        assert_eq!(nearest_rank(&times, 99), Some(Duration::from_millis(10)));
+        assert_eq!(nearest_rank(&times, 100), Some(Duration::from_millis(10)));
+        assert_eq!(nearest_rank(&[], 50), None);
+    }
+
+    #[test]
+    fn import_resolution_counts() {
+        let metrics = Metrics::new();
+        let stats = ImportResolutionStats {
+            resolved: 5,
+            external_local: 1,
+            unresolved: 2,
+        };
+
+        metrics.record_import_resolution(3, stats);
+        metrics.record_import_resolution(2, stats);
+
+        let json = metrics.to_json();
+        assert_eq!(json["modules"]["resolved"], 5);
+        assert_eq!(json["imports"]["resolved"], 10);
+        assert_eq!(json["imports"]["nonAnalyzed"], 2);
+        assert_eq!(json["imports"]["unresolved"], 4);
+    }
+
+    #[test]
+    fn total_is_recorded_as_last_phase() {
+        let metrics = Metrics::new();
+        metrics.record_phase("Parsing", Duration::from_millis(5));
+        std::thread::sleep(Duration::from_millis(2));
+        metrics.record_total();
+
+        let json = metrics.to_json();
+        let phases = json["phases"].as_array().unwrap();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[1]["name"], "Total");
+        assert!(phases[1]["ms"].as_u64().unwrap() >= 2);
+    }
+}
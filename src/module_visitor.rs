@@ -11,21 +11,29 @@ use std::{
 use swc_atoms::JsWord;
 use swc_common::{SourceMap, Span};
 use swc_ecma_ast::{
-    ArrayPat, ArrowExpr, AssignExpr, BindingIdent, BlockStmt, BlockStmtOrExpr, ClassDecl,
-    ClassExpr, ClassMember, ClassProp, Constructor, DefaultDecl, DoWhileStmt, ExportDecl,
-    ExportDefaultDecl, ExportDefaultExpr, ExportSpecifier, Expr, ExprOrSuper, FnDecl, FnExpr,
-    ForInStmt, ForOfStmt, ForStmt, Function, Ident, ImportDecl, ImportDefaultSpecifier,
-    ImportNamedSpecifier, ImportSpecifier, ImportStarAsSpecifier, MemberExpr, NamedExport,
-    ObjectPatProp, PrivateProp, PropName, TsConditionalType, TsEntityName, TsEnumDecl,
-    TsEnumMember, TsExprWithTypeArgs, TsFnType, TsIndexSignature, TsInterfaceDecl, TsMappedType,
-    TsMethodSignature, TsPropertySignature, TsType, TsTypeAliasDecl, TsTypeParam, TsTypeQuery,
-    TsTypeQueryExpr, TsTypeRef, WhileStmt,
+    ArrayPat, ArrowExpr, AssignExpr, AssignOp, AssignPat, BindingIdent, BlockStmt, BlockStmtOrExpr,
+    CallExpr, Class, ClassDecl, ClassExpr, ClassMember, ClassMethod, ClassProp, CondExpr,
+    Constructor, DefaultDecl, DoWhileStmt, ExportDecl, ExportDefaultDecl, ExportDefaultExpr,
+    ExportSpecifier, Expr, ExprOrSuper, FnDecl, FnExpr, ForInStmt, ForOfStmt, ForStmt, Function,
+    Ident, IfStmt, ImportDecl, ImportDefaultSpecifier, ImportNamedSpecifier, ImportSpecifier,
+    ImportStarAsSpecifier, JSXAttr, JSXElement, JSXElementName, JSXFragment, JSXMemberExpr,
+    JSXObject, KeyValueProp, MemberExpr, MethodProp, ModuleDecl, NamedExport, ObjectPatProp,
+    ParamOrTsParamProp, Pat, PatOrExpr, PrivateMethod, PrivateProp, Prop, PropName, PropOrSpread,
+    TsConditionalType, TsEntityName, TsEnumDecl, TsEnumMember, TsExprWithTypeArgs, TsFnType,
+    TsIndexSignature, TsInterfaceDecl, TsMappedType, TsMethodSignature, TsModuleName,
+    TsParamPropParam, TsPropertySignature, TsType, TsTypeAliasDecl, TsTypeAnn, TsTypeParam,
+    TsTypeParamDecl, TsTypePredicate, TsTypeQuery, TsTypeQueryExpr, TsTypeRef, WhileStmt, WithStmt,
 };
 use swc_ecma_visit::Node;
 
 use crate::{
-    ast_utils::walk_ts_qualified_name,
-    dependency_graph::{ExportKind, ExportName, ImportName, ModuleSourceAndLine},
+    ast_utils::{
+        assign_pat_type_ann, evaluate_guard, is_ambient_decl, is_intrinsic_element, pat_type_ann,
+        static_member_name, walk_ts_qualified_name,
+    },
+    dependency_graph::{
+        namespace_member_name, ExportKind, ExportName, ImportKind, ImportName, ModuleSourceAndLine,
+    },
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -55,7 +63,7 @@ impl Display for ScopeId {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum BindingKind {
+pub(crate) enum BindingKind {
     Value,
     Function,
     TsFunctionOverload,
@@ -63,9 +71,9 @@ enum BindingKind {
 
 #[derive(Debug, Clone)]
 pub struct Binding {
-    name: JsWord,
-    span: Span,
-    kind: BindingKind,
+    pub(crate) name: JsWord,
+    pub(crate) span: Span,
+    pub(crate) kind: BindingKind,
 }
 
 impl Binding {
@@ -78,13 +86,13 @@ impl Binding {
     }
 
     fn can_be_shadowed_by(&self, other_kind: BindingKind) -> bool {
-        match (self.kind, other_kind) {
+        matches!(
+            (self.kind, other_kind),
             (
                 BindingKind::TsFunctionOverload,
                 BindingKind::TsFunctionOverload | BindingKind::Function,
-            ) => true,
-            _ => false,
-        }
+            )
+        )
     }
 }
 
@@ -102,6 +110,9 @@ pub struct Scope {
     pub(crate) references: HashSet<JsWord>,
     pub(crate) type_references: HashSet<JsWord>,
     pub(crate) ambiguous_references: HashSet<JsWord>,
+    /// Whether the scope calls `eval` or contains a `with` statement, so the bindings visible to it
+    /// can be referenced in ways which can't be analyzed.
+    pub(crate) has_dynamic_references: bool,
 
     pub(crate) parent: Option<ScopeId>,
     pub(crate) children: Vec<ScopeId>,
@@ -117,6 +128,7 @@ impl Scope {
             references: HashSet::new(),
             type_references: HashSet::new(),
             ambiguous_references: HashSet::new(),
+            has_dynamic_references: false,
 
             parent,
             children: Vec::new(),
@@ -130,12 +142,62 @@ pub struct ModuleExport {
     pub(crate) local_name: Option<JsWord>,
     pub(crate) kind: ExportKind,
     pub(crate) source: ModuleSourceAndLine,
+    /// The import source and imported name, if this export is a re-export from another module.
+    pub(crate) reexported_from: Option<(String, ImportName)>,
+    /// The number of lines in the exported statement, or 1 for export specifiers.
+    pub(crate) line_count: usize,
+    /// The statement which declares the export. Bindings from one statement, like `export const { a, b } = c`
+    /// or `export { a, b }`, share it.
+    pub(crate) statement: ModuleSourceAndLine,
+    /// Tells apart statements on the same line.
+    pub(crate) statement_span: Span,
+    /// Whether the export is declared with `declare`, or inside a `declare namespace`.
+    pub(crate) is_ambient: bool,
+}
+
+/// A root scope declaration (function, type alias or interface) which declares type parameters.
+#[derive(Debug)]
+pub struct GenericDeclaration {
+    pub(crate) name: JsWord,
+    /// The scope which contains the type parameter bindings.
+    pub(crate) scope: ScopeId,
+    pub(crate) type_params: Vec<(JsWord, ModuleSourceAndLine)>,
+}
+
+/// An ambient module declaration in a declaration file, e.g. `declare module "*.svg" { ... }`.
+#[derive(Debug)]
+pub struct AmbientModule {
+    pub(crate) name: String,
+    /// The scope of the module's body.
+    pub(crate) scope: ScopeId,
+    pub(crate) exports: Vec<ModuleExport>,
+    pub(crate) location: ModuleSourceAndLine,
+}
+
+/// A namespace which is exported from the module, e.g. `export namespace Api { ... }`.
+/// Its exported members are added to the exports of the module with qualified names like `Api.get`.
+#[derive(Debug)]
+struct ExportedNamespace {
+    name: JsWord,
+    /// The scope of the namespace's body.
+    scope: ScopeId,
+}
+
+/// A single import or re-export statement, e.g. `import type { A } from 'a'` or `export { B } from './b'`.
+#[derive(Debug)]
+pub struct ImportDeclaration {
+    pub(crate) source: String,
+    /// Whether the statement is explicitly type-only (`import type` or `export type`).
+    pub(crate) type_only: bool,
+    pub(crate) local_bindings: Vec<JsWord>,
+    pub(crate) location: ModuleSourceAndLine,
 }
 
 #[derive(Debug)]
 pub struct ModuleImport {
     pub imported_name: ImportName,
     pub local_binding: Option<JsWord>,
+    pub kind: ImportKind,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -163,10 +225,37 @@ pub struct ModuleVisitor {
 
     pub(crate) exports: Vec<ModuleExport>,
     pub(crate) imports: HashMap<String, Vec<ModuleImport>>,
+    pub(crate) import_declarations: Vec<ImportDeclaration>,
+    pub(crate) generic_declarations: Vec<GenericDeclaration>,
+    /// Exports of `declare module "name"` blocks are tracked separately from the exports of the file.
+    pub(crate) ambient_modules: Vec<AmbientModule>,
+    exported_namespaces: Vec<ExportedNamespace>,
+
+    /// Properties accessed on identifiers, e.g. `get` in `Api.get()` or `Response` in `Api.Response`.
+    pub(crate) property_accesses: HashMap<JsWord, HashSet<JsWord>>,
+    /// Identifiers which are referenced in any other way than by accessing a property, in any scope.
+    pub(crate) direct_references: HashSet<JsWord>,
+
+    /// Whether the module contains JSX elements or fragments, which need the JSX runtime.
+    pub(crate) has_jsx: bool,
+    /// Whether the file has top-level imports or exports. Ambient module declarations in other files declare
+    /// modules instead of augmenting them.
+    has_module_syntax: bool,
 
     in_type: bool,
     export_state: ExportState,
+    /// The number of lines in the current `export` declaration.
+    export_line_count: usize,
+    /// The span of the current `export` declaration.
+    export_statement: Option<Span>,
+    /// Whether the current `export` declaration is ambient.
+    export_ambient: bool,
     in_assign_lhs: bool,
+
+    /// Constant values for guard expressions like `process.env.NODE_ENV`, used to skip impossible branches.
+    defines: Arc<HashMap<String, String>>,
+    /// Whether tsconfig.json enables `emitDecoratorMetadata`.
+    emit_decorator_metadata: bool,
 }
 
 struct ScopeIterator<'a> {
@@ -213,20 +302,76 @@ impl ModuleVisitor {
             scopes,
             in_type: false,
             export_state: ExportState::Private,
+            export_line_count: 1,
+            export_statement: None,
+            export_ambient: false,
             exports: Vec::new(),
             imports: HashMap::new(),
+            import_declarations: Vec::new(),
+            generic_declarations: Vec::new(),
+            ambient_modules: Vec::new(),
+            exported_namespaces: Vec::new(),
+            property_accesses: HashMap::new(),
+            direct_references: HashSet::new(),
+            has_jsx: false,
+            has_module_syntax: false,
             in_assign_lhs: false,
+            defines: Arc::default(),
+            emit_decorator_metadata: false,
         }
     }
 
-    fn enter_scope(&mut self, kind: ScopeKind) {
+    pub fn set_defines(&mut self, defines: Arc<HashMap<String, String>>) {
+        self.defines = defines;
+    }
+
+    pub fn set_emit_decorator_metadata(&mut self, emit_decorator_metadata: bool) {
+        self.emit_decorator_metadata = emit_decorator_metadata;
+    }
+
+    /// Whether the file has top level imports or exports, which makes it a module rather than a script.
+    pub fn has_module_syntax(&self) -> bool {
+        self.has_module_syntax
+    }
+
+    /// Registers the modules augmented with `declare module "react" { ... }` as type-only imports, so that the
+    /// augmented package is used. Wildcard declarations like `declare module "*.svg"` don't refer to a module.
+    /// Called after the module has been visited, since an import after the augmentation makes the file a module.
+    pub fn register_module_augmentations(&mut self) {
+        if !self.has_module_syntax {
+            return;
+        }
+
+        for module in &self.ambient_modules {
+            if module.name.contains('*') {
+                continue;
+            }
+
+            self.import_declarations.push(ImportDeclaration {
+                source: module.name.clone(),
+                type_only: true,
+                local_bindings: Vec::new(),
+                location: module.location.clone(),
+            });
+            self.imports.entry(module.name.clone()).or_default();
+        }
+    }
+
+    fn enter_scope(&mut self, kind: ScopeKind) -> ScopeId {
         let new_id = self.scopes.len();
         let curent_scope = self.current_scope();
         curent_scope.children.push(ScopeId(new_id));
 
         let new_scope = Scope::new(new_id, Some(curent_scope.id), kind);
-        self.scope_stack.push(new_scope.id);
+        let new_scope_id = new_scope.id;
+        self.scope_stack.push(new_scope_id);
         self.scopes.push(new_scope);
+
+        new_scope_id
+    }
+
+    fn next_scope_id(&self) -> ScopeId {
+        ScopeId(self.scopes.len())
     }
 
     fn exit_scope(&mut self) {
@@ -301,6 +446,7 @@ impl ModuleVisitor {
     }
 
     fn mark_used_atom(&mut self, atom: &JsWord) {
+        self.direct_references.insert(atom.clone());
         let scope = self.current_scope();
         scope.references.insert(atom.clone());
     }
@@ -314,7 +460,26 @@ impl ModuleVisitor {
         scope.type_references.insert(ident.sym.clone());
     }
 
+    /// Marks the root of a type name as used, e.g. `D` of `D.E`. A namespace member is also a property access.
+    fn mark_entity_type_used(&mut self, entity_name: &TsEntityName) {
+        match entity_name {
+            TsEntityName::TsQualifiedName(qualified_name) => match &qualified_name.left {
+                TsEntityName::Ident(namespace) => {
+                    self.mark_type_used(namespace);
+                    self.mark_property_accessed(namespace, &qualified_name.right);
+                }
+                TsEntityName::TsQualifiedName(_) => {
+                    self.mark_type_used(walk_ts_qualified_name(qualified_name));
+                }
+            },
+            TsEntityName::Ident(ident) => {
+                self.mark_type_used(ident);
+            }
+        }
+    }
+
     fn mark_ambiguous_used_atom(&mut self, atom: &JsWord) {
+        self.direct_references.insert(atom.clone());
         let scope = self.current_scope();
         scope.ambiguous_references.insert(atom.clone());
     }
@@ -327,19 +492,108 @@ impl ModuleVisitor {
         self.scope_stack.last().unwrap().0 == 0
     }
 
-    fn register_decl(&mut self, name: &Ident, span: Span, kind: ExportKind) {
-        if !self.in_root_scope() {
+    /// The ambient module whose body is the current scope, if any.
+    fn current_ambient_module(&mut self) -> Option<&mut AmbientModule> {
+        let scope = *self.scope_stack.last().unwrap();
+        self.ambient_modules
+            .iter_mut()
+            .find(|module| module.scope == scope)
+    }
+
+    /// The exported namespace whose body is the current scope, if any.
+    fn current_exported_namespace(&self) -> Option<&ExportedNamespace> {
+        let scope = *self.scope_stack.last().unwrap();
+        self.exported_namespaces
+            .iter()
+            .find(|namespace| namespace.scope == scope)
+    }
+
+    /// Records a property access like `Api.get`, without counting it as a direct reference to `Api`.
+    fn mark_property_accessed(&mut self, object: &Ident, property: &Ident) {
+        self.property_accesses
+            .entry(object.sym.clone())
+            .or_default()
+            .insert(property.sym.clone());
+    }
+
+    /// Adds an export to the file, or to the ambient module or exported namespace whose body is the current scope.
+    /// Exports from other nested scopes (e.g. private namespaces) are dropped.
+    fn push_export(&mut self, mut export: ModuleExport) {
+        if self.in_root_scope() {
+            self.exports.push(export);
+        } else if let Some(namespace) = self.current_exported_namespace() {
+            if let ExportName::Named(member) = &export.name {
+                export.name = namespace_member_name(&namespace.name, member);
+                self.exports.push(export);
+            }
+        } else if let Some(module) = self.current_ambient_module() {
+            module.exports.push(export);
+        }
+    }
+
+    /// Removes the value exports registered for the overloads of a function once its implementation is found, so
+    /// that the implementation is the only export of the name.
+    fn remove_overload_exports(&mut self, name: &JsWord) {
+        let is_overload = |export: &ModuleExport, export_name: &ExportName| {
+            export.kind == ExportKind::Value && export.name == *export_name
+        };
+
+        if let Some(module) = self.current_ambient_module() {
+            let export_name = ExportName::Named(name.clone());
+            module
+                .exports
+                .retain(|export| !is_overload(export, &export_name));
             return;
         }
 
+        let export_name = match self.current_exported_namespace() {
+            Some(namespace) => namespace_member_name(&namespace.name, name),
+            None => ExportName::Named(name.clone()),
+        };
+        self.exports
+            .retain(|export| !is_overload(export, &export_name));
+    }
+
+    fn register_decl(&mut self, name: &Ident, span: Span, kind: ExportKind) {
         match self.export_state {
             ExportState::Private => {}
-            ExportState::InExport => self.exports.push(ModuleExport {
-                name: ExportName::Named(name.sym.clone()),
-                local_name: Some(name.sym.clone()),
-                kind,
-                source: self.create_span_source(span),
-            }),
+            ExportState::InExport => {
+                let source = self.create_span_source(span);
+                let statement_span = self.export_statement.unwrap_or(span);
+
+                self.push_export(ModuleExport {
+                    name: ExportName::Named(name.sym.clone()),
+                    local_name: Some(name.sym.clone()),
+                    kind,
+                    statement: self.create_span_source(statement_span),
+                    statement_span,
+                    source,
+                    reexported_from: None,
+                    line_count: self.export_line_count,
+                    is_ambient: self.export_ambient,
+                })
+            }
+        }
+    }
+
+    fn register_generic_decl(
+        &mut self,
+        name: &Ident,
+        scope: ScopeId,
+        type_params: Option<&TsTypeParamDecl>,
+    ) {
+        if let Some(type_params) = type_params {
+            let type_params = type_params
+                .params
+                .iter()
+                .map(|param| (param.name.sym.clone(), self.create_span_source(param.span)))
+                .collect();
+
+            self.generic_declarations.push(GenericDeclaration {
+                name: name.sym.clone(),
+                scope,
+                type_params,
+            });
         }
     }
 
@@ -351,7 +605,171 @@ impl ModuleVisitor {
         &self.scopes[scope_id.0]
     }
 
-    fn create_span_source(&self, span: Span) -> ModuleSourceAndLine {
+    /// Registers CommonJS exports (`module.exports = ...` and `exports.foo = ...`) in the root scope,
+    /// so that ES modules importing a CommonJS module use its exports.
+    fn register_commonjs_exports(&mut self, assign_expr: &AssignExpr) {
+        if !self.in_root_scope() || assign_expr.op != AssignOp::Assign {
+            return;
+        }
+
+        let target = match &assign_expr.left {
+            PatOrExpr::Expr(expr) => expr,
+            PatOrExpr::Pat(pat) => match &**pat {
+                Pat::Expr(expr) => expr,
+                _ => return,
+            },
+        };
+
+        let target = match static_member_name(target) {
+            Some(target) => target,
+            None => return,
+        };
+
+        let names = if target == "module.exports" {
+            let mut names = vec![ExportName::Default];
+
+            // With interop, the properties of an exported object literal can be imported by name
+            if let Expr::Object(object) = &*assign_expr.right {
+                names.extend(object.props.iter().filter_map(|prop| match prop {
+                    PropOrSpread::Prop(prop) => match &**prop {
+                        Prop::Shorthand(ident) => Some(ExportName::Named(ident.sym.clone())),
+                        Prop::KeyValue(KeyValueProp {
+                            key: PropName::Ident(key),
+                            ..
+                        })
+                        | Prop::Method(MethodProp {
+                            key: PropName::Ident(key),
+                            ..
+                        }) => Some(ExportName::Named(key.sym.clone())),
+                        _ => None,
+                    },
+                    PropOrSpread::Spread(_) => None,
+                }));
+            }
+
+            names
+        } else {
+            match target
+                .strip_prefix("module.exports.")
+                .or_else(|| target.strip_prefix("exports."))
+            {
+                Some(name) if !name.contains('.') => vec![ExportName::Named(JsWord::from(name))],
+                _ => return,
+            }
+        };
+
+        for name in names {
+            self.push_export(ModuleExport {
+                name,
+                local_name: None,
+                kind: ExportKind::Value,
+                source: self.create_span_source(assign_expr.span),
+                reexported_from: None,
+                line_count: self.span_line_count(assign_expr.span),
+                statement: self.create_span_source(assign_expr.span),
+                statement_span: assign_expr.span,
+                is_ambient: false,
+            });
+        }
+    }
+
+    /// Visits a default value or a computed key inside a binding pattern. The expression declares nothing even if the
+    /// pattern is exported or the target of an assignment, so e.g. the parameters of a default arrow function are
+    /// bindings of their own scope.
+    fn visit_pattern_expr(&mut self, expr: &Expr, parent: &dyn Node) {
+        let export_state = self.export_state;
+        let in_assign_lhs = self.in_assign_lhs;
+
+        self.export_state = ExportState::Private;
+        self.in_assign_lhs = false;
+        swc_ecma_visit::Visit::visit_expr(self, expr, parent);
+
+        self.export_state = export_state;
+        self.in_assign_lhs = in_assign_lhs;
+    }
+
+    /// With `emitDecoratorMetadata`, TypeScript emits the types of decorated members and the constructor parameters of
+    /// decorated classes as values (`design:type`, `design:paramtypes` and `design:returntype`). They are often the
+    /// only usage of classes which are injected by their type, e.g. services in NestJS or Angular.
+    fn mark_decorator_metadata_used(&mut self, class: &Class) {
+        if !self.emit_decorator_metadata {
+            return;
+        }
+
+        for member in &class.body {
+            match member {
+                ClassMember::Constructor(constructor) => {
+                    let has_param_decorators = constructor.params.iter().any(|param| match param {
+                        ParamOrTsParamProp::TsParamProp(prop) => !prop.decorators.is_empty(),
+                        ParamOrTsParamProp::Param(param) => !param.decorators.is_empty(),
+                    });
+
+                    if class.decorators.is_empty() && !has_param_decorators {
+                        continue;
+                    }
+
+                    for param in &constructor.params {
+                        let type_ann = match param {
+                            ParamOrTsParamProp::TsParamProp(prop) => match &prop.param {
+                                TsParamPropParam::Ident(ident) => ident.type_ann.as_ref(),
+                                TsParamPropParam::Assign(assign) => assign_pat_type_ann(assign),
+                            },
+                            ParamOrTsParamProp::Param(param) => pat_type_ann(&param.pat),
+                        };
+
+                        self.mark_metadata_type_used(type_ann);
+                    }
+                }
+                ClassMember::Method(ClassMethod { function, .. })
+                | ClassMember::PrivateMethod(PrivateMethod { function, .. }) => {
+                    let has_param_decorators = function
+                        .params
+                        .iter()
+                        .any(|param| !param.decorators.is_empty());
+
+                    if function.decorators.is_empty() && !has_param_decorators {
+                        continue;
+                    }
+
+                    for param in &function.params {
+                        self.mark_metadata_type_used(pat_type_ann(&param.pat));
+                    }
+
+                    self.mark_metadata_type_used(function.return_type.as_ref());
+                }
+                ClassMember::ClassProp(prop) if !prop.decorators.is_empty() => {
+                    self.mark_metadata_type_used(prop.type_ann.as_ref());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Emitted metadata refers to the class of a type reference, e.g. `UserService` of `service: UserService`.
+    fn mark_metadata_type_used(&mut self, type_ann: Option<&TsTypeAnn>) {
+        if let Some(TsType::TsTypeRef(type_ref)) = type_ann.map(|type_ann| &*type_ann.type_ann) {
+            match &type_ref.type_name {
+                TsEntityName::TsQualifiedName(qualified_name) => {
+                    self.mark_used(walk_ts_qualified_name(qualified_name));
+                }
+                TsEntityName::Ident(ident) => self.mark_used(ident),
+            }
+        }
+    }
+
+    fn span_line_count(&self, span: Span) -> usize {
+        let line = |pos| {
+            self.source_map
+                .0
+                .lookup_line(pos)
+                .map(|source_and_line| source_and_line.line)
+                .unwrap_or(0)
+        };
+
+        line(span.hi()).saturating_sub(line(span.lo())) + 1
+    }
+
+    pub(crate) fn create_span_source(&self, span: Span) -> ModuleSourceAndLine {
         let line = self
             .source_map
             .0
@@ -366,29 +784,41 @@ impl ModuleVisitor {
 
 impl swc_ecma_visit::Visit for ModuleVisitor {
     fn visit_export_decl(&mut self, export_decl: &ExportDecl, parent: &dyn Node) {
+        self.export_line_count = self.span_line_count(export_decl.span);
+        self.export_statement = Some(export_decl.span);
+
+        // Exports of an ambient namespace are ambient too
+        let in_ambient_export = self.export_ambient;
+        self.export_ambient = in_ambient_export || is_ambient_decl(&export_decl.decl);
+
         self.enter_export();
         self.visit_decl(&export_decl.decl, parent);
         self.exit_export();
+
+        self.export_ambient = in_ambient_export;
     }
 
     fn visit_export_default_decl(&mut self, default_decl: &ExportDefaultDecl, _parent: &dyn Node) {
-        // This is always true... except in TS declare module blocks
-        if self.in_root_scope() {
-            let (local_ident, kind) = match &default_decl.decl {
-                DefaultDecl::Class(ClassExpr { ident, .. }) => (ident.as_ref(), ExportKind::Class),
-                DefaultDecl::Fn(FnExpr { ident, .. }) => (ident.as_ref(), ExportKind::Value),
-                DefaultDecl::TsInterfaceDecl(TsInterfaceDecl { id: ident, .. }) => {
-                    (Some(ident), ExportKind::Type)
-                }
-            };
+        let (local_ident, kind) = match &default_decl.decl {
+            DefaultDecl::Class(ClassExpr { ident, .. }) => (ident.as_ref(), ExportKind::Class),
+            DefaultDecl::Fn(FnExpr { ident, .. }) => (ident.as_ref(), ExportKind::Value),
+            DefaultDecl::TsInterfaceDecl(TsInterfaceDecl { id: ident, .. }) => {
+                (Some(ident), ExportKind::Type)
+            }
+        };
 
-            self.exports.push(ModuleExport {
-                name: ExportName::Default,
-                local_name: local_ident.map(|ident| ident.sym.clone()),
-                kind,
-                source: self.create_span_source(default_decl.span),
-            });
-        }
+        // Outside the root scope, this only happens in TS declare module blocks
+        self.push_export(ModuleExport {
+            name: ExportName::Default,
+            local_name: local_ident.map(|ident| ident.sym.clone()),
+            kind,
+            source: self.create_span_source(default_decl.span),
+            reexported_from: None,
+            line_count: self.span_line_count(default_decl.span),
+            statement: self.create_span_source(default_decl.span),
+            statement_span: default_decl.span,
+            is_ambient: false,
+        });
 
         match &default_decl.decl {
             DefaultDecl::Class(class) => {
@@ -417,17 +847,20 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         export_default_expr: &ExportDefaultExpr,
         _parent: &dyn Node,
     ) {
-        if self.in_root_scope() {
-            self.exports.push(ModuleExport {
-                name: ExportName::Default,
-                local_name: None,
-                kind: ExportKind::Unknown,
-                source: self.create_span_source(export_default_expr.span),
-            });
-        }
+        self.push_export(ModuleExport {
+            name: ExportName::Default,
+            local_name: None,
+            kind: ExportKind::Unknown,
+            source: self.create_span_source(export_default_expr.span),
+            reexported_from: None,
+            line_count: self.span_line_count(export_default_expr.span),
+            statement: self.create_span_source(export_default_expr.span),
+            statement_span: export_default_expr.span,
+            is_ambient: false,
+        });
 
         match &*export_default_expr.expr {
-            Expr::Ident(ident) => self.mark_ambiguous_used(&ident),
+            Expr::Ident(ident) => self.mark_ambiguous_used(ident),
             _ => self.visit_expr(&export_default_expr.expr, export_default_expr),
         }
     }
@@ -437,19 +870,25 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         let (mut exports, mut imports): (Vec<ModuleExport>, Vec<ModuleImport>) = named_export
             .specifiers
             .iter()
-            .filter_map(|specifier| match specifier {
-                ExportSpecifier::Namespace(namespace_export) => Some((
+            .map(|specifier| match specifier {
+                ExportSpecifier::Namespace(namespace_export) => (
                     ModuleExport {
                         name: ExportName::Named(namespace_export.name.sym.clone()),
                         local_name: None,
                         kind: ExportKind::Unknown,
                         source: self.create_span_source(namespace_export.span),
+                        reexported_from: None,
+                        line_count: 1,
+                        statement: self.create_span_source(named_export.span),
+                        statement_span: named_export.span,
+                        is_ambient: false,
                     },
                     ModuleImport {
                         imported_name: ImportName::Wildcard,
                         local_binding: None,
+                        kind: ImportKind::Reexport,
                     },
-                )),
+                ),
                 ExportSpecifier::Default(_default_export) => {
                     // Do nothing. As far as I can tell this form is not valid ES - why does it exist in SWC's AST?
                     unreachable!("Named default exports should be impossible");
@@ -462,28 +901,47 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
                         _ => ExportName::Named(name),
                     };
 
-                    Some((
+                    // Forwarding a default export, e.g. `export { default as Thing } from './impl'`
+                    let imported_name = match named.orig.sym.as_ref() {
+                        "default" => ImportName::Default,
+                        _ => ImportName::Named(named.orig.sym.clone()),
+                    };
+
+                    (
                         ModuleExport {
                             name: export_name,
                             local_name: Some(named.orig.sym.clone()),
                             kind: ExportKind::Unknown,
                             source: self.create_span_source(named.span),
+                            reexported_from: named_export
+                                .src
+                                .as_ref()
+                                .map(|src| (src.value.to_string(), imported_name.clone())),
+                            line_count: 1,
+                            statement: self.create_span_source(named_export.span),
+                            statement_span: named_export.span,
+                            is_ambient: false,
                         },
                         ModuleImport {
-                            imported_name: ImportName::Named(named.orig.sym.clone()),
+                            imported_name,
                             local_binding: None,
+                            kind: ImportKind::Reexport,
                         },
-                    ))
+                    )
                 }
             })
             .unzip();
 
         // TODO - this technically allows invalid forms? You can't re-export * without specifying a source
         if let Some(source) = &named_export.src {
-            let imports_for_module = self
-                .imports
-                .entry(source.value.to_string())
-                .or_insert_with(Vec::new);
+            self.import_declarations.push(ImportDeclaration {
+                source: source.value.to_string(),
+                type_only: named_export.type_only,
+                local_bindings: Vec::new(),
+                location: self.create_span_source(named_export.span),
+            });
+
+            let imports_for_module = self.imports.entry(source.value.to_string()).or_default();
             imports_for_module.append(&mut imports);
         }
 
@@ -496,7 +954,10 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
             }
         }
 
-        self.exports.append(&mut exports);
+        match self.current_ambient_module() {
+            Some(module) => module.exports.append(&mut exports),
+            None => self.exports.append(&mut exports),
+        }
     }
 
     fn visit_import_decl(&mut self, import_decl: &ImportDecl, _parent: &dyn Node) {
@@ -504,8 +965,13 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
 
         // TODO: Do we ever need to access import_decl.asserts? What does it do and why?
 
-        // TODO: Should we take advantage of import_decl.type_only (import type {} from "foo")?
-        // We treat all imports as ambiguous anyways.
+        // The bindings of type-only imports are still treated as ambiguous.
+        // Value imports which are only referenced as types are classified when the module is analyzed.
+        let kind = if import_decl.type_only {
+            ImportKind::TypeOnly
+        } else {
+            ImportKind::Value
+        };
 
         for specifier in &import_decl.specifiers {
             match specifier {
@@ -522,27 +988,40 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
                     new_imports.push(ModuleImport {
                         imported_name: name,
                         local_binding: Some(local.sym.clone()),
+                        kind,
                     });
                 }
                 ImportSpecifier::Default(ImportDefaultSpecifier { local, .. }) => {
                     new_imports.push(ModuleImport {
                         imported_name: ImportName::Default,
                         local_binding: Some(local.sym.clone()),
+                        kind,
                     });
                 }
                 ImportSpecifier::Namespace(ImportStarAsSpecifier { local, .. }) => {
                     new_imports.push(ModuleImport {
                         imported_name: ImportName::Wildcard,
                         local_binding: Some(local.sym.clone()),
+                        kind,
                     });
                 }
             }
         }
 
+        self.import_declarations.push(ImportDeclaration {
+            source: import_decl.src.value.to_string(),
+            type_only: import_decl.type_only,
+            local_bindings: new_imports
+                .iter()
+                .filter_map(|import| import.local_binding.clone())
+                .collect(),
+            location: self.create_span_source(import_decl.span),
+        });
+
         let module_imports = self
             .imports
             .entry(import_decl.src.value.to_string())
-            .or_insert_with(Vec::new);
+            .or_default();
 
         module_imports.append(&mut new_imports);
     }
@@ -554,13 +1033,36 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
             BindingKind::TsFunctionOverload
         };
 
-        if kind != BindingKind::TsFunctionOverload {
-            self.register_decl(&fn_decl.ident, fn_decl.function.span, ExportKind::Value);
+        // The first overload is exported until an implementation replaces it, since functions in .d.ts files and
+        // `declare function` have no implementation
+        let follows_overload = matches!(
+            self.current_scope().bindings.get(&fn_decl.ident.sym),
+            Some(binding) if binding.kind == BindingKind::TsFunctionOverload
+        );
+
+        match kind {
+            BindingKind::TsFunctionOverload if follows_overload => {}
+            BindingKind::Function if follows_overload => {
+                self.remove_overload_exports(&fn_decl.ident.sym);
+                self.register_decl(&fn_decl.ident, fn_decl.function.span, ExportKind::Value);
+            }
+            _ => self.register_decl(&fn_decl.ident, fn_decl.function.span, ExportKind::Value),
         }
 
         self.add_binding(&fn_decl.ident, kind);
 
+        let is_top_level = self.in_root_scope();
+        let function_scope = self.next_scope_id();
+
         self.visit_function(&fn_decl.function, fn_decl);
+
+        if is_top_level && kind != BindingKind::TsFunctionOverload {
+            self.register_generic_decl(
+                &fn_decl.ident,
+                function_scope,
+                fn_decl.function.type_params.as_ref(),
+            );
+        }
     }
 
     fn visit_fn_expr(&mut self, fn_expr: &FnExpr, _parent: &dyn Node) {
@@ -628,6 +1130,30 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         self.visit_class(&class_decl.class, class_decl);
     }
 
+    fn visit_class(&mut self, class: &Class, _parent: &dyn Node) {
+        self.visit_decorators(&class.decorators, class);
+
+        if let Some(type_params) = &class.type_params {
+            self.visit_ts_type_param_decl(type_params, class);
+        }
+
+        // The base class is a value reference, and its type arguments and the implemented interfaces are types
+        if let Some(super_class) = &class.super_class {
+            self.visit_expr(super_class, class);
+        }
+
+        if let Some(super_type_params) = &class.super_type_params {
+            self.visit_ts_type_param_instantiation(super_type_params, class);
+        }
+
+        for ts_expr in &class.implements {
+            self.visit_ts_expr_with_type_args(ts_expr, class);
+        }
+
+        self.mark_decorator_metadata_used(class);
+        self.visit_class_members(&class.body, class);
+    }
+
     fn visit_class_members(&mut self, class_members: &[ClassMember], parent: &dyn Node) {
         self.enter_scope(ScopeKind::Type);
         for class_member in class_members {
@@ -644,6 +1170,7 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
 
     fn visit_class_prop(&mut self, class_prop: &ClassProp, _parent: &dyn Node) {
         // Do not visit key, because it's not a reference nor a binding
+        self.visit_decorators(&class_prop.decorators, class_prop);
 
         if let Some(value) = &class_prop.value {
             self.visit_expr(value, class_prop);
@@ -656,6 +1183,7 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
 
     fn visit_private_prop(&mut self, class_prop: &PrivateProp, _parent: &dyn Node) {
         // Do not visit key, because it's not a reference nor a binding
+        self.visit_decorators(&class_prop.decorators, class_prop);
 
         if let Some(value) = &class_prop.value {
             self.visit_expr(value, class_prop);
@@ -684,8 +1212,18 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         self.register_decl(&interface_decl.id, interface_decl.id.span, ExportKind::Type);
         self.add_type_binding(&interface_decl.id);
 
+        let is_top_level = self.in_root_scope();
+
         self.enter_type();
-        self.enter_scope(ScopeKind::Type);
+        let interface_scope = self.enter_scope(ScopeKind::Type);
+
+        if is_top_level {
+            self.register_generic_decl(
+                &interface_decl.id,
+                interface_scope,
+                interface_decl.type_params.as_ref(),
+            );
+        }
 
         if let Some(type_params) = &interface_decl.type_params {
             self.visit_ts_type_param_decl(type_params, interface_decl);
@@ -739,8 +1277,18 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         );
         self.add_type_binding(&type_alias_decl.id);
 
+        let is_top_level = self.in_root_scope();
+
         self.enter_type();
-        self.enter_scope(ScopeKind::Type);
+        let alias_scope = self.enter_scope(ScopeKind::Type);
+
+        if is_top_level {
+            self.register_generic_decl(
+                &type_alias_decl.id,
+                alias_scope,
+                type_alias_decl.type_params.as_ref(),
+            );
+        }
 
         if let Some(type_params) = &type_alias_decl.type_params {
             self.visit_ts_type_param_decl(type_params, type_alias_decl);
@@ -780,14 +1328,8 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
     }
 
     fn visit_ts_expr_with_type_args(&mut self, ts_expr: &TsExprWithTypeArgs, _parent: &dyn Node) {
-        match &ts_expr.expr {
-            TsEntityName::TsQualifiedName(_) => {
-                // TODO?
-            }
-            TsEntityName::Ident(ident) => {
-                self.mark_type_used(ident);
-            }
-        }
+        // e.g. `implements C<T>`, `implements D.E` or `interface A extends B`
+        self.mark_entity_type_used(&ts_expr.expr);
 
         if let Some(type_args) = &ts_expr.type_args {
             self.visit_ts_type_param_instantiation(type_args, ts_expr);
@@ -807,14 +1349,7 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
     }
 
     fn visit_ts_type_ref(&mut self, type_ref: &TsTypeRef, _parent: &dyn Node) {
-        match &type_ref.type_name {
-            TsEntityName::TsQualifiedName(_) => {
-                // TODO?
-            }
-            TsEntityName::Ident(ident) => {
-                self.mark_type_used(ident);
-            }
-        }
+        self.mark_entity_type_used(&type_ref.type_name);
 
         if let Some(type_params) = &type_ref.type_params {
             self.visit_ts_type_param_instantiation(type_params, type_ref);
@@ -825,19 +1360,29 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         match &type_query.expr_name {
             TsTypeQueryExpr::TsEntityName(entity_name) => match entity_name {
                 TsEntityName::TsQualifiedName(qualified_name) => {
-                    let ident = walk_ts_qualified_name(&qualified_name);
+                    let ident = walk_ts_qualified_name(qualified_name);
                     self.mark_used(ident);
                 }
                 TsEntityName::Ident(ident) => {
-                    self.mark_used(&ident);
+                    self.mark_used(ident);
                 }
             },
-            TsTypeQueryExpr::Import(_import) => {
-                todo!("typeof on import items not implemented")
+            // e.g. `typeof import("./config")` in declaration files, which refers to a module instead of a binding
+            TsTypeQueryExpr::Import(import) => {
+                if let Some(type_args) = &import.type_args {
+                    self.visit_ts_type_param_instantiation(type_args, import);
+                }
             }
         }
     }
 
+    fn visit_ts_type_predicate(&mut self, predicate: &TsTypePredicate, _parent: &dyn Node) {
+        // e.g. `value is Foo` or `asserts value is Foo`. The name refers to a parameter, so only the type is a reference.
+        if let Some(type_ann) = &predicate.type_ann {
+            self.visit_ts_type_ann(type_ann, predicate);
+        }
+    }
+
     fn visit_ts_type(&mut self, ts_type: &TsType, parent: &dyn Node) {
         self.enter_type();
         swc_ecma_visit::visit_ts_type(self, ts_type, parent);
@@ -921,6 +1466,16 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         }
     }
 
+    fn visit_assign_pat(&mut self, assign_pat: &AssignPat, _parent: &dyn Node) {
+        self.visit_pat(&assign_pat.left, assign_pat);
+
+        if let Some(type_ann) = &assign_pat.type_ann {
+            self.visit_ts_type_ann(type_ann, assign_pat);
+        }
+
+        self.visit_pattern_expr(&assign_pat.right, assign_pat);
+    }
+
     fn visit_object_pat_prop(&mut self, pat_prop: &ObjectPatProp, _parent: &dyn Node) {
         match pat_prop {
             ObjectPatProp::KeyValue(kv) => {
@@ -930,18 +1485,23 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
                     swc_ecma_ast::PropName::Str(_s) => {}
                     swc_ecma_ast::PropName::Num(_n) => {}
                     swc_ecma_ast::PropName::Computed(computed) => {
-                        self.visit_expr(&computed.expr, kv);
+                        self.visit_pattern_expr(&computed.expr, kv);
                     }
                     swc_ecma_ast::PropName::BigInt(_bi) => {}
                 }
                 self.visit_pat(&kv.value, kv);
             }
             ObjectPatProp::Assign(assign) => {
-                self.register_decl(&assign.key, assign.span, ExportKind::Value);
-                self.add_binding(&assign.key, BindingKind::Value);
+                // Shorthand properties are bindings too, or references in assignments like `({ a = 1 } = b)`
+                if self.in_assign_lhs {
+                    self.mark_used(&assign.key);
+                } else {
+                    self.register_decl(&assign.key, assign.key.span, ExportKind::Value);
+                    self.add_binding(&assign.key, BindingKind::Value);
+                }
 
                 if let Some(expr) = &assign.value {
-                    self.visit_expr(expr, assign);
+                    self.visit_pattern_expr(expr, assign);
                 }
             }
             ObjectPatProp::Rest(rest) => {
@@ -955,9 +1515,16 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
     }
 
     fn visit_member_expr(&mut self, member: &MemberExpr, _parent: &dyn Node) {
-        match &member.obj {
-            ExprOrSuper::Super(_) => {}
-            ExprOrSuper::Expr(expr) => {
+        match (&member.obj, &*member.prop) {
+            (ExprOrSuper::Super(_), _) => {}
+            (ExprOrSuper::Expr(expr), Expr::Ident(property)) if !member.computed => match &**expr {
+                Expr::Ident(object) => {
+                    self.current_scope().references.insert(object.sym.clone());
+                    self.mark_property_accessed(object, property);
+                }
+                _ => self.visit_expr(expr, member),
+            },
+            (ExprOrSuper::Expr(expr), _) => {
                 self.visit_expr(expr, member);
             }
         }
@@ -971,6 +1538,8 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
     }
 
     fn visit_assign_expr(&mut self, assign_expr: &AssignExpr, _parent: &dyn Node) {
+        self.register_commonjs_exports(assign_expr);
+
         self.in_assign_lhs = true;
         self.visit_pat_or_expr(&assign_expr.left, assign_expr);
         self.in_assign_lhs = false;
@@ -978,6 +1547,38 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         self.visit_expr(&assign_expr.right, assign_expr);
     }
 
+    fn visit_if_stmt(&mut self, if_stmt: &IfStmt, _parent: &dyn Node) {
+        self.visit_expr(&if_stmt.test, if_stmt);
+
+        // Branches which can never be taken with the configured defines are skipped entirely,
+        // so references inside them don't count as usages.
+        let condition = evaluate_guard(&if_stmt.test, &self.defines);
+
+        if condition != Some(false) {
+            self.visit_stmt(&if_stmt.cons, if_stmt);
+        }
+
+        if condition != Some(true) {
+            if let Some(alt) = &if_stmt.alt {
+                self.visit_stmt(alt, if_stmt);
+            }
+        }
+    }
+
+    fn visit_cond_expr(&mut self, cond_expr: &CondExpr, _parent: &dyn Node) {
+        self.visit_expr(&cond_expr.test, cond_expr);
+
+        let condition = evaluate_guard(&cond_expr.test, &self.defines);
+
+        if condition != Some(false) {
+            self.visit_expr(&cond_expr.cons, cond_expr);
+        }
+
+        if condition != Some(true) {
+            self.visit_expr(&cond_expr.alt, cond_expr);
+        }
+    }
+
     fn visit_for_in_stmt(&mut self, for_in_statement: &ForInStmt, parent: &dyn Node) {
         self.enter_scope(ScopeKind::Block);
         swc_ecma_visit::visit_for_in_stmt(self, for_in_statement, parent);
@@ -1002,15 +1603,127 @@ impl swc_ecma_visit::Visit for ModuleVisitor {
         self.exit_scope();
     }
 
+    fn visit_call_expr(&mut self, call: &CallExpr, parent: &dyn Node) {
+        // Only direct calls can access local bindings, indirect eval runs in the global scope
+        if let ExprOrSuper::Expr(callee) = &call.callee {
+            if matches!(&**callee, Expr::Ident(ident) if &*ident.sym == "eval") {
+                self.current_scope().has_dynamic_references = true;
+            }
+        }
+
+        swc_ecma_visit::visit_call_expr(self, call, parent);
+    }
+
+    fn visit_jsx_element(&mut self, element: &JSXElement, parent: &dyn Node) {
+        self.has_jsx = true;
+        swc_ecma_visit::visit_jsx_element(self, element, parent);
+    }
+
+    fn visit_jsx_fragment(&mut self, fragment: &JSXFragment, parent: &dyn Node) {
+        self.has_jsx = true;
+        swc_ecma_visit::visit_jsx_fragment(self, fragment, parent);
+    }
+
+    fn visit_jsx_element_name(&mut self, name: &JSXElementName, _parent: &dyn Node) {
+        match name {
+            JSXElementName::Ident(ident) => {
+                if !is_intrinsic_element(&ident.sym) {
+                    self.mark_used(ident);
+                }
+            }
+            JSXElementName::JSXMemberExpr(member) => self.visit_jsx_member_expr(member, name),
+            // e.g. `<svg:rect>`, which is always intrinsic
+            JSXElementName::JSXNamespacedName(_) => {}
+        }
+    }
+
+    fn visit_jsx_member_expr(&mut self, member: &JSXMemberExpr, _parent: &dyn Node) {
+        // e.g. `<Form.Field />`, which is handled like the member expression `Form.Field`
+        match &member.obj {
+            JSXObject::Ident(object) => {
+                self.current_scope().references.insert(object.sym.clone());
+                self.mark_property_accessed(object, &member.prop);
+            }
+            JSXObject::JSXMemberExpr(object) => self.visit_jsx_member_expr(object, member),
+        }
+    }
+
+    fn visit_jsx_attr(&mut self, attr: &JSXAttr, _parent: &dyn Node) {
+        // The name of an attribute is a prop, not a reference
+        if let Some(value) = &attr.value {
+            self.visit_jsx_attr_value(value, attr);
+        }
+    }
+
+    fn visit_with_stmt(&mut self, with_stmt: &WithStmt, parent: &dyn Node) {
+        self.current_scope().has_dynamic_references = true;
+        swc_ecma_visit::visit_with_stmt(self, with_stmt, parent);
+    }
+
     fn visit_do_while_stmt(&mut self, do_while_statement: &DoWhileStmt, parent: &dyn Node) {
         self.enter_scope(ScopeKind::Block);
         swc_ecma_visit::visit_do_while_stmt(self, do_while_statement, parent);
         self.exit_scope();
     }
 
-    fn visit_ts_module_decl(&mut self, n: &swc_ecma_ast::TsModuleDecl, parent: &dyn Node) {
-        self.enter_scope(ScopeKind::Block);
-        swc_ecma_visit::visit_ts_module_decl(self, n, parent);
+    fn visit_module_decl(&mut self, module_decl: &ModuleDecl, parent: &dyn Node) {
+        // Imports and exports in the body of an ambient module don't make the file a module
+        if self.in_root_scope() {
+            self.has_module_syntax = true;
+        }
+
+        swc_ecma_visit::visit_module_decl(self, module_decl, parent);
+    }
+
+    fn visit_ts_module_decl(&mut self, n: &swc_ecma_ast::TsModuleDecl, _parent: &dyn Node) {
+        let export_state = self.export_state;
+
+        // Namespaces nested in exported namespaces get qualified names, e.g. Api.Admin
+        let exported_name = match &n.id {
+            TsModuleName::Ident(ident) if export_state == ExportState::InExport => {
+                if self.in_root_scope() {
+                    Some(ident.sym.clone())
+                } else {
+                    self.current_exported_namespace()
+                        .map(|namespace| JsWord::from(format!("{}.{}", namespace.name, ident.sym)))
+                }
+            }
+            _ => None,
+        };
+
+        if let TsModuleName::Ident(ident) = &n.id {
+            self.register_decl(ident, n.span, ExportKind::Value);
+        }
+
+        let scope = self.enter_scope(ScopeKind::Block);
+
+        match &n.id {
+            TsModuleName::Str(name) => {
+                let location = self.create_span_source(n.span);
+
+                self.ambient_modules.push(AmbientModule {
+                    name: name.value.to_string(),
+                    scope,
+                    exports: Vec::new(),
+                    location,
+                })
+            }
+            TsModuleName::Ident(_) => {
+                if let Some(name) = exported_name {
+                    self.exported_namespaces
+                        .push(ExportedNamespace { name, scope });
+                }
+            }
+        }
+
+        // Only declarations with their own export keyword are exported from the body
+        self.exit_export();
+
+        if let Some(body) = &n.body {
+            self.visit_ts_namespace_body(body, n);
+        }
+
+        self.export_state = export_state;
         self.exit_scope();
     }
 }
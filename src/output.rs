@@ -0,0 +1,685 @@
+//! Machine-readable reports (JSON and SARIF), which can be written to files in the same run as the text report.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{stdout, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::{
+    analysis::{DependencySection, UnusedExportStyle, UnusedSuppression},
+    config::{Config, OutputFormat, OutputPaths, Rule, Severity},
+    dependency_graph::{Module, ModuleSourceAndLine, NormalizedModulePath},
+    file_writes::write_atomically,
+    parsing::ModuleFile,
+    project::ProjectAnalysis,
+    template::render_template,
+    workspaces::UnusedPackageExport,
+};
+
+/// The findings included in machine-readable reports.
+pub struct MachineReport<'a> {
+    pub projects: &'a [ProjectAnalysis],
+    /// `None` unless workspaces are analyzed.
+    pub unused_package_exports: Option<&'a [UnusedPackageExport]>,
+}
+
+/// Renders and writes every output of `config` other than text. Files are written atomically,
+/// so a CI job never picks up a partially written report.
+pub fn write_machine_reports(report: &MachineReport, config: &Config) -> anyhow::Result<()> {
+    for output in &config.outputs {
+        let value = match output.format {
+            OutputFormat::Text => continue,
+            OutputFormat::Json => render_json(report, config),
+            OutputFormat::Sarif => render_sarif(report, config),
+        };
+
+        let contents = serde_json::to_string_pretty(&value)? + "\n";
+
+        match &output.path {
+            Some(path) => {
+                write_atomically(path, contents.as_bytes())?;
+                info!("Wrote {}", path.display());
+            }
+            None => print!("{}", contents),
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the text report to its output, if text is one of the formats. A report written to a file
+/// leaves out the logging which is printed during the analysis.
+pub fn write_text_report(
+    config: &Config,
+    report: impl FnOnce(&mut dyn Write) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let output = match config
+        .outputs
+        .iter()
+        .find(|output| output.format == OutputFormat::Text)
+    {
+        Some(output) => output,
+        None => return Ok(()),
+    };
+
+    match &output.path {
+        Some(path) => {
+            let mut buffer = Vec::new();
+            report(&mut buffer)?;
+            write_atomically(path, &buffer)?;
+            info!("Wrote {}", path.display());
+        }
+        None => {
+            let stdout = stdout();
+            let mut stdout = stdout.lock();
+            report(&mut stdout)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn location_to_json(location: &ModuleSourceAndLine, config: &Config) -> Value {
+    json!({
+        "file": config.output_path(location.path()),
+        "line": location.line(),
+    })
+}
+
+fn dependency_section_name(section: DependencySection) -> &'static str {
+    match section {
+        DependencySection::Dependencies => "dependencies",
+        DependencySection::DevDependencies => "devDependencies",
+    }
+}
+
+fn project_to_json(analysis: &ProjectAnalysis, config: &Config) -> Value {
+    // Locations are relative to the package, not the workspace root
+    let config = &Config {
+        root: Arc::new(analysis.root.clone()),
+        ..config.clone()
+    };
+    let is_off = |rule| config.severity(rule) == Severity::Off;
+
+    let unused_exports = if is_off(Rule::UnusedExports) {
+        Vec::new()
+    } else {
+        analysis
+            .unused_exports
+            .sorted_exports
+            .iter()
+            .map(|export| {
+                json!({
+                    "name": export.name.to_string(),
+                    "kind": export.kind,
+                    "location": location_to_json(&export.location, config),
+                    "usedLocally": export.usage.used_locally,
+                    "lineCount": export.line_count,
+                    "statement": location_to_json(&export.statement, config),
+                })
+            })
+            .collect()
+    };
+
+    let unused_export_statements = if is_off(Rule::UnusedExports) {
+        Vec::new()
+    } else {
+        analysis
+            .unused_exports
+            .statements()
+            .iter()
+            .map(|statement| {
+                json!({
+                    "location": location_to_json(statement.statement(), config),
+                    "exports": statement
+                        .exports
+                        .iter()
+                        .map(|export| json!({
+                            "name": export.name.to_string(),
+                            "location": location_to_json(&export.location, config),
+                            "usedLocally": export.usage.used_locally,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "lineCount": statement.line_count(),
+                    "deletable": statement.is_deletable(),
+                })
+            })
+            .collect()
+    };
+
+    let unused_type_parameters = if is_off(Rule::UnusedTypeParameters) {
+        Vec::new()
+    } else {
+        analysis
+            .unused_type_parameters
+            .iter()
+            .map(|type_parameter| {
+                json!({
+                    "name": &*type_parameter.name,
+                    "declaration": &*type_parameter.declaration,
+                    "location": location_to_json(&type_parameter.location, config),
+                })
+            })
+            .collect()
+    };
+
+    let misplaced_dependencies = analysis
+        .misplaced_dependencies
+        .as_ref()
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .map(|dependency| {
+                    json!({
+                        "name": dependency.name,
+                        "moveTo": dependency_section_name(dependency.move_to),
+                        "evidence": dependency
+                            .evidence
+                            .iter()
+                            .map(|usage| location_to_json(&usage.location, config))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+    let lockfile_problems = analysis.lockfile_problems.as_ref().map(|problems| {
+        json!({
+            "lockfile": problems.lockfile,
+            "missingFromLockfile": problems.missing_from_lockfile,
+            "phantomImports": problems
+                .phantom_imports
+                .iter()
+                .map(|usage| json!({
+                    "package": usage.package,
+                    "location": location_to_json(&usage.location, config),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    });
+
+    let unused_suppressions = analysis
+        .unused_suppressions
+        .iter()
+        .map(|suppression| match suppression {
+            UnusedSuppression::IgnoredKind(filter) => json!({ "ignoreKinds": filter }),
+            UnusedSuppression::IgnoreComment(location) => {
+                json!({ "ignoreComment": location_to_json(location, config) })
+            }
+            UnusedSuppression::BaselineEntry(entry) => json!({ "baselineEntry": entry }),
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "root": analysis.root,
+        "unusedExports": unused_exports,
+        "unusedExportStatements": unused_export_statements,
+        "deletableLines": analysis.unused_exports.deletable_lines(),
+        "unusedDependencies": analysis.unused_dependencies,
+        "unusedBundledDependencies": analysis.unused_bundled_dependencies,
+        "unusedOverriddenDependencies": analysis.unused_overridden_dependencies,
+        "misplacedDependencies": misplaced_dependencies,
+        "lockfileProblems": lockfile_problems,
+        "unusedTypeParameters": unused_type_parameters,
+        "unusedAssets": analysis
+            .unused_assets
+            .iter()
+            .map(|asset| config.output_path(asset))
+            .collect::<Vec<_>>(),
+        "unreachableModules": analysis
+            .unreachable_islands
+            .iter()
+            .map(|island| json!({
+                "modules": island
+                    .modules
+                    .iter()
+                    .map(|module| config.output_path(module))
+                    .collect::<Vec<_>>(),
+                "lineCount": island.line_count,
+            }))
+            .collect::<Vec<_>>(),
+        "defaultExportMismatches": analysis
+            .default_export_mismatches
+            .iter()
+            .map(|mismatch| json!({
+                "module": config.output_path(&mismatch.module),
+                "unused": match mismatch.unused {
+                    UnusedExportStyle::Default => "default",
+                    UnusedExportStyle::Named => "named",
+                },
+                "importerCount": mismatch.importer_count,
+            }))
+            .collect::<Vec<_>>(),
+        "unusedSuppressions": unused_suppressions,
+        "exportUsages": analysis
+            .export_usages
+            .iter()
+            .map(|usage| json!({
+                "name": usage.name.to_string(),
+                "location": location_to_json(&usage.location, config),
+                "value": usage.kinds.value,
+                "typeOnly": usage.kinds.type_only,
+                "reexport": usage.kinds.reexport,
+                "namespaceMember": usage.kinds.namespace_member,
+                "onlyUsedAsType": usage.kinds.is_type_only(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Renders the findings as JSON. Paths are relative to the root of their project, unless `output_paths` says otherwise.
+pub fn render_json(report: &MachineReport, config: &Config) -> Value {
+    let mut value = json!({
+        "projects": report
+            .projects
+            .iter()
+            .map(|analysis| project_to_json(analysis, config))
+            .collect::<Vec<_>>(),
+    });
+
+    if let Some(exports) = report.unused_package_exports {
+        value["unusedPackageExports"] = exports
+            .iter()
+            .map(|export| {
+                json!({
+                    "package": export.package,
+                    "name": export.name.to_string(),
+                    "location": location_to_json(&export.location, config),
+                })
+            })
+            .collect();
+    }
+
+    value
+}
+
+/// The files which are analyzed as modules, for `list-files --json`.
+pub fn module_files_to_json(
+    files: &[(ModuleFile, NormalizedModulePath)],
+    config: &Config,
+) -> Value {
+    files
+        .iter()
+        .map(|(file, normalized_path)| {
+            json!({
+                "file": config.output_path(&file.path),
+                "kind": file.kind,
+                "normalizedPath": &**normalized_path,
+            })
+        })
+        .collect()
+}
+
+/// What each module imports, for `graph --json`. Modules are sorted by path.
+pub fn module_graph_to_json(
+    modules: &HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> Value {
+    let mut sorted_modules = modules.values().collect::<Vec<_>>();
+    sorted_modules.sort_by(|a, b| a.path.root_relative.cmp(&b.path.root_relative));
+
+    let sorted_paths = |paths: Vec<PathBuf>| {
+        let mut paths = paths
+            .iter()
+            .map(|path| config.output_path(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    };
+
+    sorted_modules
+        .into_iter()
+        .map(|module| {
+            let (analyzed, not_analyzed): (Vec<_>, Vec<_>) = module
+                .imported_modules
+                .keys()
+                .partition(|path| modules.contains_key(*path));
+
+            let mut packages = module.imported_packages.iter().collect::<Vec<_>>();
+            packages.sort();
+
+            json!({
+                "file": config.output_path(&module.path.root_relative),
+                "modules": sorted_paths(
+                    analyzed
+                        .into_iter()
+                        .map(|path| (*modules[path].path.root_relative).clone())
+                        .collect()
+                ),
+                "nonAnalyzedModules": sorted_paths(
+                    not_analyzed
+                        .into_iter()
+                        .map(|path| (**path).clone())
+                        .chain(module.imported_external_modules.iter().cloned())
+                        .collect()
+                ),
+                "packages": packages,
+                "unresolved": sorted_paths(module.unresolved_imports.iter().cloned().collect()),
+            })
+        })
+        .collect()
+}
+
+/// Renders the text report with the user's template, which receives the same data as the JSON report.
+pub fn render_text_template(
+    report: &MachineReport,
+    config: &Config,
+    template: &Path,
+) -> anyhow::Result<String> {
+    let contents = fs::read_to_string(template)
+        .with_context(|| format!("Failed to read {}", template.display()))?;
+
+    render_template(&contents, &render_json(report, config))
+        .with_context(|| format!("Failed to render {}", template.display()))
+}
+
+/// A single finding in a SARIF report.
+struct SarifResult {
+    rule: Rule,
+    message: String,
+    /// Relative to the folder the analysis was started in.
+    file: PathBuf,
+    line: Option<usize>,
+}
+
+const SARIF_RULES: &[(Rule, &str)] = &[
+    (
+        Rule::UnusedExports,
+        "Exports which no other module imports.",
+    ),
+    (
+        Rule::UnusedDependencies,
+        "Dependencies in package.json which no module imports.",
+    ),
+    (
+        Rule::UnusedTypeParameters,
+        "Type parameters which are never referenced.",
+    ),
+    (Rule::UnusedAssets, "Assets which no module imports."),
+    (
+        Rule::MisplacedDependencies,
+        "Dependencies listed in the wrong section of package.json.",
+    ),
+    (
+        Rule::LockfileConsistency,
+        "Dependencies which the lockfile doesn't match.",
+    ),
+    (
+        Rule::DefaultExportMismatches,
+        "Modules whose consumers only use the default export or only the named exports.",
+    ),
+    (
+        Rule::UnreachableModules,
+        "Modules which can't be reached from the entry points.",
+    ),
+];
+
+fn rule_id(rule: Rule) -> String {
+    serde_json::to_value(rule)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn project_sarif_results(analysis: &ProjectAnalysis, config: &Config) -> Vec<SarifResult> {
+    let mut results = Vec::new();
+    let root = &analysis.root;
+    let package_config = Config {
+        root: Arc::new(root.clone()),
+        ..config.clone()
+    };
+    let file = |path: &Path| sarif_path(root, path, &package_config);
+    let package_json = file(Path::new("package.json"));
+
+    let mut push = |rule, message: String, file: PathBuf, line| {
+        if config.severity(rule) != Severity::Off {
+            results.push(SarifResult {
+                rule,
+                message,
+                file,
+                line,
+            });
+        }
+    };
+
+    for export in &analysis.unused_exports.sorted_exports {
+        push(
+            Rule::UnusedExports,
+            format!("Unused export {}", export.name),
+            file(export.location.path()),
+            Some(export.location.line()),
+        );
+    }
+
+    for dependency in analysis
+        .unused_dependencies
+        .iter()
+        .flatten()
+        .chain(&analysis.unused_bundled_dependencies)
+    {
+        push(
+            Rule::UnusedDependencies,
+            format!("Unused dependency {}", dependency),
+            package_json.clone(),
+            None,
+        );
+    }
+
+    for dependency in analysis.misplaced_dependencies.iter().flatten() {
+        push(
+            Rule::MisplacedDependencies,
+            format!(
+                "{} should be moved to {}",
+                dependency.name,
+                dependency_section_name(dependency.move_to)
+            ),
+            package_json.clone(),
+            None,
+        );
+    }
+
+    if let Some(problems) = &analysis.lockfile_problems {
+        for dependency in &problems.missing_from_lockfile {
+            push(
+                Rule::LockfileConsistency,
+                format!(
+                    "{} is missing from {}",
+                    dependency,
+                    problems.lockfile.display()
+                ),
+                package_json.clone(),
+                None,
+            );
+        }
+
+        for usage in &problems.phantom_imports {
+            push(
+                Rule::LockfileConsistency,
+                format!(
+                    "{} is imported but not declared in package.json",
+                    usage.package
+                ),
+                file(usage.location.path()),
+                Some(usage.location.line()),
+            );
+        }
+    }
+
+    for type_parameter in &analysis.unused_type_parameters {
+        push(
+            Rule::UnusedTypeParameters,
+            format!(
+                "Unused type parameter {} in {}",
+                type_parameter.name, type_parameter.declaration
+            ),
+            file(type_parameter.location.path()),
+            Some(type_parameter.location.line()),
+        );
+    }
+
+    for asset in &analysis.unused_assets {
+        push(
+            Rule::UnusedAssets,
+            String::from("Asset not imported by any module"),
+            file(asset),
+            None,
+        );
+    }
+
+    for island in &analysis.unreachable_islands {
+        for module in &island.modules {
+            push(
+                Rule::UnreachableModules,
+                String::from("Module not reachable from the entry points"),
+                file(module),
+                None,
+            );
+        }
+    }
+
+    for mismatch in &analysis.default_export_mismatches {
+        let message = match mismatch.unused {
+            UnusedExportStyle::Default => "The default export is never imported",
+            UnusedExportStyle::Named => "The named exports are never imported",
+        };
+
+        push(
+            Rule::DefaultExportMismatches,
+            String::from(message),
+            file(&mismatch.module),
+            None,
+        );
+    }
+
+    results
+}
+
+/// By default, SARIF paths are relative to the folder the analysis was started in.
+fn sarif_path(root: &Path, path: &Path, config: &Config) -> PathBuf {
+    match config.output_paths {
+        OutputPaths::Relative => root.join(path),
+        _ => config.output_path(path),
+    }
+}
+
+/// Formats a path as a relative URI with forward slashes, or an absolute path as a `file://` URI.
+fn artifact_uri(path: &Path) -> String {
+    let path = path.strip_prefix(".").unwrap_or(path);
+
+    let uri = path
+        .components()
+        .filter(|component| !matches!(component, Component::RootDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    if path.is_absolute() {
+        format!("file:///{}", uri)
+    } else {
+        uri
+    }
+}
+
+/// Renders the findings as a SARIF 2.1.0 log, for code scanning tools.
+/// Paths are relative to the folder the analysis was started in, unless `output_paths` says otherwise.
+pub fn render_sarif(report: &MachineReport, config: &Config) -> Value {
+    let mut results = report
+        .projects
+        .iter()
+        .flat_map(|analysis| project_sarif_results(analysis, config))
+        .collect::<Vec<_>>();
+
+    if config.severity(Rule::UnusedExports) != Severity::Off {
+        for export in report.unused_package_exports.into_iter().flatten() {
+            results.push(SarifResult {
+                rule: Rule::UnusedExports,
+                message: format!(
+                    "Export {} of {} is not used by other workspaces",
+                    export.name, export.package
+                ),
+                file: sarif_path(&config.root, export.location.path(), config),
+                line: Some(export.location.line()),
+            });
+        }
+    }
+
+    let rules = SARIF_RULES
+        .iter()
+        .map(|(rule, description)| {
+            json!({
+                "id": rule_id(*rule),
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = results
+        .into_iter()
+        .map(|result| {
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": artifact_uri(&result.file) },
+                },
+            });
+
+            if let Some(line) = result.line {
+                location["physicalLocation"]["region"] = json!({ "startLine": line });
+            }
+
+            json!({
+                "ruleId": rule_id(result.rule),
+                "level": match config.severity(result.rule) {
+                    Severity::Error => "error",
+                    _ => "warning",
+                },
+                "message": { "text": result.message },
+                "locations": [location],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "customs",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_uris_are_relative() {
+        assert_eq!(artifact_uri(Path::new("./src/a.ts")), "src/a.ts");
+        assert_eq!(
+            artifact_uri(Path::new("packages/app/package.json")),
+            "packages/app/package.json"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn artifact_uris_of_absolute_paths() {
+        assert_eq!(
+            artifact_uri(Path::new("/repo/src/a.ts")),
+            "file:///repo/src/a.ts"
+        );
+    }
+}
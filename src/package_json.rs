@@ -1,19 +1,95 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use anyhow::anyhow;
+use rayon::prelude::*;
 use serde::Deserialize;
+use serde_json::Value;
 
-use crate::json_config::JsonConfig;
+use crate::{
+    config::Config,
+    json_config::{find_and_read_config, read_config, JsonConfig},
+};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageJson {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// Either an SPDX expression, or an object with a `type` in older packages.
+    pub license: Option<Value>,
+
     #[serde(default)]
     pub dependencies: HashMap<String, String>,
     #[serde(default)]
     pub dev_dependencies: HashMap<String, String>,
+    #[serde(default)]
+    pub peer_dependencies: HashMap<String, String>,
+    #[serde(default)]
+    pub optional_dependencies: HashMap<String, String>,
+    /// Also accepted as `bundleDependencies`.
+    #[serde(alias = "bundleDependencies")]
+    pub bundled_dependencies: Option<BundledDependencies>,
+    /// Supported versions of Node and package managers, e.g. `{ "node": ">=16" }`.
+    #[serde(default)]
+    pub engines: HashMap<String, String>,
 
     pub main: Option<String>,
+    pub types: Option<String>,
+    pub typings: Option<String>,
     pub style: Option<String>,
+
+    pub workspaces: Option<Workspaces>,
+
+    /// npm overrides, where values are either versions or nested overrides.
+    #[serde(default)]
+    pub overrides: HashMap<String, Value>,
+    /// Yarn resolutions, keyed by package name or a path like `**/foo`.
+    #[serde(default)]
+    pub resolutions: HashMap<String, String>,
+    #[serde(default)]
+    pub pnpm: Option<PnpmSettings>,
+
+    /// The `exports` map. Only the root entry is currently used.
+    pub exports: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct PnpmSettings {
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
+}
+
+/// Dependencies which are included in the published package, either as a list or `true` for all dependencies.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum BundledDependencies {
+    All(bool),
+    List(Vec<String>),
+}
+
+/// Workspace package globs, either as a plain list or in the Yarn `{ "packages": [...] }` format.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Workspaces {
+    List(Vec<String>),
+    Object {
+        #[serde(default)]
+        packages: Vec<String>,
+    },
+}
+
+impl Workspaces {
+    pub fn patterns(&self) -> &[String] {
+        match self {
+            Workspaces::List(patterns) => patterns,
+            Workspaces::Object { packages } => packages,
+        }
+    }
 }
 
 impl JsonConfig for PackageJson {
@@ -21,3 +97,329 @@ impl JsonConfig for PackageJson {
         "package.json"
     }
 }
+
+impl PackageJson {
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.dependencies.contains_key(name)
+            || self.dev_dependencies.contains_key(name)
+            || self.optional_dependencies.contains_key(name)
+    }
+
+    pub fn bundled_dependency_names(&self) -> Vec<&str> {
+        match &self.bundled_dependencies {
+            Some(BundledDependencies::List(names)) => names.iter().map(String::as_str).collect(),
+            Some(BundledDependencies::All(true)) => {
+                self.dependencies.keys().map(String::as_str).collect()
+            }
+            Some(BundledDependencies::All(false)) | None => Vec::new(),
+        }
+    }
+
+    /// The file exported as the package root in the `exports` map, preferring type declarations.
+    pub fn root_export_entry(&self) -> Option<&str> {
+        let root_export = match self.exports.as_ref()? {
+            Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => map.get(".")?,
+            exports => exports,
+        };
+
+        match root_export {
+            Value::String(entry) => Some(entry),
+            Value::Object(conditions) => ["types", "import", "default", "require"]
+                .iter()
+                .find_map(|condition| conditions.get(*condition)?.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The entries of the package in the order they're preferred: the root of the exports map, types and main.
+    pub fn declared_entries(&self) -> impl Iterator<Item = &str> {
+        self.root_export_entry()
+            .into_iter()
+            .chain(self.types.as_deref())
+            .chain(self.typings.as_deref())
+            .chain(self.main.as_deref())
+    }
+
+    pub fn has_dependency_with_prefix(&self, prefix: &str) -> bool {
+        self.dependencies
+            .keys()
+            .chain(self.dev_dependencies.keys())
+            .any(|name| name.starts_with(prefix))
+    }
+
+    /// Names of the packages whose version is overridden with `overrides`, `resolutions` or `pnpm.overrides`.
+    pub fn overridden_packages(&self) -> HashSet<String> {
+        let mut packages = HashSet::new();
+        let mut npm_overrides = self.overrides.iter().collect::<Vec<_>>();
+
+        while let Some((key, value)) = npm_overrides.pop() {
+            // "." sets the version of the package containing the nested overrides
+            if key != "." {
+                packages.insert(override_package_name(key));
+            }
+
+            if let Value::Object(nested) = value {
+                npm_overrides.extend(nested.iter());
+            }
+        }
+
+        let yarn_and_pnpm_overrides = self
+            .resolutions
+            .keys()
+            .chain(self.pnpm.iter().flat_map(|pnpm| pnpm.overrides.keys()));
+
+        for key in yarn_and_pnpm_overrides {
+            packages.insert(override_package_name(key));
+        }
+
+        packages
+    }
+
+    pub fn license_name(&self) -> Option<&str> {
+        match self.license.as_ref()? {
+            Value::String(license) => Some(license),
+            Value::Object(license) => license.get("type")?.as_str(),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the overridden package from keys like `foo`, `foo@1.0.0`, `**/foo`, `parent>foo` or `@scope/foo@^2`.
+fn override_package_name(key: &str) -> String {
+    // The last segment of a path or a pnpm selector is the overridden package
+    let segments = key.split(['/', '>']).collect::<Vec<_>>();
+
+    let name = match segments.as_slice() {
+        [.., scope, name] if scope.starts_with('@') => format!("{}/{}", scope, name),
+        [.., name] => name.to_string(),
+        [] => String::new(),
+    };
+
+    match name.get(1..).and_then(|rest| rest.find('@')) {
+        Some(index) => name[..index + 1].to_string(),
+        None => name,
+    }
+}
+
+/// Metadata of a dependency, read from its package.json in node_modules.
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub license: Option<String>,
+}
+
+/// Finds the installed package from the closest node_modules folder, like Node does.
+/// Returns `None` if the package isn't installed or its package.json can't be read.
+pub fn read_installed_package(root: &Path, name: &str) -> Option<InstalledPackage> {
+    let path = root
+        .ancestors()
+        .map(|folder| {
+            folder
+                .join("node_modules")
+                .join(name)
+                .join(PackageJson::file_name())
+        })
+        .find(|path| path.is_file())?;
+
+    let package_json = read_config::<PackageJson>(&path).ok()?;
+
+    Some(InstalledPackage {
+        license: package_json.license_name().map(String::from),
+        version: package_json.version,
+        description: package_json.description,
+    })
+}
+
+/// The package.json files under a folder, found in a single walk and parsed in parallel,
+/// so that analyzing several roots doesn't search for and parse them repeatedly.
+pub struct PackageJsonIndex {
+    pub root: PathBuf,
+    /// Keyed by the folder of each package.json. Files which couldn't be read or parsed have the error message.
+    packages: HashMap<PathBuf, Result<Arc<PackageJson>, String>>,
+}
+
+impl PackageJsonIndex {
+    /// Skips node_modules, and files ignored by .gitignore and the other standard filters.
+    pub fn discover(root: &Path) -> Self {
+        let paths = ignore::WalkBuilder::new(root)
+            .standard_filters(true)
+            .filter_entry(|entry| entry.file_name() != "node_modules")
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter(|entry| entry.file_name() == PackageJson::file_name())
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>();
+
+        let packages = paths
+            .into_par_iter()
+            .filter_map(|path| {
+                let package_json = read_config::<PackageJson>(&path)
+                    .map(Arc::new)
+                    .map_err(|err| format!("{:#}", err));
+
+                Some((path.parent()?.to_owned(), package_json))
+            })
+            .collect();
+
+        PackageJsonIndex {
+            root: root.to_owned(),
+            packages,
+        }
+    }
+
+    /// Folders which contain a package.json, in no particular order.
+    pub fn folders(&self) -> impl Iterator<Item = &Path> {
+        self.packages.keys().map(PathBuf::as_path)
+    }
+
+    /// The package.json in the folder, or `None` if the folder doesn't have one.
+    pub fn get(&self, folder: &Path) -> Option<anyhow::Result<Arc<PackageJson>>> {
+        self.packages
+            .get(folder)
+            .map(|package_json| package_json.clone().map_err(|message| anyhow!(message)))
+    }
+}
+
+/// Finds and reads the closest package.json from the root or its parents.
+/// Folders covered by `config.package_jsons` are looked up from the index instead of the file system.
+pub fn find_and_read_package_json(
+    config: &Config,
+) -> anyhow::Result<Option<(PathBuf, Arc<PackageJson>)>> {
+    let index = match &config.package_jsons {
+        Some(index) => index,
+        None => {
+            return Ok(find_and_read_config::<PackageJson>(&config.root)?
+                .map(|(path, package_json)| (path, Arc::new(package_json))))
+        }
+    };
+
+    for folder in config.root.ancestors() {
+        let path = folder.join(PackageJson::file_name());
+
+        let package_json = if folder.starts_with(&index.root) {
+            index.get(folder)
+        } else {
+            path.is_file()
+                .then(|| read_config::<PackageJson>(&path).map(Arc::new))
+        };
+
+        if let Some(package_json) = package_json {
+            return Ok(Some((path, package_json?)));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_export_entry() {
+        let parse = |json: &str| serde_json::from_str::<PackageJson>(json).unwrap();
+
+        let package_json = parse(r#"{ "exports": "./src/index.ts" }"#);
+        assert_eq!(package_json.root_export_entry(), Some("./src/index.ts"));
+
+        let package_json = parse(
+            r#"{ "exports": { ".": { "types": "./dist/index.d.ts", "import": "./dist/index.mjs" }, "./package.json": "./package.json" } }"#,
+        );
+        assert_eq!(package_json.root_export_entry(), Some("./dist/index.d.ts"));
+
+        let package_json = parse(r#"{ "exports": { "import": "./index.mjs" } }"#);
+        assert_eq!(package_json.root_export_entry(), Some("./index.mjs"));
+
+        let package_json = parse(r#"{ "main": "index.js" }"#);
+        assert_eq!(package_json.root_export_entry(), None);
+    }
+
+    #[test]
+    fn license_name() {
+        let parse = |json: &str| serde_json::from_str::<PackageJson>(json).unwrap();
+
+        assert_eq!(parse(r#"{ "license": "MIT" }"#).license_name(), Some("MIT"));
+        assert_eq!(
+            parse(r#"{ "license": { "type": "ISC", "url": "https://example.com" } }"#)
+                .license_name(),
+            Some("ISC")
+        );
+        assert_eq!(parse("{}").license_name(), None);
+    }
+
+    #[test]
+    fn bundled_dependency_names() {
+        let parse = |json: &str| serde_json::from_str::<PackageJson>(json).unwrap();
+
+        assert_eq!(
+            parse(r#"{ "bundleDependencies": ["a"] }"#).bundled_dependency_names(),
+            vec!["a"]
+        );
+        assert_eq!(
+            parse(r#"{ "dependencies": { "b": "1.0.0" }, "bundledDependencies": true }"#)
+                .bundled_dependency_names(),
+            vec!["b"]
+        );
+        assert!(parse("{}").bundled_dependency_names().is_empty());
+    }
+
+    #[test]
+    fn overridden_packages() {
+        let package_json = serde_json::from_str::<PackageJson>(
+            r#"{
+                "overrides": { "foo": "1.0.0", "bar@2": { ".": "2.1.0", "@scope/baz": "3.0.0" } },
+                "resolutions": { "**/qux": "1.0.0", "a/@scope/quux@^1": "1.2.0" },
+                "pnpm": { "overrides": { "parent>corge": "1.0.0" } }
+            }"#,
+        )
+        .unwrap();
+
+        let mut packages = package_json
+            .overridden_packages()
+            .into_iter()
+            .collect::<Vec<_>>();
+        packages.sort();
+
+        assert_eq!(
+            packages,
+            vec!["@scope/baz", "@scope/quux", "bar", "corge", "foo", "qux"]
+        );
+    }
+
+    #[test]
+    fn package_json_index() {
+        let root = std::env::temp_dir().join(format!("customs-packages-{}", std::process::id()));
+
+        for (folder, name) in [("", "root"), ("packages/a", "a"), ("node_modules/b", "b")] {
+            let folder = root.join(folder);
+            std::fs::create_dir_all(&folder).unwrap();
+            std::fs::write(
+                folder.join("package.json"),
+                format!(r#"{{ "name": "{}" }}"#, name),
+            )
+            .unwrap();
+        }
+
+        let index = Arc::new(PackageJsonIndex::discover(&root));
+        assert_eq!(index.folders().count(), 2);
+
+        let find_name = |folder: &str| {
+            let config = Config {
+                root: Arc::new(root.join(folder)),
+                package_jsons: Some(index.clone()),
+                ..Config::default()
+            };
+
+            find_and_read_package_json(&config)
+                .unwrap()
+                .and_then(|(_, package_json)| package_json.name.clone())
+        };
+
+        assert_eq!(find_name("packages/a/src").as_deref(), Some("a"));
+        assert_eq!(find_name("packages").as_deref(), Some("root"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
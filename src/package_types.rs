@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use relative_path::RelativePath;
+use swc_ecma_visit::Visit;
+
+use crate::{
+    dependency_graph::{ExportKind, ExportName, ModuleKind},
+    json_config::read_config,
+    module_visitor::ModuleVisitor,
+    package_json::PackageJson,
+    parsing::module_from_file,
+};
+
+/// The kinds of the exports declared in the type declarations of an installed package.
+pub type PackageExportKinds = HashMap<ExportName, ExportKind>;
+
+fn find_package_folder(root: &Path, package_name: &str) -> Option<PathBuf> {
+    root.ancestors()
+        .flat_map(|folder| {
+            [
+                folder.join("node_modules").join(package_name),
+                folder
+                    .join("node_modules")
+                    .join("@types")
+                    .join(package_name.trim_start_matches('@').replace('/', "__")),
+            ]
+        })
+        .find(|folder| folder.join("package.json").is_file())
+}
+
+fn find_type_entry(package_folder: &Path) -> anyhow::Result<PathBuf> {
+    let package_json = read_config::<PackageJson>(&package_folder.join("package.json"))?;
+
+    let entry = package_json
+        .types
+        .or(package_json.typings)
+        .unwrap_or_else(|| String::from("index.d.ts"));
+
+    let entry_path = RelativePath::new(&entry).to_logical_path(package_folder);
+
+    if entry_path.is_file() {
+        Ok(entry_path)
+    } else {
+        Ok(entry_path.join("index.d.ts"))
+    }
+}
+
+/// Parses the entry point declaration file of an installed package and collects the kinds of its exports.
+/// If the file declares the package as an ambient module (`declare module "name"`), the exports of that block are used.
+/// Re-exports within the declaration file are not followed.
+pub fn read_package_export_kinds(
+    root: &Path,
+    package_name: &str,
+) -> anyhow::Result<PackageExportKinds> {
+    let package_folder = find_package_folder(root, package_name)
+        .with_context(|| format!("Failed to find package {} in node_modules", package_name))?;
+
+    let entry_path = find_type_entry(&package_folder)?;
+
+    let (source_map, module) = module_from_file(&entry_path, ModuleKind::DTS)
+        .with_context(|| format!("Failed to parse {}", entry_path.display()))?;
+
+    let mut visitor = ModuleVisitor::new(entry_path, source_map);
+    visitor.visit_module(&module, &module);
+
+    Ok(declared_export_kinds(&visitor, package_name))
+}
+
+/// The kinds of the exports of a visited declaration file, or of its `declare module "name"` block.
+/// Only a block or a file without any exports or imports exports all of its declarations implicitly.
+fn declared_export_kinds(visitor: &ModuleVisitor, package_name: &str) -> PackageExportKinds {
+    let (scope, exports, exports_implicitly) = match visitor
+        .ambient_modules
+        .iter()
+        .find(|module| module.name == package_name)
+    {
+        Some(module) => (
+            visitor.get_scope(module.scope),
+            &module.exports,
+            module.exports.is_empty(),
+        ),
+        None => (
+            &visitor.scopes[0],
+            &visitor.exports,
+            !visitor.has_module_syntax(),
+        ),
+    };
+
+    let mut kinds = PackageExportKinds::new();
+
+    if exports_implicitly {
+        for name in scope.type_bindings.keys() {
+            kinds.insert(ExportName::Named(name.clone()), ExportKind::Type);
+        }
+
+        for name in scope.bindings.keys() {
+            // A name with both a type and a value declaration could be imported as either
+            let kind = match kinds.contains_key(&ExportName::Named(name.clone())) {
+                true => ExportKind::Unknown,
+                false => ExportKind::Value,
+            };
+            kinds.insert(ExportName::Named(name.clone()), kind);
+        }
+    }
+
+    for export in exports {
+        let kind = match (export.kind, &export.local_name) {
+            // export { Foo } is ambiguous, but the bindings of the module scope tell us what it refers to
+            (ExportKind::Unknown, Some(local_name)) => {
+                match (
+                    scope.bindings.contains_key(local_name),
+                    scope.type_bindings.contains_key(local_name),
+                ) {
+                    (true, false) => ExportKind::Value,
+                    (false, true) => ExportKind::Type,
+                    _ => ExportKind::Unknown,
+                }
+            }
+            (kind, _) => kind,
+        };
+
+        kinds.insert(export.name.clone(), kind);
+    }
+
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::parse_and_visit;
+
+    fn kinds(source: &'static str) -> Vec<(String, ExportKind)> {
+        let visitor = parse_and_visit("index.d.ts", source);
+        let mut kinds = declared_export_kinds(&visitor, "package")
+            .into_iter()
+            .map(|(name, kind)| (name.to_string(), kind))
+            .collect::<Vec<_>>();
+        kinds.sort_by(|(a, _), (b, _)| a.cmp(b));
+        kinds
+    }
+
+    #[test]
+    fn local_types_are_not_exports() {
+        let source = r#"
+            interface Internal {}
+            type Options = {};
+            declare const value: number;
+            export { value, Options };
+            export interface Props {}
+        "#;
+
+        assert_eq!(
+            kinds(source),
+            vec![
+                (String::from("Options"), ExportKind::Type),
+                (String::from("Props"), ExportKind::Type),
+                (String::from("value"), ExportKind::Value),
+            ]
+        );
+    }
+
+    #[test]
+    fn ambient_module_exports() {
+        let with_exports = r#"
+            declare module "package" {
+                interface Internal {}
+                export interface Props {}
+            }
+        "#;
+        let without_exports = r#"
+            declare module "package" {
+                interface Props {}
+                function render(): void;
+            }
+        "#;
+
+        assert_eq!(
+            kinds(with_exports),
+            vec![(String::from("Props"), ExportKind::Type)]
+        );
+        assert_eq!(
+            kinds(without_exports),
+            vec![
+                (String::from("Props"), ExportKind::Type),
+                (String::from("render"), ExportKind::Value),
+            ]
+        );
+    }
+
+    #[test]
+    fn function_declarations() {
+        let source = r#"
+            export function get(id: string): string;
+            export function get(id: number): string;
+            export declare const config: typeof import("./config");
+        "#;
+
+        assert_eq!(
+            kinds(source),
+            vec![
+                (String::from("config"), ExportKind::Value),
+                (String::from("get"), ExportKind::Value),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_installed_declarations() {
+        let root =
+            std::env::temp_dir().join(format!("customs-package-types-{}", std::process::id()));
+        let package_folder = root.join("node_modules/package");
+        std::fs::create_dir_all(package_folder.join("dist")).unwrap();
+        std::fs::write(
+            package_folder.join("package.json"),
+            r#"{ "name": "package", "types": "dist/index.d.ts" }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            package_folder.join("dist/index.d.ts"),
+            "type Local = string;\nexport type Id = Local;\nexport declare function get(): Id;\n",
+        )
+        .unwrap();
+
+        let kinds = read_package_export_kinds(&root, "package").unwrap();
+
+        assert_eq!(kinds.len(), 2);
+        assert_eq!(
+            kinds.get(&ExportName::Named("Id".into())),
+            Some(&ExportKind::Type)
+        );
+        assert_eq!(
+            kinds.get(&ExportName::Named("get".into())),
+            Some(&ExportKind::Value)
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
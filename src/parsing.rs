@@ -4,7 +4,11 @@ use std::{
     ops::Deref,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
 };
 
 use anyhow::{anyhow, Context};
@@ -12,6 +16,7 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use rayon::prelude::*;
 use regex::Regex;
+use tracing::{error, warn};
 
 use swc_atoms::JsWord;
 use swc_common::{FileName, FilePathMapping, SourceFile, SourceMap};
@@ -19,15 +24,19 @@ use swc_ecma_parser::StringInput;
 use swc_ecma_visit::Visit;
 
 use crate::{
-    config::Config,
+    ast_utils::is_reexport_only_module,
+    config::{Config, ModuleExtension, BYTES_PER_MEGABYTE},
+    customs_config::{build_glob_matcher, build_ignore_overrides},
     dependency_graph::{
-        normalize_module_path, resolve_import_source, Export, ExportName, Module, ModuleKind,
-        ModulePath, NormalizedImportSource, NormalizedModulePath, Usage, Visibility,
+        normalize_module_path, resolve_import_source, Export, ExportName, ImportKind, ImportName,
+        Module, ModuleKind, ModulePath, ModuleSourceAndLine, NormalizedImportSource,
+        NormalizedModulePath, PackageUsage, UnusedTypeParameter, Visibility,
     },
-    module_visitor::{ModuleImport, ModuleVisitor},
+    module_visitor::{ModuleImport, ModuleVisitor, ScopeId},
+    progress::Progress,
 };
 
-fn normalize_package_import(import_source: &str) -> Option<String> {
+pub fn normalize_package_import(import_source: &str) -> Option<String> {
     lazy_static! {
         // Parses the package name from an import source as capture group #1
         static ref PACKAGE_NAME_RE: Regex = Regex::new("((:?@[^/]+/[^/]+)|(:?[^@^/]*)).*").unwrap();
@@ -47,12 +56,36 @@ fn parse_imports(
             let module_name =
                 normalize_package_import(&name).context("Failed to normalize package import")?;
             module.imported_packages.insert(module_name);
+            module
+                .package_imports
+                .entry(name)
+                .or_default()
+                .extend(imports.into_iter().map(|import| import.imported_name));
             return Ok(());
         }
         NormalizedImportSource::Local(path) => path,
+        NormalizedImportSource::ExternalLocal(path)
+        | NormalizedImportSource::AssetVariant(path) => {
+            module.imported_external_modules.insert(path);
+            return Ok(());
+        }
+        NormalizedImportSource::Unresolved(path) => {
+            module.unresolved_imports.insert(path);
+            return Ok(());
+        }
     };
 
-    // TODO: handle CSS & other non-code imports
+    let import_kinds = module
+        .import_kinds
+        .entry(normalized_module_path.clone())
+        .or_default();
+
+    for import in &imports {
+        import_kinds
+            .entry(import.imported_name.clone())
+            .or_default()
+            .insert(import.kind);
+    }
 
     let import_names = imports.into_iter().map(|import| import.imported_name);
 
@@ -66,9 +99,45 @@ fn parse_imports(
 pub fn module_from_file(
     file_path: &Path,
     module_kind: ModuleKind,
+) -> anyhow::Result<(SourceMap, swc_ecma_ast::Module)> {
+    let source =
+        read_source(file_path)?.map_err(|reason| anyhow!("{} {}", file_path.display(), reason))?;
+
+    module_from_path_and_source(file_path, source, module_kind)
+}
+
+/// Reads a source file. The inner error describes why the file can't be decoded, see `decode_source`.
+fn read_source(file_path: &Path) -> anyhow::Result<Result<String, &'static str>> {
+    let bytes = std::fs::read(file_path)
+        .with_context(|| format!("Failed to read {}", file_path.display()))?;
+
+    Ok(decode_source(bytes))
+}
+
+/// Decodes the contents of a source file as UTF-8, without a byte order mark.
+/// Other encodings aren't supported, so they are returned as an error describing the encoding.
+pub fn decode_source(mut bytes: Vec<u8>) -> Result<String, &'static str> {
+    const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Err("is encoded as UTF-16LE");
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Err("is encoded as UTF-16BE");
+    } else if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    }
+
+    String::from_utf8(bytes).map_err(|_| "is not valid UTF-8")
+}
+
+/// Parses already read source code, keeping the file path in the source map.
+fn module_from_path_and_source(
+    file_path: &Path,
+    source: String,
+    module_kind: ModuleKind,
 ) -> anyhow::Result<(SourceMap, swc_ecma_ast::Module)> {
     let source_map = SourceMap::new(FilePathMapping::empty());
-    let source_file = source_map.load_file(file_path)?;
+    let source_file = source_map.new_source_file(FileName::Real(file_path.to_owned()), source);
     let module = module_from_source_file(&source_file, module_kind)?;
 
     Ok((source_map, module))
@@ -91,10 +160,11 @@ pub fn module_from_source_file(
 ) -> anyhow::Result<swc_ecma_ast::Module> {
     use swc_ecma_parser::{lexer::Lexer, Parser, Syntax, TsConfig};
 
+    // The lexer skips a hashbang (`#!/usr/bin/env node`) on the first line, so executable scripts parse as is
     let input = StringInput::from(source_file.deref());
 
     let tsconfig = TsConfig {
-        decorators: false,
+        decorators: true,
         dynamic_import: false,
         import_assertions: false,
         no_early_errors: true,
@@ -118,39 +188,232 @@ pub fn module_from_source_file(
     Ok(module)
 }
 
+/// Whether the root scope bindings of an export are referenced in a scope where they aren't shadowed.
+/// Value and type bindings shadow separately, e.g. a nested type alias doesn't hide a value from value references.
+/// Ambiguous references (like `export { Foo }`) are credited to both the value and the type binding they could refer to.
 fn is_shadowed_export_used(module_visitor: &ModuleVisitor, identifier: &JsWord) -> bool {
     let root_scope = &module_visitor.scopes[0];
-    let mut stack = vec![root_scope];
+    let has_value = root_scope.bindings.contains_key(identifier);
+    let has_type = root_scope.type_bindings.contains_key(identifier);
 
-    while let Some(scope) = stack.pop() {
-        if scope.bindings.contains_key(identifier) || scope.type_bindings.contains_key(identifier) {
-            continue;
+    // Each entry tracks whether the value and the type binding are visible in the scope
+    let mut stack = vec![(root_scope, has_value, has_type)];
+
+    while let Some((scope, value_visible, type_visible)) = stack.pop() {
+        if (value_visible && scope.references.contains(identifier))
+            || (type_visible && scope.type_references.contains(identifier))
+            || ((value_visible || type_visible) && scope.ambiguous_references.contains(identifier))
+        {
+            return true;
+        }
+
+        for child in &scope.children {
+            let child = module_visitor.get_scope(*child);
+            let value_visible = value_visible && !child.bindings.contains_key(identifier);
+            let type_visible = type_visible && !child.type_bindings.contains_key(identifier);
+
+            if value_visible || type_visible {
+                stack.push((child, value_visible, type_visible));
+            }
         }
+    }
+
+    false
+}
+
+fn is_type_param_used(module_visitor: &ModuleVisitor, scope: ScopeId, identifier: &JsWord) -> bool {
+    let mut stack = vec![module_visitor.get_scope(scope)];
 
-        if scope.references.contains(identifier) || scope.type_references.contains(identifier) {
+    while let Some(scope) = stack.pop() {
+        if scope.type_references.contains(identifier) {
             return true;
         }
 
         for child in &scope.children {
-            stack.push(&module_visitor.get_scope(*child));
+            let child = module_visitor.get_scope(*child);
+
+            // Skip scopes where the type parameter is shadowed
+            if !child.type_bindings.contains_key(identifier) {
+                stack.push(child);
+            }
         }
     }
 
     false
 }
 
+fn find_unused_type_parameters(module_visitor: &ModuleVisitor) -> Vec<UnusedTypeParameter> {
+    module_visitor
+        .generic_declarations
+        .iter()
+        .filter(|declaration| {
+            module_visitor
+                .exports
+                .iter()
+                .any(|export| export.local_name.as_ref() == Some(&declaration.name))
+        })
+        .flat_map(|declaration| {
+            declaration
+                .type_params
+                .iter()
+                .filter(|(name, _)| !is_type_param_used(module_visitor, declaration.scope, name))
+                .map(move |(name, location)| UnusedTypeParameter {
+                    declaration: declaration.name.clone(),
+                    name: name.clone(),
+                    location: location.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Identifiers which are referenced as values (or ambiguously) in any scope.
+fn value_references(visitor: &ModuleVisitor) -> HashSet<&JsWord> {
+    visitor
+        .scopes
+        .iter()
+        .flat_map(|scope| {
+            scope
+                .references
+                .iter()
+                .chain(scope.ambiguous_references.iter())
+        })
+        .collect()
+}
+
+/// Lists the package imports of the module. An import is type-only if it's declared with `import type`,
+/// or if none of its bindings are referenced as values.
+fn find_package_usages(visitor: &ModuleVisitor) -> Vec<PackageUsage> {
+    let value_references = value_references(visitor);
+
+    visitor
+        .import_declarations
+        .iter()
+        .filter(|declaration| !declaration.source.starts_with('.'))
+        .filter_map(|declaration| {
+            let package = normalize_package_import(&declaration.source)?;
+
+            let type_only = declaration.type_only
+                || (!declaration.local_bindings.is_empty()
+                    && declaration
+                        .local_bindings
+                        .iter()
+                        .all(|binding| !value_references.contains(binding)));
+
+            Some(PackageUsage {
+                package,
+                type_only,
+                location: declaration.location.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Imported bindings which aren't referenced as values, so they're erased at runtime.
+fn find_type_only_bindings(visitor: &ModuleVisitor) -> HashSet<JsWord> {
+    let value_references = value_references(visitor);
+
+    visitor
+        .imports
+        .values()
+        .flatten()
+        .filter_map(|import| import.local_binding.as_ref())
+        .filter(|binding| !value_references.contains(binding))
+        .cloned()
+        .collect()
+}
+
+/// Replaces named imports which are only used for accessing properties, like `Api.get()`, with the accessed members.
+/// This way the unused members of imported namespaces can be found.
+fn expand_member_imports(
+    imports: Vec<ModuleImport>,
+    property_accesses: &HashMap<JsWord, HashSet<JsWord>>,
+    direct_references: &HashSet<JsWord>,
+) -> Vec<ModuleImport> {
+    imports
+        .into_iter()
+        .flat_map(|import| {
+            let members = match (&import.imported_name, &import.local_binding) {
+                (ImportName::Named(name), Some(local)) if !direct_references.contains(local) => {
+                    property_accesses
+                        .get(local)
+                        .map(|members| (name.clone(), members))
+                }
+                _ => None,
+            };
+
+            match members {
+                Some((namespace, members)) => members
+                    .iter()
+                    .sorted()
+                    .map(|member| ModuleImport {
+                        imported_name: ImportName::Member(namespace.clone(), member.clone()),
+                        local_binding: import.local_binding.clone(),
+                        kind: ImportKind::NamespaceMember,
+                    })
+                    .collect(),
+                None => vec![import],
+            }
+        })
+        .collect()
+}
+
+/// Parses a module with `parse`. TS modules which fail to parse are retried as TSX, since some projects keep JSX
+/// in `.ts` files. Returns the parsed module and the kind it was parsed as.
+pub fn parse_with_jsx_fallback<T>(
+    module_kind: ModuleKind,
+    parse: impl Fn(ModuleKind) -> anyhow::Result<T>,
+) -> anyhow::Result<(T, ModuleKind)> {
+    match parse(module_kind) {
+        Ok(parsed) => Ok((parsed, module_kind)),
+        Err(err) if module_kind == ModuleKind::TS => match parse(ModuleKind::TSX) {
+            Ok(parsed) => Ok((parsed, ModuleKind::TSX)),
+            // The original error is more relevant, since the file is supposed to be TS
+            Err(_) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns `None` if the file isn't UTF-8, since it can't be analyzed.
 fn read_and_parse_module(
-    root: Arc<PathBuf>,
+    config: &Config,
     file_path: &Path,
     module_kind: ModuleKind,
-) -> anyhow::Result<Module> {
-    let (source_map, module_ast) = module_from_file(file_path, module_kind)?;
+) -> anyhow::Result<Option<Module>> {
+    let root = config.root.clone();
+
+    let source = match read_source(file_path)? {
+        Ok(source) => source,
+        Err(reason) => {
+            warn!(
+                "Skipping {}, since it {}. Only UTF-8 is supported.",
+                file_path.display(),
+                reason
+            );
+            return Ok(None);
+        }
+    };
+
+    let ((source_map, module_ast), parsed_kind) = parse_with_jsx_fallback(module_kind, |kind| {
+        module_from_path_and_source(file_path, source.clone(), kind)
+    })?;
+
+    if parsed_kind != module_kind {
+        warn!(
+            "{} failed to parse as {:?}, but parsed as {:?}. It probably contains JSX.",
+            file_path.display(),
+            module_kind,
+            parsed_kind
+        );
+    }
+
+    let module_kind = parsed_kind;
 
-    let normalized_path = normalize_module_path(&root, &file_path)?;
+    let normalized_path = normalize_module_path(&root, file_path)?;
 
     let file_path = Arc::new(file_path.to_path_buf());
 
-    let module = Module::new(
+    let mut module = Module::new(
         ModulePath {
             root,
             root_relative: file_path,
@@ -158,14 +421,142 @@ fn read_and_parse_module(
         },
         module_kind,
     );
+    module.only_reexports = is_reexport_only_module(&module_ast);
+    // Comments after the last statement are not counted
+    module.line_count = source_map
+        .lookup_line(module_ast.span.hi())
+        .map_or(0, |source_and_line| source_and_line.line + 1);
+
+    let source = source_map
+        .lookup_char_pos(module_ast.span.lo())
+        .file
+        .src
+        .clone();
+    module.has_dynamic_exports = has_dynamic_exports_comment(&source);
+    module.ignore_comments = ignore_comment_lines(&source);
+    module.is_generated = config.detect_generated && has_generated_header(&source);
+
+    // JSX can only be used in TSX modules
+    let jsx_runtime_package = match module_kind {
+        ModuleKind::TSX => config.compiler_options.jsx_runtime_package(&source),
+        _ => None,
+    };
 
     let mut visitor = ModuleVisitor::new(module.path.root_relative.clone(), source_map);
+    visitor.set_defines(config.defines.clone());
+    visitor.set_emit_decorator_metadata(config.compiler_options.emit_decorator_metadata);
+    visitor.visit_module(&module_ast, &module_ast);
+
+    let has_jsx = visitor.has_jsx;
+    let mut module = analyze_module(module, visitor, config)?;
+
+    if let Some(package) = jsx_runtime_package.filter(|_| has_jsx) {
+        add_jsx_runtime_usage(&mut module, package);
+    }
+
+    Ok(Some(module))
+}
+
+/// Parses and visits a module which isn't read from disk, e.g. one piped from an editor.
+/// The path picks the module kind and is shown in locations, but the file doesn't have to exist.
+pub fn visit_module_source(
+    path: &Path,
+    source: String,
+    config: &Config,
+) -> anyhow::Result<ModuleVisitor> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Expected a file: {}", path.display()))?;
+    let module_kind = get_module_kind(file_name, &config.extensions)
+        .ok_or_else(|| anyhow!("{} is not a supported module type", path.display()))?;
+
+    let (source_map, module_ast) = module_from_path_and_source(path, source, module_kind)?;
+
+    let mut visitor = ModuleVisitor::new(path.to_owned(), source_map);
+    visitor.set_defines(config.defines.clone());
+    visitor.set_emit_decorator_metadata(config.compiler_options.emit_decorator_metadata);
     visitor.visit_module(&module_ast, &module_ast);
 
-    analyze_module(module, visitor)
+    Ok(visitor)
+}
+
+/// Whether the source has a `customs-dynamic-exports` comment, which declares that some exports are created at runtime.
+pub(crate) fn has_dynamic_exports_comment(source: &str) -> bool {
+    lazy_static! {
+        static ref DYNAMIC_EXPORTS_RE: Regex =
+            Regex::new(r"(?m)(?://|/\*|^\s*\*)\s*customs-dynamic-exports\b").unwrap();
+    }
+
+    DYNAMIC_EXPORTS_RE.is_match(source)
+}
+
+/// Zero-based lines with a `customs-ignore` comment, which suppresses the unused exports of the next statement.
+pub(crate) fn ignore_comment_lines(source: &str) -> Vec<usize> {
+    lazy_static! {
+        static ref IGNORE_RE: Regex = Regex::new(r"(?://|/\*)\s*customs-ignore\b").unwrap();
+    }
+
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| IGNORE_RE.is_match(line))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Whether the comments at the start of the source mark it as generated, with `@generated` or
+/// `Code generated ... DO NOT EDIT`.
+pub(crate) fn has_generated_header(source: &str) -> bool {
+    lazy_static! {
+        static ref GENERATED_RE: Regex =
+            Regex::new(r"@generated\b|Code generated .*DO NOT EDIT").unwrap();
+    }
+
+    GENERATED_RE.is_match(leading_comments(source))
+}
+
+/// The comments (and a hashbang) before the first statement.
+fn leading_comments(source: &str) -> &str {
+    let mut rest = source;
+
+    if rest.starts_with("#!") {
+        rest = rest.find('\n').map_or("", |end| &rest[end..]);
+    }
+
+    loop {
+        rest = rest.trim_start();
+
+        let comment_end = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            rest.find("*/").map_or(rest.len(), |end| end + 2)
+        } else {
+            break;
+        };
+
+        rest = &rest[comment_end..];
+    }
+
+    &source[..source.len() - rest.len()]
+}
+
+/// Registers the implicit import of the JSX runtime, so that its package isn't reported as unused.
+fn add_jsx_runtime_usage(module: &mut Module, package: String) {
+    module.imported_packages.insert(package.clone());
+    module.package_usages.push(PackageUsage {
+        package,
+        type_only: false,
+        location: ModuleSourceAndLine::new(module.path.root_relative.clone(), 0),
+    });
 }
 
-pub fn analyze_module(mut module: Module, visitor: ModuleVisitor) -> anyhow::Result<Module> {
+pub fn analyze_module(
+    mut module: Module,
+    mut visitor: ModuleVisitor,
+    config: &Config,
+) -> anyhow::Result<Module> {
+    visitor.register_module_augmentations();
+
     let binding_counts = visitor
         .scopes
         .iter()
@@ -205,32 +596,76 @@ pub fn analyze_module(mut module: Module, visitor: ModuleVisitor) -> anyhow::Res
 
     let locally_used_shadowed_exports_iter = shadowed_exports
         .into_iter()
-        .filter(|export| !is_shadowed_export_used(&visitor, &export));
+        .filter(|export| is_shadowed_export_used(&visitor, export));
 
     let locally_used_exports = locally_used_exports_iter
         .chain(locally_used_shadowed_exports_iter)
         .collect::<HashSet<_>>();
 
+    module.unused_type_parameters = find_unused_type_parameters(&visitor);
+
+    // Every scope can see the root scope, so eval or with anywhere could reference any export
+    let has_dynamic_references = visitor
+        .scopes
+        .iter()
+        .any(|scope| scope.has_dynamic_references);
+
+    module.package_usages = find_package_usages(&visitor);
+
+    let type_only_bindings = find_type_only_bindings(&visitor);
+
+    // Imported bindings which aren't shadowed, with their import source and imported name
+    let imported_bindings = visitor
+        .imports
+        .iter()
+        .flat_map(|(source, imports)| {
+            imports.iter().filter_map(move |import| {
+                Some((
+                    import.local_binding.clone()?,
+                    (source.clone(), import.imported_name.clone()),
+                ))
+            })
+        })
+        .filter(|(local, _)| *binding_counts.get(local).unwrap_or(&1) == 1)
+        .collect::<HashMap<_, _>>();
+
     let ModuleVisitor {
         exports,
         mut scopes,
-        imports,
+        mut imports,
+        property_accesses,
+        direct_references,
         ..
     } = visitor;
 
     for export in exports {
-        let export_entry = Export::new(export.kind, Visibility::Exported, export.source);
+        let mut export_entry = Export::new(
+            export.name,
+            export.kind,
+            Visibility::Exported,
+            export.source,
+        );
+        // Exporting an imported binding forwards the original export, like a re-export
+        export_entry.reexported_from = export.reexported_from.or_else(|| {
+            let local_name = export.local_name.as_ref()?;
+            imported_bindings.get(local_name).cloned()
+        });
+        export_entry.line_count = export.line_count;
+        export_entry.statement = export.statement;
+        export_entry.statement_span = export.statement_span;
+        export_entry.is_ambient = export.is_ambient;
 
         if let Some(local_name) = export.local_name {
             if locally_used_exports.contains(&local_name) {
-                export_entry.usage.set(Usage {
-                    used_locally: true,
-                    used_externally: false,
-                });
+                export_entry.usage.mark_used_locally();
+            }
+
+            if has_dynamic_references && export_entry.reexported_from.is_none() {
+                export_entry.usage.mark_used_dynamically();
             }
         }
 
-        module.add_export(export.name, export_entry)
+        module.add_export(export_entry);
     }
 
     // In declaration modules all types defined in the root scope are implicitly exported
@@ -239,14 +674,12 @@ pub fn analyze_module(mut module: Module, visitor: ModuleVisitor) -> anyhow::Res
 
         for (type_binding_name, type_binding) in root_scope.type_bindings {
             let export_name = ExportName::Named(type_binding_name);
-            module.add_export(
+            module.add_export(Export::new(
                 export_name,
-                Export::new(
-                    crate::dependency_graph::ExportKind::Type,
-                    Visibility::ImplicitlyExported,
-                    type_binding.source,
-                ),
-            );
+                crate::dependency_graph::ExportKind::Type,
+                Visibility::ImplicitlyExported,
+                type_binding.source,
+            ));
         }
     }
 
@@ -257,18 +690,69 @@ pub fn analyze_module(mut module: Module, visitor: ModuleVisitor) -> anyhow::Res
         .expect("A file path should always have a parent")
         .to_owned();
 
+    for import in imports.values_mut().flatten() {
+        let is_type_only = import
+            .local_binding
+            .as_ref()
+            .is_some_and(|binding| type_only_bindings.contains(binding));
+
+        if import.kind == ImportKind::Value && is_type_only {
+            import.kind = ImportKind::TypeOnly;
+        }
+    }
+
     for (unnormalized_module, imports) in imports {
-        let source =
-            resolve_import_source(&module.path.root, &current_folder, &unnormalized_module)?;
+        let imports = expand_member_imports(imports, &property_accesses, &direct_references);
+        let source = resolve_import_source(
+            &module.path.root,
+            &current_folder,
+            &unnormalized_module,
+            config,
+        )?;
+
+        if let NormalizedImportSource::Local(path) = &source {
+            for export in &mut module.exports {
+                if let Some((reexport_source, imported_name)) = &export.reexported_from {
+                    if *reexport_source == unnormalized_module {
+                        export.forwards_to = Some((path.clone(), imported_name.clone()));
+                    }
+                }
+            }
+        }
+
         parse_imports(&mut module, source, imports)?;
     }
 
     Ok(module)
 }
 
-pub fn parse_all_modules(config: &Config) -> HashMap<NormalizedModulePath, Module> {
+/// Parses all modules under the source roots in parallel. With `threads`, the modules are parsed in a thread pool of
+/// that size instead of the global one which uses every core.
+pub fn parse_all_modules(config: &Config) -> anyhow::Result<HashMap<NormalizedModulePath, Module>> {
+    match config.threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.get())
+            .build()
+            .context("Failed to create the thread pool")?
+            .install(|| parse_modules(config)),
+        None => parse_modules(config),
+    }
+}
+
+/// A file which is analyzed as a module, after ignore rules and include patterns.
+#[derive(Debug)]
+pub struct ModuleFile {
+    pub path: PathBuf,
+    /// The kind picked from the file name. A TS module which contains JSX is parsed as TSX instead.
+    pub kind: ModuleKind,
+}
+
+/// Walks the source roots for the files which are analyzed as modules.
+pub fn find_module_files(
+    config: &Config,
+) -> anyhow::Result<impl Iterator<Item = ModuleFile> + Send + '_> {
     // This is kind of nasty: filter_entry wants a static closure, and this is the easiest way to to do that.
-    // We leak a bit of memory (up to a few hundred bytes), but as long as this function is only ran once per process it's not an issue.
+    // We leak a bit of memory (up to a few hundred bytes), but as long as this function is only ran once per project it's not an issue.
     // If we _really_ wanted to clean this up we could use a bit of unsafe to "unleak" the vector, based on the assumption
     // that walker does not hold onto any references after iteration is finished.
     // Alternatively we could filter after directory walking, but doing it earlier should more efficient.
@@ -276,10 +760,18 @@ pub fn parse_all_modules(config: &Config) -> HashMap<NormalizedModulePath, Modul
     let leaked_ignored_folders = &*ignored_folders.leak::<'static>();
 
     let root = config.root.as_ref();
+    let overrides = build_ignore_overrides(root, &config.ignore_patterns)?;
+    let includes = build_glob_matcher(root, &config.include_patterns)?;
 
-    let walker = ignore::WalkBuilder::new(root)
-        .standard_filters(true)
-        .add_custom_ignore_filename(".customsignore")
+    let mut source_roots = config.source_roots.iter().map(PathBuf::as_path);
+    let mut walk_builder = config.walk_builder(source_roots.next().unwrap_or(root));
+
+    for source_root in source_roots {
+        walk_builder.add(source_root);
+    }
+
+    let walker = walk_builder
+        .overrides(overrides)
         .filter_entry(move |entry| {
             !leaked_ignored_folders
                 .iter()
@@ -287,9 +779,8 @@ pub fn parse_all_modules(config: &Config) -> HashMap<NormalizedModulePath, Modul
         })
         .build();
 
-    walker
+    let files = walker
         .into_iter()
-        .par_bridge()
         // TODO: don't silently ignore read errors?
         .filter_map(|entry| {
             entry.ok().filter(|entry| {
@@ -299,30 +790,125 @@ pub fn parse_all_modules(config: &Config) -> HashMap<NormalizedModulePath, Modul
                     .is_file()
             })
         })
-        .filter_map(|entry| {
-            let file_path = entry.path();
+        .filter_map(move |entry| {
+            let file_path = entry.into_path();
             let file_name = file_path
                 .file_name()
                 .expect("Surely every file must have a name?");
 
-            let module_kind = get_module_kind(file_name)?;
+            if !config.include_patterns.is_empty()
+                && !includes.matched(&file_path, false).is_whitelist()
+            {
+                return None;
+            }
+
+            let kind = get_module_kind(file_name, &config.extensions)?;
+
+            Some(ModuleFile {
+                path: file_path,
+                kind,
+            })
+        });
+
+    Ok(files)
+}
+
+fn parse_modules(config: &Config) -> anyhow::Result<HashMap<NormalizedModulePath, Module>> {
+    let dynamic_exports = build_glob_matcher(&config.root, &config.dynamic_exports)?;
+
+    let retained_size = AtomicUsize::new(0);
+    let budget_exceeded = AtomicBool::new(false);
+    let parse_failures = AtomicUsize::new(0);
+    let progress = Progress::new("Parsing", None, config);
+
+    let modules = find_module_files(config)?
+        .par_bridge()
+        .filter_map(|file| {
+            if config.cancellation.is_cancelled() || budget_exceeded.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let file_path = file.path;
+            let module_kind = file.kind;
+
+            let started_at = Instant::now();
+
+            match read_and_parse_module(config, &file_path, module_kind) {
+                Ok(None) => {
+                    progress.tick();
+                    None
+                }
+                Ok(Some(mut module)) => {
+                    progress.tick();
+
+                    if let Some(metrics) = &config.metrics {
+                        metrics.record_module(started_at.elapsed(), module.approximate_size());
+                    }
+
+                    module.has_dynamic_exports |= dynamic_exports
+                        .matched(&*module.path.root_relative, false)
+                        .is_whitelist();
 
-            match read_and_parse_module(config.root.clone(), &file_path, module_kind) {
-                Ok(module) => Some((module.path.normalized.clone(), module)),
+                    if let Some(max_memory) = config.max_memory {
+                        let module_size = module.approximate_size();
+                        let total_size =
+                            retained_size.fetch_add(module_size, Ordering::Relaxed) + module_size;
+
+                        if total_size > max_memory {
+                            budget_exceeded.store(true, Ordering::Relaxed);
+                        }
+                    }
+
+                    Some((module.path.normalized.clone(), module))
+                }
                 Err(err) => {
-                    eprintln!("Error while parsing {}: {}", file_path.display(), err);
+                    error!("Error while parsing {}: {}", file_path.display(), err);
+                    parse_failures.fetch_add(1, Ordering::Relaxed);
+                    progress.tick_failed();
+
+                    if let Some(metrics) = &config.metrics {
+                        metrics.record_failed_module();
+                    }
                     None
                 }
             }
         })
-        .collect()
+        .collect::<HashMap<_, _>>();
+
+    progress.finish();
+
+    if budget_exceeded.load(Ordering::Relaxed) {
+        return Err(anyhow!(
+            "Memory budget of {} MB exceeded after parsing {} modules (approximately {} MB retained). \
+            Consider ignoring large or generated folders, or raising --max-memory.",
+            config.max_memory.unwrap_or_default() / BYTES_PER_MEGABYTE,
+            modules.len(),
+            retained_size.load(Ordering::Relaxed) / BYTES_PER_MEGABYTE,
+        ));
+    }
+
+    let parse_failures = parse_failures.load(Ordering::Relaxed);
+
+    if config.strict && parse_failures > 0 {
+        return Err(anyhow!(
+            "{} modules could not be parsed. Without --strict they are left out of the analysis.",
+            parse_failures
+        ));
+    }
+
+    Ok(modules)
 }
 
-fn get_module_kind(file_name: &OsStr) -> Option<ModuleKind> {
+pub fn get_module_kind(file_name: &OsStr, extensions: &[ModuleExtension]) -> Option<ModuleKind> {
     // OsStr doesn't support ends_with and extension() doesn't work with .d.ts files, so we have to do a hack like this:
     let file_name = file_name.to_string_lossy();
 
-    if file_name.ends_with(".d.ts") {
+    if let Some(extension) = extensions
+        .iter()
+        .find(|extension| file_name.ends_with(&extension.suffix))
+    {
+        Some(extension.kind)
+    } else if file_name.ends_with(".d.ts") {
         Some(ModuleKind::DTS)
     } else if file_name.ends_with(".ts") {
         Some(ModuleKind::TS)
@@ -0,0 +1,113 @@
+//! Periodic progress of the slow phases with `--progress`, so that large monorepos aren't silent for many seconds.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tracing::info;
+
+use crate::config::Config;
+
+/// How often progress is logged while a phase runs.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Counts the modules a phase has processed. Shared by the threads of the phase.
+#[derive(Debug)]
+pub struct Progress {
+    phase: &'static str,
+    enabled: bool,
+    /// The number of modules to process, if it's known before the phase starts.
+    total: Option<usize>,
+    done: AtomicUsize,
+    errors: AtomicUsize,
+    last_report: Mutex<Instant>,
+}
+
+impl Progress {
+    pub fn new(phase: &'static str, total: Option<usize>, config: &Config) -> Self {
+        Progress {
+            phase,
+            enabled: config.progress,
+            total,
+            done: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn tick(&self) {
+        self.record(false);
+    }
+
+    pub fn tick_failed(&self) {
+        self.record(true);
+    }
+
+    fn record(&self, failed: bool) {
+        if !self.enabled {
+            return;
+        }
+
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if failed {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Another thread is already reporting, so there's no need to wait for it
+        let mut last_report = match self.last_report.try_lock() {
+            Ok(last_report) => last_report,
+            Err(_) => return,
+        };
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            *last_report = Instant::now();
+            info!("{}", self.message(done));
+        }
+    }
+
+    /// Logs the final counts of the phase.
+    pub fn finish(&self) {
+        if self.enabled {
+            info!("{} (done)", self.message(self.done.load(Ordering::Relaxed)));
+        }
+    }
+
+    fn message(&self, done: usize) -> String {
+        let count = match self.total {
+            Some(total) => format!("{}/{}", done, total),
+            None => done.to_string(),
+        };
+
+        match self.errors.load(Ordering::Relaxed) {
+            0 => format!("{}: {} modules", self.phase, count),
+            errors => format!("{}: {} modules, {} errors", self.phase, count, errors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_messages() {
+        let config = Config {
+            progress: true,
+            ..Config::default()
+        };
+
+        let progress = Progress::new("Parsing", None, &config);
+        progress.tick();
+        progress.tick_failed();
+        assert_eq!(progress.message(2), "Parsing: 2 modules, 1 errors");
+
+        let progress = Progress::new("Resolving imports", Some(10), &config);
+        progress.tick();
+        assert_eq!(progress.message(1), "Resolving imports: 1/10 modules");
+    }
+}
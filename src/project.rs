@@ -0,0 +1,532 @@
+//! The analysis pipeline for a single project folder, shared by the CLI modes.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use regex::Regex;
+use relative_path::RelativePath;
+use tracing::{info, warn};
+
+use crate::{
+    analysis::{
+        classify_package_reexports, find_default_export_mismatches, find_export_usages,
+        find_lockfile_problems, find_misplaced_dependencies, find_unreachable_islands,
+        find_unused_assets, find_unused_bundled_dependencies, find_unused_dependencies,
+        find_unused_exports, find_unused_overridden_dependencies, find_unused_suppressions,
+        find_unused_type_parameters, mark_entry_point_exports, mark_forwarded_exports,
+        resolve_reachable_module_imports, DefaultExportMismatch, ExportUsage, LockfileProblems,
+        MisplacedDependency, UnreachableIsland, UnusedExportsResults, UnusedSuppression,
+    },
+    baseline::{read_baseline, write_baseline, Baseline, BaselineExport},
+    config::{Config, ModuleExtension, Rule, Severity},
+    customs_config::read_customs_config,
+    dependency_graph::{Module, ModuleKind, NormalizedModulePath, UnusedTypeParameter},
+    git::{retain_exports_in_changed_files, retain_exports_older_than},
+    json_config::find_and_read_config,
+    package_json::{
+        find_and_read_package_json, read_installed_package, InstalledPackage, PackageJson,
+    },
+    package_types::{read_package_export_kinds, PackageExportKinds},
+    parsing::parse_all_modules,
+    tsconfig::TsConfig,
+    workspaces::probe_source_file,
+};
+
+/// Applies settings from tsconfig.json and customs.json to the config.
+pub fn load_project_config(config: &mut Config) -> anyhow::Result<()> {
+    let tsconfig = find_and_read_config::<TsConfig>(&config.root)?;
+
+    if let Some((path, tsconfig)) = tsconfig {
+        let mut roots = tsconfig.normalized_type_roots(&path);
+        config.ignored_folders.append(&mut roots);
+        config.compiler_options = tsconfig.project_compiler_options(&path);
+
+        if config.compiler_options.allow_js {
+            add_javascript_extensions(config);
+        }
+    }
+
+    if let Some((_, customs_config)) = read_customs_config(&config.root)? {
+        config.ignore_patterns.extend(customs_config.ignore);
+        config.include_patterns.extend(customs_config.include);
+        config.ignore_file_names.extend(customs_config.ignore_files);
+
+        // The command line flags only change the defaults, so customs.json can't undo them
+        if let Some(respect_gitignore) = customs_config.respect_gitignore {
+            config.respect_gitignore &= respect_gitignore;
+        }
+
+        if let Some(include_hidden) = customs_config.include_hidden {
+            config.include_hidden |= include_hidden;
+        }
+
+        config.entry_points.extend(customs_config.entry_points);
+        config
+            .dynamic_exports
+            .extend(customs_config.dynamic_exports);
+        config.ignored_kinds.extend(customs_config.ignore_kinds);
+
+        for pattern in &customs_config.ignore_exports {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Invalid ignoreExports pattern {}", pattern))?;
+            config.ignored_export_names.push(regex);
+        }
+
+        match customs_config.analyze {
+            Some(analyze_target) if !config.analyze_target_from_cli => {
+                config.analyze_target = analyze_target
+            }
+            _ => {}
+        }
+        config.asset_folders.extend(customs_config.asset_folders);
+        config
+            .polyfilled_builtins
+            .extend(customs_config.polyfilled_builtins);
+
+        if !customs_config.dev_files.is_empty() {
+            config.dev_files = customs_config.dev_files;
+        }
+    }
+
+    if config.entry_points.is_empty() {
+        let package_json = find_and_read_config::<PackageJson>(&config.root)?;
+        let has_package_entry = match &package_json {
+            Some((path, package_json)) if package_json.declared_entries().next().is_some() => {
+                config.entry_points = package_entry_points(path, package_json, config);
+                true
+            }
+            _ => false,
+        };
+
+        if !has_package_entry {
+            config.entry_points = infer_entry_points(config);
+
+            if !config.entry_points.is_empty() {
+                info!(
+                    "No entry points are configured for {}, so {} {} assumed. Set entryPoints in customs.json to change this.",
+                    config.root.display(),
+                    config.entry_points.join(", "),
+                    if config.entry_points.len() == 1 { "is" } else { "are" }
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The source modules of the entries declared in package.json, relative to the root.
+fn package_entry_points(
+    package_json_path: &Path,
+    package_json: &PackageJson,
+    config: &Config,
+) -> Vec<String> {
+    let folder = package_json_path.parent().unwrap_or(package_json_path);
+    let mut entry_points = Vec::new();
+
+    for entry in package_json.declared_entries() {
+        let source =
+            match probe_source_file(&RelativePath::new(entry).to_logical_path(folder), config) {
+                Some(source) => source,
+                None => continue,
+            };
+
+        if let Ok(relative) = source.strip_prefix(&*config.root) {
+            let entry_point = relative.to_string_lossy().replace('\\', "/");
+
+            if !entry_points.contains(&entry_point) {
+                entry_points.push(entry_point);
+            }
+        }
+    }
+
+    entry_points
+}
+
+/// Conventional entry modules of applications, without a suffix. Only the first group with existing modules is
+/// used, since the modules of the second group are usually imported by those of the first.
+const CONVENTIONAL_ENTRY_POINTS: &[&[&str]] = &[
+    &["src/index", "src/main", "index", "main"],
+    &["src/app", "src/server", "src/cli", "app", "server"],
+];
+
+/// Guesses the entry points of a project which configures none, by looking for conventionally named modules.
+/// Paths are relative to the root.
+fn infer_entry_points(config: &Config) -> Vec<String> {
+    let mut suffixes = vec![".ts", ".tsx"];
+
+    if config.compiler_options.allow_js {
+        suffixes.extend(JAVASCRIPT_SUFFIXES);
+    }
+
+    CONVENTIONAL_ENTRY_POINTS
+        .iter()
+        .map(|names| {
+            names
+                .iter()
+                .flat_map(|name| {
+                    suffixes
+                        .iter()
+                        .map(move |suffix| format!("{}{}", name, suffix))
+                })
+                .filter(|path| config.root.join(path).is_file())
+                .collect::<Vec<_>>()
+        })
+        .find(|entry_points| !entry_points.is_empty())
+        .unwrap_or_default()
+}
+
+/// JavaScript suffixes analyzed when tsconfig.json sets `allowJs`.
+const JAVASCRIPT_SUFFIXES: &[&str] = &[".js", ".jsx", ".mjs", ".cjs"];
+
+/// Analyzes JavaScript files like the compiler does when `allowJs` is set.
+/// They are parsed as TSX, which accepts JavaScript with JSX. Suffixes mapped with `--extension` are left as they are.
+fn add_javascript_extensions(config: &mut Config) {
+    for suffix in JAVASCRIPT_SUFFIXES {
+        if !config
+            .extensions
+            .iter()
+            .any(|extension| extension.suffix == *suffix)
+        {
+            config.extensions.push(ModuleExtension {
+                suffix: suffix.to_string(),
+                kind: ModuleKind::TSX,
+            });
+        }
+    }
+}
+
+/// Reads the type declarations of the analyzed packages. Packages which can't be read are skipped with a warning.
+pub fn load_package_export_kinds(config: &Config) -> HashMap<String, PackageExportKinds> {
+    config
+        .analyzed_packages
+        .iter()
+        .filter_map(
+            |package| match read_package_export_kinds(&config.root, package) {
+                Ok(kinds) => Some((package.clone(), kinds)),
+                Err(err) => {
+                    warn!("Failed to analyze package {}: {:#}", package, err);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// The findings of a single project.
+pub struct ProjectAnalysis {
+    pub root: PathBuf,
+    pub unused_exports: UnusedExportsResults,
+    /// `None` if the project has no package.json.
+    pub unused_dependencies: Option<Vec<String>>,
+    /// Bundled dependencies which no module imports.
+    pub unused_bundled_dependencies: Vec<String>,
+    /// Unused dependencies which are overridden in package.json, if `list_overridden_dependencies` is enabled.
+    pub unused_overridden_dependencies: Vec<String>,
+    /// `None` if the project has no package.json.
+    pub misplaced_dependencies: Option<Vec<MisplacedDependency>>,
+    /// Installed metadata of the reported dependencies, if `describe_dependencies` is enabled.
+    pub dependency_details: HashMap<String, InstalledPackage>,
+    /// `None` if the project has no package.json or lockfile.
+    pub lockfile_problems: Option<LockfileProblems>,
+    pub unused_type_parameters: Vec<UnusedTypeParameter>,
+    /// Root-relative paths of files in the asset folders which no module imports.
+    pub unused_assets: Vec<PathBuf>,
+    pub default_export_mismatches: Vec<DefaultExportMismatch>,
+    /// Modules which can't be reached from the entry points, grouped by the imports between them.
+    /// Empty if there are no entry points.
+    pub unreachable_islands: Vec<UnreachableIsland>,
+    /// Suppressions which don't hide any finding, if `report_unused_suppressions` is enabled.
+    pub unused_suppressions: Vec<UnusedSuppression>,
+    /// How the used exports are imported, if `report_export_usage` is enabled.
+    pub export_usages: Vec<ExportUsage>,
+}
+
+impl ProjectAnalysis {
+    /// The number of findings of a rule.
+    pub fn finding_count(&self, rule: Rule) -> usize {
+        match rule {
+            Rule::UnusedExports => self.unused_exports.sorted_exports.len(),
+            Rule::UnusedDependencies => {
+                self.unused_bundled_dependencies.len()
+                    + self.unused_dependencies.as_ref().map_or(0, Vec::len)
+            }
+            Rule::UnusedTypeParameters => self.unused_type_parameters.len(),
+            Rule::UnusedAssets => self.unused_assets.len(),
+            Rule::DefaultExportMismatches => self.default_export_mismatches.len(),
+            Rule::UnreachableModules => self.unreachable_islands.len(),
+            Rule::MisplacedDependencies => self.misplaced_dependencies.as_ref().map_or(0, Vec::len),
+            Rule::LockfileConsistency => self.lockfile_problems.as_ref().map_or(0, |problems| {
+                problems.missing_from_lockfile.len() + problems.phantom_imports.len()
+            }),
+        }
+    }
+
+    /// Whether any rule with the error severity has findings.
+    pub fn has_errors(&self, config: &Config) -> bool {
+        Rule::ALL_RULES
+            .iter()
+            .any(|rule| config.severity(*rule) == Severity::Error && self.finding_count(*rule) > 0)
+    }
+
+    /// The total number of findings of rules with the warn severity.
+    pub fn warning_count(&self, config: &Config) -> usize {
+        Rule::ALL_RULES
+            .iter()
+            .filter(|rule| config.severity(**rule) == Severity::Warn)
+            .map(|rule| self.finding_count(*rule))
+            .sum()
+    }
+}
+
+/// Finds unused exports, dependencies and type parameters in a module graph with resolved imports.
+pub fn analyze_module_graph(
+    modules: HashMap<NormalizedModulePath, Module>,
+    config: &Config,
+) -> anyhow::Result<ProjectAnalysis> {
+    let checks_dependencies = [
+        Rule::UnusedDependencies,
+        Rule::MisplacedDependencies,
+        Rule::LockfileConsistency,
+    ]
+    .iter()
+    .any(|rule| config.severity(*rule) != Severity::Off);
+
+    let package_json = if checks_dependencies {
+        find_and_read_package_json(config)?
+    } else {
+        None
+    };
+
+    if checks_dependencies && package_json.is_none() {
+        warn!(
+            "Failed to find package.json for {}, skipping dependency analysis.",
+            config.root.display()
+        );
+    }
+
+    let unused_dependencies = match (&package_json, config.severity(Rule::UnusedDependencies)) {
+        (_, Severity::Off) | (None, _) => None,
+        (Some((_, package_json)), _) => {
+            Some(find_unused_dependencies(&modules, package_json, config))
+        }
+    };
+
+    let unused_bundled_dependencies =
+        match (&package_json, config.severity(Rule::UnusedDependencies)) {
+            (_, Severity::Off) | (None, _) => Vec::new(),
+            (Some((_, package_json)), _) => {
+                find_unused_bundled_dependencies(&modules, package_json, config)
+            }
+        };
+
+    let unused_overridden_dependencies = match &package_json {
+        Some((_, package_json))
+            if config.list_overridden_dependencies
+                && config.severity(Rule::UnusedDependencies) != Severity::Off =>
+        {
+            find_unused_overridden_dependencies(&modules, package_json, config)
+        }
+        _ => Vec::new(),
+    };
+
+    let misplaced_dependencies = match (&package_json, config.severity(Rule::MisplacedDependencies))
+    {
+        (_, Severity::Off) | (None, _) => None,
+        (Some((_, package_json)), _) => {
+            Some(find_misplaced_dependencies(&modules, package_json, config)?)
+        }
+    };
+
+    let lockfile_problems = match (&package_json, config.severity(Rule::LockfileConsistency)) {
+        (_, Severity::Off) | (None, _) => None,
+        (Some((_, package_json)), _) => find_lockfile_problems(&modules, package_json, config)?,
+    };
+
+    let dependency_details = if config.describe_dependencies {
+        describe_dependencies(
+            unused_dependencies.iter().flatten().chain(
+                misplaced_dependencies
+                    .iter()
+                    .flatten()
+                    .map(|dependency| &dependency.name),
+            ),
+            config,
+        )
+    } else {
+        HashMap::new()
+    };
+
+    let unused_type_parameters = match config.severity(Rule::UnusedTypeParameters) {
+        Severity::Off => Vec::new(),
+        _ => find_unused_type_parameters(&modules, config),
+    };
+    let unused_assets = match config.severity(Rule::UnusedAssets) {
+        Severity::Off => Vec::new(),
+        _ => find_unused_assets(&modules, config),
+    };
+    let default_export_mismatches = match config.severity(Rule::DefaultExportMismatches) {
+        Severity::Off => Vec::new(),
+        _ => find_default_export_mismatches(&modules, config),
+    };
+    let unreachable_islands = match config.severity(Rule::UnreachableModules) {
+        Severity::Off => Vec::new(),
+        _ => find_unreachable_islands(&modules, config)?,
+    };
+    let mut unused_suppressions = if config.report_unused_suppressions {
+        find_unused_suppressions(&modules, config)
+    } else {
+        Vec::new()
+    };
+    let export_usages = if config.report_export_usage {
+        find_export_usages(&modules)
+    } else {
+        Vec::new()
+    };
+    let unused_exports = match config.severity(Rule::UnusedExports) {
+        Severity::Off => UnusedExportsResults {
+            sorted_exports: Vec::new(),
+        },
+        _ => {
+            let (unused_exports, stale_baseline_entries) =
+                narrow_unused_exports(find_unused_exports(modules, config), config)?;
+            unused_suppressions.extend(
+                stale_baseline_entries
+                    .into_iter()
+                    .map(UnusedSuppression::BaselineEntry),
+            );
+            unused_exports
+        }
+    };
+
+    Ok(ProjectAnalysis {
+        root: (*config.root).clone(),
+        unused_exports,
+        unused_dependencies,
+        unused_bundled_dependencies,
+        unused_overridden_dependencies,
+        misplaced_dependencies,
+        dependency_details,
+        lockfile_problems,
+        unused_type_parameters,
+        unused_assets,
+        default_export_mismatches,
+        unreachable_islands,
+        unused_suppressions,
+        export_usages,
+    })
+}
+
+/// Leaves out the unused exports which are too recent, in the baseline or not in changed files.
+/// Also returns the baseline entries which no longer match any unused export, if they're reported.
+fn narrow_unused_exports(
+    mut unused_exports: UnusedExportsResults,
+    config: &Config,
+) -> anyhow::Result<(UnusedExportsResults, Vec<BaselineExport>)> {
+    let baseline = if config.use_baseline && !config.update_baseline {
+        Some(read_baseline(&config.root)?)
+    } else {
+        None
+    };
+
+    // Compared to all findings, since an entry filtered out by the other options still matches a finding
+    let stale_baseline_entries = match &baseline {
+        Some(baseline) if config.report_unused_suppressions => {
+            baseline.stale_entries(&unused_exports, &config.root)
+        }
+        _ => Vec::new(),
+    };
+
+    if let Some(min_age) = config.min_age {
+        retain_exports_older_than(&mut unused_exports, min_age);
+    }
+
+    if config.update_baseline {
+        write_baseline(
+            &config.root,
+            &Baseline::from_results(&unused_exports, &config.root),
+        )?;
+    } else if let Some(baseline) = &baseline {
+        baseline.retain_new_exports(&mut unused_exports, &config.root);
+    }
+
+    // After the baseline is written, so that it covers the whole project
+    if let Some(base) = &config.changed_since {
+        retain_exports_in_changed_files(&mut unused_exports, &config.root, base)?;
+    }
+
+    Ok((unused_exports, stale_baseline_entries))
+}
+
+fn describe_dependencies<'a>(
+    names: impl Iterator<Item = &'a String>,
+    config: &Config,
+) -> HashMap<String, InstalledPackage> {
+    names
+        .filter_map(|name| {
+            let installed = read_installed_package(&config.root, name);
+
+            if installed.is_none() {
+                warn!("Failed to read the installed package.json of {}", name);
+            }
+
+            Some((name.clone(), installed?))
+        })
+        .collect()
+}
+
+/// Runs the whole analysis for the project in `config.root`, without printing progress.
+pub fn analyze_project(config: &Config) -> anyhow::Result<ProjectAnalysis> {
+    let mut modules = parse_all_modules(config)?;
+
+    if !config.analyzed_packages.is_empty() {
+        let package_export_kinds = load_package_export_kinds(config);
+        classify_package_reexports(&mut modules, &package_export_kinds);
+    }
+
+    if config.analyze_target.analyzes_exports() {
+        resolve_reachable_module_imports(&modules, config)?;
+        mark_entry_point_exports(&modules, config)?;
+        mark_forwarded_exports(&modules);
+    }
+
+    analyze_module_graph(modules, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use super::*;
+
+    #[test]
+    fn conventional_entry_points() {
+        let root = std::env::temp_dir().join(format!("customs-entries-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+
+        for file in ["src/main.tsx", "src/app.ts", "src/utils.ts"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let config = Config {
+            root: Arc::new(root.clone()),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            infer_entry_points(&config),
+            vec![String::from("src/main.tsx")]
+        );
+
+        fs::remove_file(root.join("src/main.tsx")).unwrap();
+        assert_eq!(
+            infer_entry_points(&config),
+            vec![String::from("src/app.ts")]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
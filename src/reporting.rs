@@ -1,49 +1,1097 @@
-use std::io::stdout;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fmt::Display;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::analysis::UnusedExportsResults;
-use crate::config::Config;
+use crate::analysis::{
+    is_node_builtin, DefaultExportMismatch, DependencySection, ExportExplanation, LockfileProblems,
+    MisplacedDependency, UnreachableIsland, UnusedExportStyle, UnusedExportsResults,
+    UnusedSuppression,
+};
+use crate::barrel::BarrelUpdate;
+use crate::config::{Config, HyperlinkStyle, Rule, Severity};
+use crate::config_check::ConfigProblem;
+use crate::dependency_graph::{
+    ImportName, Module, ModuleSourceAndLine, NormalizedImportSource, NormalizedModulePath,
+    ResolutionTrace, UnusedTypeParameter,
+};
+use crate::package_json::{InstalledPackage, PackageJson};
+use crate::parsing::{normalize_package_import, ModuleFile};
+use crate::project::ProjectAnalysis;
+use crate::workspaces::UnusedPackageExport;
 
 pub fn report_unused_exports(
-    UnusedExportsResults { sorted_exports }: UnusedExportsResults,
-    _config: &Config,
+    results: UnusedExportsResults,
+    config: &Config,
+    out: &mut dyn Write,
 ) -> anyhow::Result<()> {
-    if sorted_exports.is_empty() {
-        println!("No unused exports!");
+    if results.sorted_exports.is_empty() {
+        writeln!(out, "No unused exports!")?;
         return Ok(());
     }
 
-    let stdout = stdout();
-    let mut stdout = stdout.lock();
+    let deletable_lines = results.deletable_lines();
 
-    writeln!(stdout, "Unused exports:")?;
+    writeln!(
+        out,
+        "{}",
+        header("Unused exports:", Rule::UnusedExports, config)
+    )?;
 
-    for (name, location, usage) in sorted_exports {
-        write!(&mut stdout, "  {} - {}", location, name)?;
+    for statement in results.statements() {
+        if let [export] = statement.exports {
+            write!(
+                out,
+                "  {} - {}",
+                format_location(&export.location, config),
+                paint(&export.name, Style::Name, config)
+            )?;
 
-        if usage.used_locally {
-            write!(&mut stdout, " (used locally)")?;
+            if export.usage.used_locally {
+                write!(out, " (used locally)")?;
+            } else if export.line_count > 1 {
+                write!(out, " (~{} lines)", export.line_count)?;
+            }
+
+            writeln!(out)?;
+            continue;
+        }
+
+        // Exports declared by one statement are reported together, since they are fixed together
+        let names = statement
+            .exports
+            .iter()
+            .map(|export| {
+                let name = paint(&export.name, Style::Name, config);
+
+                if export.usage.used_locally {
+                    format!("{} (used locally)", name)
+                } else {
+                    name
+                }
+            })
+            .collect::<Vec<_>>();
+
+        write!(
+            out,
+            "  {} - {}",
+            format_location(statement.statement(), config),
+            names.join(", ")
+        )?;
+
+        if statement.is_deletable() && statement.line_count() > 1 {
+            write!(out, " (~{} lines)", statement.line_count())?;
         }
 
-        writeln!(&mut stdout)?;
+        writeln!(out)?;
     }
 
-    stdout.flush()?;
+    writeln!(
+        out,
+        "Estimated deletable lines: {} (statements whose exports are also unused locally)",
+        deletable_lines
+    )?;
 
     Ok(())
 }
 
-pub fn report_unused_dependencies(mut dependencies: Vec<String>, _config: &Config) {
+pub fn report_unused_dependencies(
+    mut dependencies: Vec<String>,
+    details: &HashMap<String, InstalledPackage>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
     dependencies.sort_unstable();
 
     if dependencies.is_empty() {
-        println!("No unused dependencies.");
-        return;
+        writeln!(out, "No unused dependencies.")?;
+        return Ok(());
     }
 
-    println!("Potentially unused dependencies:");
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Potentially unused dependencies:",
+            Rule::UnusedDependencies,
+            config
+        )
+    )?;
 
     for dependency in dependencies {
-        println!("  {}", dependency);
+        writeln!(
+            out,
+            "  {}{}",
+            paint(&dependency, Style::Name, config),
+            describe_dependency(&dependency, details)
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_bundled_dependencies(
+    dependencies: Vec<String>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Bundled dependencies which are not imported:",
+            Rule::UnusedDependencies,
+            config
+        )
+    )?;
+
+    for dependency in dependencies {
+        writeln!(out, "  {}", dependency)?;
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_overridden_dependencies(
+    mut dependencies: Vec<String>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    dependencies.sort_unstable();
+
+    if dependencies.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Unused dependencies which are overridden in package.json:",
+            Rule::UnusedDependencies,
+            config
+        )
+    )?;
+
+    for dependency in dependencies {
+        writeln!(out, "  {}", dependency)?;
+    }
+
+    Ok(())
+}
+
+/// Formats the installed version, license and description of a dependency, e.g. ` 1.2.3 (MIT) - A utility library`.
+fn describe_dependency(name: &str, details: &HashMap<String, InstalledPackage>) -> String {
+    let installed = match details.get(name) {
+        Some(installed) => installed,
+        None => return String::new(),
+    };
+
+    let mut description = String::new();
+
+    if let Some(version) = &installed.version {
+        description.push_str(&format!(" {}", version));
+    }
+
+    if let Some(license) = &installed.license {
+        description.push_str(&format!(" ({})", license));
+    }
+
+    if let Some(text) = &installed.description {
+        description.push_str(&format!(" - {}", text));
+    }
+
+    description
+}
+
+pub fn report_misplaced_dependencies(
+    dependencies: Vec<MisplacedDependency>,
+    details: &HashMap<String, InstalledPackage>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if dependencies.is_empty() {
+        writeln!(out, "No misplaced dependencies.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Misplaced dependencies:",
+            Rule::MisplacedDependencies,
+            config
+        )
+    )?;
+
+    for dependency in dependencies {
+        writeln!(
+            out,
+            "  {}{}",
+            paint(&dependency.name, Style::Name, config),
+            describe_dependency(&dependency.name, details)
+        )?;
+
+        match dependency.move_to {
+            DependencySection::DevDependencies => writeln!(
+                out,
+                "    Move from dependencies to devDependencies, it is only used as types or in dev files:"
+            )?,
+            DependencySection::Dependencies => writeln!(
+                out,
+                "    Move from devDependencies to dependencies, it is used at runtime:"
+            )?,
+        }
+
+        for usage in dependency.evidence {
+            let location = format_location(&usage.location, config);
+
+            if usage.type_only {
+                writeln!(out, "      {} (types only)", location)?;
+            } else {
+                writeln!(out, "      {}", location)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn report_lockfile_problems(
+    problems: LockfileProblems,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let LockfileProblems {
+        lockfile,
+        missing_from_lockfile,
+        phantom_imports,
+    } = problems;
+
+    if missing_from_lockfile.is_empty() && phantom_imports.is_empty() {
+        writeln!(
+            out,
+            "package.json and imports match {}.",
+            lockfile.display()
+        )?;
+        return Ok(());
+    }
+
+    if !missing_from_lockfile.is_empty() {
+        let title = format!("Dependencies missing from {}:", lockfile.display());
+        writeln!(out, "{}", header(&title, Rule::LockfileConsistency, config))?;
+
+        for dependency in missing_from_lockfile {
+            writeln!(out, "  {}", dependency)?;
+        }
+    }
+
+    if !phantom_imports.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            header(
+                "Imported packages which are only installed through other dependencies:",
+                Rule::LockfileConsistency,
+                config
+            )
+        )?;
+
+        for usage in phantom_imports {
+            writeln!(
+                out,
+                "  {} - {}",
+                format_location(&usage.location, config),
+                usage.package
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_type_parameters(
+    type_parameters: Vec<UnusedTypeParameter>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if type_parameters.is_empty() {
+        writeln!(out, "No unused type parameters.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Unused type parameters:",
+            Rule::UnusedTypeParameters,
+            config
+        )
+    )?;
+
+    for type_parameter in type_parameters {
+        writeln!(
+            out,
+            "  {} - {} in {}",
+            format_location(&type_parameter.location, config),
+            paint(&type_parameter.name, Style::Name, config),
+            type_parameter.declaration
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_assets(
+    assets: Vec<PathBuf>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if assets.is_empty() {
+        writeln!(out, "No unused assets.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Assets not imported by any module:",
+            Rule::UnusedAssets,
+            config
+        )
+    )?;
+
+    for asset in assets {
+        writeln!(out, "  {}", format_path(&asset, config))?;
+    }
+
+    Ok(())
+}
+
+pub fn report_unreachable_islands(
+    islands: Vec<UnreachableIsland>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if islands.is_empty() {
+        writeln!(out, "No unreachable modules.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Modules not reachable from the entry points:",
+            Rule::UnreachableModules,
+            config
+        )
+    )?;
+
+    for island in islands {
+        if let [module] = island.modules.as_slice() {
+            writeln!(
+                out,
+                "  {} (~{} lines)",
+                format_path(module, config),
+                island.line_count
+            )?;
+            continue;
+        }
+
+        writeln!(
+            out,
+            "  {} modules which only import each other (~{} lines):",
+            island.modules.len(),
+            island.line_count
+        )?;
+
+        for module in island.modules {
+            writeln!(out, "    {}", format_path(&module, config))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn report_default_export_mismatches(
+    mismatches: Vec<DefaultExportMismatch>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Modules whose consumers only use the default export or only the named exports:",
+            Rule::DefaultExportMismatches,
+            config
+        )
+    )?;
+
+    for mismatch in mismatches {
+        let importers = match mismatch.importer_count {
+            1 => String::from("1 module uses"),
+            count => format!("{} modules use", count),
+        };
+
+        match mismatch.unused {
+            UnusedExportStyle::Default => writeln!(
+                out,
+                "  {} - the default export is never imported, {} named imports",
+                format_path(&mismatch.module, config),
+                importers
+            )?,
+            UnusedExportStyle::Named => writeln!(
+                out,
+                "  {} - the named exports are never imported, {} the default import",
+                format_path(&mismatch.module, config),
+                importers
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_suppressions(
+    suppressions: Vec<UnusedSuppression>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if suppressions.is_empty() {
+        writeln!(out, "No unused suppressions.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        paint(
+            "Suppressions which no longer hide any finding:",
+            Style::Heading,
+            config
+        )
+    )?;
+
+    for suppression in suppressions {
+        match suppression {
+            UnusedSuppression::IgnoredKind(filter) => writeln!(
+                out,
+                "  ignoreKinds entry {}",
+                serde_json::to_string(&filter).unwrap_or_default()
+            )?,
+            UnusedSuppression::IgnoreComment(location) => writeln!(
+                out,
+                "  customs-ignore comment at {}",
+                format_location(&location, config)
+            )?,
+            UnusedSuppression::BaselineEntry(entry) => writeln!(
+                out,
+                "  baseline entry {} in {}",
+                paint(&entry.name, Style::Name, config),
+                entry.file
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+pub fn report_export_explanation(explanation: ExportExplanation, config: &Config) {
+    let ExportExplanation {
+        module,
+        name,
+        kind,
+        location,
+        usage,
+        reexported_from,
+        importers,
+        is_wildcard_imported,
+    } = explanation;
+
+    println!(
+        "{} - {} ({:?})",
+        format_location(&location, config),
+        name,
+        kind
+    );
+
+    if let Some((source, imported_name)) = reexported_from {
+        println!("  Re-exported from {} ({})", source, imported_name);
+    }
+
+    if importers.is_empty() && usage.used_externally {
+        println!(
+            "  {} is an entry point, so its exports are always used.",
+            module.display()
+        );
+    } else if importers.is_empty() {
+        println!("  No module imports {} from {}.", name, module.display());
+    } else {
+        println!("  Imported by:");
+
+        for (importer, imported_name) in importers {
+            match imported_name {
+                ImportName::Wildcard => println!("    {} (wildcard import)", importer.display()),
+                _ => println!("    {}", importer.display()),
+            }
+        }
+    }
+
+    if is_wildcard_imported {
+        println!("  The module is wildcard imported, so all of its exports are considered used.");
+    }
+
+    if usage.used_locally {
+        println!("  Referenced within {}.", module.display());
+    }
+
+    if usage.used_dynamically {
+        println!(
+            "  {} uses eval or with, so it could reference {} dynamically.",
+            module.display(),
+            name
+        );
+    }
+
+    let verdict = if usage.used_externally || is_wildcard_imported {
+        "used"
+    } else if usage.used_dynamically {
+        "possibly used (dynamic references)"
+    } else if usage.used_locally {
+        "unused (only used locally)"
+    } else {
+        "unused"
+    };
+
+    println!("  Verdict: {}", verdict);
+
+    if !kind.matches_analyze_target(config.analyze_target) {
+        println!(
+            "  Exports of this kind are not reported when analyzing {}.",
+            config.analyze_target.as_str()
+        );
+    }
+}
+
+pub fn report_import_resolution(
+    import_source: &str,
+    trace: ResolutionTrace,
+    resolved: NormalizedImportSource,
+    package_json: Option<&PackageJson>,
+    config: &Config,
+) {
+    println!("Resolving {}", import_source);
+
+    for step in trace {
+        println!("  {}", step);
+    }
+
+    match resolved {
+        NormalizedImportSource::Local(path) => {
+            println!("Resolved to module {}", path.display());
+        }
+        NormalizedImportSource::ExternalLocal(path) => {
+            println!("Resolved to {}, which is not analyzed", path.display());
+        }
+        NormalizedImportSource::Unresolved(path) => {
+            println!("Unresolved: no module found for {}", path.display());
+        }
+        NormalizedImportSource::AssetVariant(path) => {
+            println!(
+                "Resolved to {}, which is imported as an asset",
+                path.display()
+            );
+        }
+        NormalizedImportSource::Global(import_source) => {
+            let package = match normalize_package_import(&import_source) {
+                Some(package) => package,
+                None => {
+                    println!("Failed to parse a package name from {}", import_source);
+                    return;
+                }
+            };
+
+            println!("Resolved to package {}", package);
+
+            if let Some(package_json) = package_json {
+                if package_json.dependencies.contains_key(&package) {
+                    println!("  Listed in dependencies");
+                } else if package_json.dev_dependencies.contains_key(&package) {
+                    println!("  Listed in devDependencies");
+                } else if is_node_builtin(&package, package_json, config) {
+                    println!("  Node builtin module");
+                } else {
+                    println!("  Not listed in package.json");
+                }
+            }
+
+            if config.analyzed_packages.contains(&package) {
+                println!("  Re-exports are classified using the package's type declarations");
+            }
+        }
+    }
+}
+
+pub fn report_barrel_updates(updates: &[BarrelUpdate], _config: &Config) {
+    if updates.is_empty() {
+        println!("All barrels are up to date.");
+        return;
+    }
+
+    println!("Out of date barrels:");
+
+    for update in updates {
+        println!("  {}", update.path.display());
+
+        for reexport in &update.removed {
+            println!("    - {} (not imported through the barrel)", reexport);
+        }
+
+        for reexport in &update.added {
+            println!(
+                "    + {} (imported directly from outside the folder)",
+                reexport
+            );
+        }
+    }
+}
+
+pub fn report_config_problems(problems: &[ConfigProblem], _config: &Config) {
+    if problems.is_empty() {
+        println!("No configuration problems found.");
+        return;
+    }
+
+    println!("Configuration problems:");
+
+    for problem in problems {
+        println!("  {} - {}", problem.file.display(), problem.message);
+    }
+}
+
+/// Lists the files which are analyzed as modules, with their module kind and the normalized path which imports
+/// resolve to.
+pub fn report_module_files(files: &[(ModuleFile, NormalizedModulePath)], config: &Config) {
+    println!("{} modules are analyzed:", files.len());
+
+    for (file, normalized_path) in files {
+        println!(
+            "  {} ({:?}) -> {}",
+            format_path(&file.path, config),
+            file.kind,
+            normalized_path.display()
+        );
+    }
+}
+
+/// Prints what each module imports, for `graph`. Imported files which aren't analyzed are listed by their path.
+pub fn report_module_graph(modules: &HashMap<NormalizedModulePath, Module>, config: &Config) {
+    let mut sorted_modules = modules.values().collect::<Vec<_>>();
+    sorted_modules.sort_by(|a, b| a.path.root_relative.cmp(&b.path.root_relative));
+
+    for module in sorted_modules {
+        println!("{}", format_path(&module.path.root_relative, config));
+
+        let mut imported_modules = module
+            .imported_modules
+            .keys()
+            .map(|path| match modules.get(path) {
+                Some(imported) => format_path(&imported.path.root_relative, config),
+                None => format!("{} (not analyzed)", format_path(path, config)),
+            })
+            .chain(
+                module
+                    .imported_external_modules
+                    .iter()
+                    .map(|path| format!("{} (not analyzed)", format_path(path, config))),
+            )
+            .collect::<Vec<_>>();
+        imported_modules.sort();
+
+        let mut imported_packages = module.imported_packages.iter().collect::<Vec<_>>();
+        imported_packages.sort();
+
+        let mut unresolved_imports = module.unresolved_imports.iter().collect::<Vec<_>>();
+        unresolved_imports.sort();
+
+        for imported in imported_modules {
+            println!("  -> {}", imported);
+        }
+
+        for package in imported_packages {
+            println!("  -> {}", paint(package, Style::Name, config));
+        }
+
+        for path in unresolved_imports {
+            println!("  -> {} (unresolved)", format_path(path, config));
+        }
+    }
+}
+
+/// Reports the findings of each rule which is not turned off.
+pub fn report_project_analysis(
+    analysis: ProjectAnalysis,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if config.severity(Rule::UnusedExports) != Severity::Off {
+        report_unused_exports(analysis.unused_exports, config, out)?;
+    }
+
+    if let Some(dependencies) = analysis.unused_dependencies {
+        report_unused_dependencies(dependencies, &analysis.dependency_details, config, out)?;
+    }
+
+    report_unused_bundled_dependencies(analysis.unused_bundled_dependencies, config, out)?;
+
+    if config.list_overridden_dependencies {
+        report_unused_overridden_dependencies(
+            analysis.unused_overridden_dependencies,
+            config,
+            out,
+        )?;
+    }
+
+    if let Some(dependencies) = analysis.misplaced_dependencies {
+        report_misplaced_dependencies(dependencies, &analysis.dependency_details, config, out)?;
+    }
+
+    if let Some(problems) = analysis.lockfile_problems {
+        report_lockfile_problems(problems, config, out)?;
+    }
+
+    if config.severity(Rule::UnusedTypeParameters) != Severity::Off {
+        report_unused_type_parameters(analysis.unused_type_parameters, config, out)?;
+    }
+
+    if !config.asset_folders.is_empty() && config.severity(Rule::UnusedAssets) != Severity::Off {
+        report_unused_assets(analysis.unused_assets, config, out)?;
+    }
+
+    if !config.entry_points.is_empty() && config.severity(Rule::UnreachableModules) != Severity::Off
+    {
+        report_unreachable_islands(analysis.unreachable_islands, config, out)?;
+    }
+
+    if config.severity(Rule::DefaultExportMismatches) != Severity::Off {
+        report_default_export_mismatches(analysis.default_export_mismatches, config, out)?;
+    }
+
+    if config.report_unused_suppressions {
+        report_unused_suppressions(analysis.unused_suppressions, config, out)?;
+    }
+
+    Ok(())
+}
+
+/// Reports each workspace package in its own section, followed by a summary where findings shared by
+/// several packages (e.g. nested workspaces) are only counted once.
+pub fn report_workspaces(
+    analyses: Vec<ProjectAnalysis>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let package_count = analyses.len();
+
+    let mut unique_exports = HashSet::new();
+    let mut unique_type_parameters = HashSet::new();
+    let mut unique_assets = HashSet::new();
+    let mut misplaced_dependency_count = 0;
+    let mut unused_dependencies: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+
+    for analysis in analyses {
+        writeln!(out)?;
+        let title = format!("== {} ==", analysis.root.display());
+        writeln!(out, "{}", paint(&title, Style::Heading, config))?;
+
+        for export in &analysis.unused_exports.sorted_exports {
+            unique_exports.insert((
+                export.location.path().to_owned(),
+                export.location.line(),
+                export.name.clone(),
+            ));
+        }
+
+        for type_parameter in &analysis.unused_type_parameters {
+            unique_type_parameters.insert((
+                type_parameter.location.path().to_owned(),
+                type_parameter.location.line(),
+                type_parameter.name.clone(),
+            ));
+        }
+
+        for asset in &analysis.unused_assets {
+            unique_assets.insert(analysis.root.join(asset));
+        }
+
+        misplaced_dependency_count += analysis.misplaced_dependencies.as_ref().map_or(0, Vec::len);
+
+        for dependency in analysis.unused_dependencies.iter().flatten() {
+            unused_dependencies
+                .entry(dependency.clone())
+                .or_default()
+                .push(analysis.root.clone());
+        }
+
+        // Locations are relative to the package, not the workspace root
+        let package_config = Config {
+            root: Arc::new(analysis.root.clone()),
+            ..config.clone()
+        };
+
+        report_project_analysis(analysis, &package_config, out)?;
+    }
+
+    writeln!(out)?;
+    let title = format!("Summary of {} workspace packages:", package_count);
+    writeln!(out, "{}", paint(&title, Style::Heading, config))?;
+    writeln!(out, "  {} unused exports", unique_exports.len())?;
+    writeln!(
+        out,
+        "  {} unused type parameters",
+        unique_type_parameters.len()
+    )?;
+    writeln!(out, "  {} unused assets", unique_assets.len())?;
+    writeln!(
+        out,
+        "  {} misplaced dependencies",
+        misplaced_dependency_count
+    )?;
+    writeln!(out, "  {} unused dependencies", unused_dependencies.len())?;
+
+    for (dependency, mut packages) in unused_dependencies {
+        packages.sort();
+        writeln!(
+            out,
+            "    {} ({})",
+            dependency,
+            packages
+                .iter()
+                .map(|package| package.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn report_unused_package_exports(
+    exports: Vec<UnusedPackageExport>,
+    config: &Config,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    if exports.is_empty() {
+        writeln!(out, "No unused workspace package exports.")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "{}",
+        header(
+            "Workspace package exports not used by other workspaces:",
+            Rule::UnusedExports,
+            config
+        )
+    )?;
+
+    for export in exports {
+        writeln!(
+            out,
+            "  {} - {} ({})",
+            format_location(&export.location, config),
+            paint(&export.name, Style::Name, config),
+            export.package
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Formats a root-relative location as `path:line`, as a hyperlink if they are enabled.
+fn format_location(location: &ModuleSourceAndLine, config: &Config) -> String {
+    let text = format!(
+        "{}:{}",
+        config.output_path(location.path()).display(),
+        location.line()
+    );
+    let text = paint(&text, Style::Path, config);
+
+    hyperlink(&text, location.path(), Some(location.line()), config)
+}
+
+/// Formats a root-relative path, as a hyperlink if they are enabled.
+fn format_path(path: &Path, config: &Config) -> String {
+    let text = config.output_path(path).display().to_string();
+    let text = paint(&text, Style::Path, config);
+    hyperlink(&text, path, None, config)
+}
+
+/// The ANSI styles of the text report.
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    /// Headings of rules with the error severity.
+    Error,
+    /// Headings of rules with the warn severity.
+    Warning,
+    /// Other headings, e.g. workspace packages.
+    Heading,
+    Path,
+    /// Names of exports, dependencies and type parameters.
+    Name,
+}
+
+impl Style {
+    /// The SGR parameters of the style.
+    fn code(self) -> &'static str {
+        match self {
+            Style::Error => "1;31",
+            Style::Warning => "1;33",
+            Style::Heading => "1",
+            Style::Path => "36",
+            Style::Name => "1",
+        }
+    }
+}
+
+/// Wraps the text in an ANSI style, if colors are enabled.
+fn paint(text: impl Display, style: Style, config: &Config) -> String {
+    if !config.colors {
+        return text.to_string();
+    }
+
+    format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+}
+
+/// Formats the heading of a rule's findings, colored by the severity of the rule.
+fn header(text: &str, rule: Rule, config: &Config) -> String {
+    let style = match config.severity(rule) {
+        Severity::Error => Style::Error,
+        _ => Style::Warning,
+    };
+
+    paint(text, style, config)
+}
+
+fn hyperlink(text: &str, path: &Path, line: Option<usize>, config: &Config) -> String {
+    let path = config.root.join(path);
+    let path = match env::current_dir() {
+        Ok(current_dir) if path.is_relative() => current_dir.join(path),
+        _ => path,
+    };
+
+    let url = match (config.hyperlinks, line) {
+        (HyperlinkStyle::Off, _) => return text.to_string(),
+        (HyperlinkStyle::File, _) => format!("file://{}", url_path(&path)),
+        (HyperlinkStyle::Vscode, Some(line)) => {
+            format!("vscode://file{}:{}", url_path(&path), line)
+        }
+        (HyperlinkStyle::Vscode, None) => format!("vscode://file{}", url_path(&path)),
+    };
+
+    // OSC 8 ; params ; URI ST, followed by the text and an OSC 8 with an empty URI
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Formats an absolute path as the path of a URL, with forward slashes and a leading slash (also on Windows).
+fn url_path(path: &Path) -> String {
+    let mut url = String::new();
+
+    for component in path.components() {
+        let component = component.as_os_str().to_string_lossy();
+
+        if component == "." || component == "/" || component == "\\" {
+            continue;
+        }
+
+        url.push('/');
+
+        for byte in component.bytes() {
+            match byte {
+                b'A'..=b'Z'
+                | b'a'..=b'z'
+                | b'0'..=b'9'
+                | b'-'
+                | b'.'
+                | b'_'
+                | b'~'
+                | b':'
+                | b'@' => url.push(byte as char),
+                _ => url.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+    }
+
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_paths_are_escaped() {
+        assert_eq!(
+            url_path(Path::new("/home/user/my project/src/a.ts")),
+            "/home/user/my%20project/src/a.ts"
+        );
+        assert_eq!(
+            url_path(Path::new("/repo/./src/[id].ts")),
+            "/repo/src/%5Bid%5D.ts"
+        );
+    }
+
+    #[test]
+    fn headers_are_colored_by_severity() {
+        let config = Config {
+            colors: true,
+            rule_severities: [(Rule::UnusedAssets, Severity::Error)]
+                .into_iter()
+                .collect(),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            header("Unused assets:", Rule::UnusedAssets, &config),
+            "\x1b[1;31mUnused assets:\x1b[0m"
+        );
+        assert_eq!(
+            header("Unused exports:", Rule::UnusedExports, &config),
+            "\x1b[1;33mUnused exports:\x1b[0m"
+        );
+        assert_eq!(
+            header("Unused exports:", Rule::UnusedExports, &Config::default()),
+            "Unused exports:"
+        );
+    }
+
+    #[test]
+    fn vscode_hyperlink() {
+        let config = Config {
+            root: Arc::new(PathBuf::from("/repo")),
+            hyperlinks: HyperlinkStyle::Vscode,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            hyperlink("src/a.ts:3", Path::new("src/a.ts"), Some(3), &config),
+            "\x1b]8;;vscode://file/repo/src/a.ts:3\x1b\\src/a.ts:3\x1b]8;;\x1b\\"
+        );
     }
 }
@@ -0,0 +1,267 @@
+//! Reproduction bundles for bug reports about modules which the parser or the module visitor fails on.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context};
+use flate2::{write::GzEncoder, Compression};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::json;
+
+use crate::{
+    config::Config,
+    parsing::{decode_source, get_module_kind, visit_module_source},
+};
+
+/// Words which are kept when identifiers are stripped, so that the module still has the same syntax.
+const KEPT_WORDS: &[&str] = &[
+    "abstract",
+    "any",
+    "as",
+    "asserts",
+    "async",
+    "await",
+    "bigint",
+    "boolean",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "constructor",
+    "continue",
+    "debugger",
+    "declare",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "get",
+    "global",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "infer",
+    "instanceof",
+    "interface",
+    "is",
+    "keyof",
+    "let",
+    "module",
+    "namespace",
+    "never",
+    "new",
+    "null",
+    "number",
+    "object",
+    "of",
+    "override",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "require",
+    "return",
+    "set",
+    "static",
+    "string",
+    "super",
+    "switch",
+    "symbol",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "unique",
+    "unknown",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Replaces every identifier-like word (also in strings and comments) with a generated name.
+/// The same word always gets the same name, so references still match their declarations.
+pub fn strip_identifiers(source: &str) -> String {
+    lazy_static! {
+        static ref WORD_RE: Regex = Regex::new(r"[A-Za-z_$][A-Za-z0-9_$]*").unwrap();
+    }
+
+    let mut names = HashMap::<String, String>::new();
+
+    WORD_RE
+        .replace_all(source, |captures: &Captures| {
+            let word = &captures[0];
+
+            if KEPT_WORDS.contains(&word) {
+                return word.to_string();
+            }
+
+            let next_id = names.len();
+            names
+                .entry(word.to_string())
+                .or_insert_with(|| format!("id{}", next_id))
+                .clone()
+        })
+        .into_owned()
+}
+
+/// Parses and visits the module, turning a panic into an error.
+fn try_visit(path: &Path, source: &str, config: &Config) -> Result<(), String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        visit_module_source(path, source.to_string(), config).map(|_| ())
+    }));
+
+    match result {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(err)) => Err(format!("{:#}", err)),
+        Err(panic) => Err(match panic.downcast_ref::<&str>() {
+            Some(message) => format!("panic: {}", message),
+            None => match panic.downcast_ref::<String>() {
+                Some(message) => format!("panic: {}", message),
+                None => String::from("panic"),
+            },
+        }),
+    }
+}
+
+/// The file name in the bundle. Stripping identifiers also replaces the name, but keeps the suffixes
+/// (e.g. `.d.ts` or `.test.tsx`) since they decide how the module is parsed.
+fn bundled_file_name(path: &Path, strip: bool) -> anyhow::Result<String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Expected a file: {}", path.display()))?
+        .to_string_lossy();
+
+    if !strip {
+        return Ok(file_name.into_owned());
+    }
+
+    Ok(match file_name.find('.') {
+        Some(index) => format!("module{}", &file_name[index..]),
+        None => String::from("module"),
+    })
+}
+
+fn append_file(
+    builder: &mut tar::Builder<GzEncoder<File>>,
+    path: &str,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    // A fixed time, so that the bundle doesn't tell when it was made
+    header.set_mtime(0);
+    header.set_cksum();
+
+    builder
+        .append_data(&mut header, path, contents)
+        .with_context(|| format!("Failed to add {} to the bundle", path))
+}
+
+/// Writes a `.tar.gz` bundle with the module and the settings which affect how it's parsed, for attaching to a
+/// bug report. Absolute paths are left out. Returns whether the bundled module still fails.
+pub fn write_repro_bundle(
+    file: &Path,
+    archive: &Path,
+    strip: bool,
+    config: &Config,
+) -> anyhow::Result<bool> {
+    let bytes =
+        std::fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let source = decode_source(bytes).map_err(|reason| anyhow!("{} {}", file.display(), reason))?;
+    let source = if strip {
+        strip_identifiers(&source)
+    } else {
+        source
+    };
+
+    let file_name = bundled_file_name(file, strip)?;
+    let module_kind = file
+        .file_name()
+        .and_then(|name| get_module_kind(name, &config.extensions))
+        .ok_or_else(|| anyhow!("{} is not a supported module type", file.display()))?;
+
+    let outcome = try_visit(Path::new(&file_name), &source, config);
+
+    let settings = json!({
+        "customsVersion": env!("CARGO_PKG_VERSION"),
+        "file": file_name,
+        "moduleKind": module_kind,
+        "jsx": config.compiler_options.jsx,
+        "jsxImportSource": config.compiler_options.jsx_import_source,
+        "defines": &*config.defines,
+        "identifiersStripped": strip,
+        "error": outcome.as_ref().err(),
+    });
+
+    let archive_file =
+        File::create(archive).with_context(|| format!("Failed to create {}", archive.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(archive_file, Compression::default()));
+
+    append_file(
+        &mut builder,
+        &format!("repro/{}", file_name),
+        source.as_bytes(),
+    )?;
+    append_file(
+        &mut builder,
+        "repro/repro.json",
+        (serde_json::to_string_pretty(&settings)? + "\n").as_bytes(),
+    )?;
+
+    builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .with_context(|| format!("Failed to write {}", archive.display()))?;
+
+    Ok(outcome.is_err())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stripped_identifiers_are_consistent() {
+        let source =
+            "import { secret } from './secret';\nexport const value: string = secret(value);\n";
+
+        assert_eq!(
+            strip_identifiers(source),
+            "import { id0 } from './id0';\nexport const id1: string = id0(id1);\n"
+        );
+    }
+
+    #[test]
+    fn bundled_file_keeps_suffixes() {
+        assert_eq!(
+            bundled_file_name(Path::new("src/Button.test.tsx"), true).unwrap(),
+            "module.test.tsx"
+        );
+        assert_eq!(
+            bundled_file_name(Path::new("src/types.d.ts"), false).unwrap(),
+            "types.d.ts"
+        );
+    }
+}
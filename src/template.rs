@@ -0,0 +1,223 @@
+//! A small subset of Mustache for user-provided report templates.
+//!
+//! Supported tags are variables (`{{name}}`, `{{location.file}}`, `{{.}}`), sections (`{{#name}}...{{/name}}`),
+//! inverted sections (`{{^name}}...{{/name}}`) and comments (`{{! ... }}`). Values are not escaped, since
+//! the output is text or Markdown. Section and comment tags on a line of their own don't leave an empty line.
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// Renders the template with the data. Sections iterate arrays, enter objects and show other values if they are truthy.
+pub fn render_template(template: &str, data: &Value) -> anyhow::Result<String> {
+    let nodes = parse_template(template)?;
+    let mut output = String::new();
+    render_nodes(&nodes, &mut vec![data], &mut output);
+    Ok(output)
+}
+
+fn parse_template(template: &str) -> anyhow::Result<Vec<Node>> {
+    // The root, followed by each open section
+    let mut stack = vec![(String::new(), false, Vec::new())];
+    let mut rest = template;
+    let mut at_line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("Unclosed tag: {}", &rest[start..]))?;
+
+        let tag = rest[start + 2..end].trim();
+        let mut text = &rest[..start];
+        let mut after = &rest[end + 2..];
+
+        let is_standalone_kind = matches!(tag.chars().next(), Some('#' | '^' | '/' | '!'));
+        let line_start = text.rfind('\n').map_or(0, |index| index + 1);
+        let line_end = after.find('\n');
+        let is_standalone = is_standalone_kind
+            && (line_start > 0 || at_line_start)
+            && text[line_start..].chars().all(char::is_whitespace)
+            && after[..line_end.unwrap_or(after.len())]
+                .chars()
+                .all(char::is_whitespace);
+
+        if is_standalone {
+            text = &text[..line_start];
+            after = &after[line_end.map_or(after.len(), |index| index + 1)..];
+        }
+
+        at_line_start = is_standalone;
+
+        let (_, _, nodes) = stack.last_mut().unwrap();
+
+        if !text.is_empty() {
+            nodes.push(Node::Text(text.to_string()));
+        }
+
+        match (tag.chars().next(), tag.get(1..).map(str::trim)) {
+            (Some('!'), _) => {}
+            (Some('#'), Some(name)) => stack.push((name.to_string(), false, Vec::new())),
+            (Some('^'), Some(name)) => stack.push((name.to_string(), true, Vec::new())),
+            (Some('/'), Some(name)) => {
+                if stack.len() == 1 {
+                    return Err(anyhow!("Unexpected closing tag: {{{{/{}}}}}", name));
+                }
+
+                let (open, inverted, children) = stack.pop().unwrap();
+
+                if open != name {
+                    return Err(anyhow!(
+                        "Expected {{{{/{}}}}} but found {{{{/{}}}}}",
+                        open,
+                        name
+                    ));
+                }
+
+                stack.last_mut().unwrap().2.push(Node::Section {
+                    name: open,
+                    inverted,
+                    children,
+                });
+            }
+            _ if tag.is_empty() => return Err(anyhow!("Empty tag in template")),
+            _ => nodes.push(Node::Variable(tag.to_string())),
+        }
+
+        rest = after;
+    }
+
+    if let Some((name, _, _)) = stack.get(1) {
+        return Err(anyhow!("Unclosed section: {{{{#{}}}}}", name));
+    }
+
+    let (_, _, mut nodes) = stack.pop().unwrap();
+
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+
+    Ok(nodes)
+}
+
+/// Finds a dotted name from the innermost context which has its first part, like Mustache.
+fn lookup<'a>(context: &[&'a Value], name: &str) -> Option<&'a Value> {
+    if name == "." {
+        return context.last().copied();
+    }
+
+    let mut parts = name.split('.');
+    let first = parts.next()?;
+    let value = context.iter().rev().find_map(|value| value.get(first))?;
+
+    parts.try_fold(value, |value, part| value.get(part))
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn render_nodes<'a>(nodes: &'a [Node], context: &mut Vec<&'a Value>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => match lookup(context, name) {
+                Some(Value::String(value)) => output.push_str(value),
+                None | Some(Value::Null) => {}
+                Some(value) => output.push_str(&value.to_string()),
+            },
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let value = lookup(context, name);
+
+                if *inverted {
+                    if !is_truthy(value) {
+                        render_nodes(children, context, output);
+                    }
+
+                    continue;
+                }
+
+                match value {
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            context.push(item);
+                            render_nodes(children, context, output);
+                            context.pop();
+                        }
+                    }
+                    Some(value) if is_truthy(Some(value)) => {
+                        context.push(value);
+                        render_nodes(children, context, output);
+                        context.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn variables_and_sections() {
+        let data = json!({
+            "title": "Report",
+            "exports": [
+                { "name": "a", "location": { "file": "a.ts", "line": 1 } },
+                { "name": "b", "location": { "file": "b.ts", "line": 2 } },
+            ],
+            "assets": [],
+        });
+
+        let template = "# {{title}}\n{{#exports}}\n- {{location.file}}:{{location.line}} {{name}} ({{title}})\n{{/exports}}\n{{^assets}}\nNo unused assets.\n{{/assets}}\n";
+
+        assert_eq!(
+            render_template(template, &data).unwrap(),
+            "# Report\n- a.ts:1 a (Report)\n- b.ts:2 b (Report)\nNo unused assets.\n"
+        );
+    }
+
+    #[test]
+    fn current_item_and_comments() {
+        let data = json!({ "dependencies": ["left-pad", "lodash"] });
+
+        assert_eq!(
+            render_template(
+                "{{! unused }}{{#dependencies}}[{{.}}]{{/dependencies}}",
+                &data
+            )
+            .unwrap(),
+            "[left-pad][lodash]"
+        );
+    }
+
+    #[test]
+    fn mismatched_sections() {
+        assert!(render_template("{{#a}}", &json!({})).is_err());
+        assert!(render_template("{{#a}}{{/b}}", &json!({})).is_err());
+        assert!(render_template("{{/a}}", &json!({})).is_err());
+        assert!(render_template("{{a", &json!({})).is_err());
+    }
+}
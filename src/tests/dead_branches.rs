@@ -0,0 +1,61 @@
+use crate::tests::utils::{is_referenced, parse_and_visit_with_defines};
+
+#[test]
+pub fn node_env_impossible_branch() {
+    let source = r#"
+        if (process.env.NODE_ENV === "development") {
+            devOnly()
+        } else {
+            prodOnly()
+        }
+    "#;
+
+    let defines = [("process.env.NODE_ENV", "production")];
+    let visitor = parse_and_visit_with_defines("unknown.ts", source, &defines);
+
+    assert!(!is_referenced(&visitor, "devOnly"));
+    assert!(is_referenced(&visitor, "prodOnly"));
+}
+
+#[test]
+pub fn node_env_without_defines() {
+    let source = r#"
+        if (process.env.NODE_ENV === "development") {
+            devOnly()
+        }
+    "#;
+
+    let visitor = parse_and_visit_with_defines("unknown.ts", source, &[]);
+    assert!(is_referenced(&visitor, "devOnly"));
+}
+
+#[test]
+pub fn dev_flag_negated() {
+    let source = r#"
+        if (!__DEV__) {
+            prodOnly()
+        }
+        const x = __DEV__ ? devOnly() : prodOnly2()
+    "#;
+
+    let defines = [("__DEV__", "true")];
+    let visitor = parse_and_visit_with_defines("unknown.ts", source, &defines);
+
+    assert!(!is_referenced(&visitor, "prodOnly"));
+    assert!(is_referenced(&visitor, "devOnly"));
+    assert!(!is_referenced(&visitor, "prodOnly2"));
+}
+
+#[test]
+pub fn unknown_condition() {
+    let source = r#"
+        if (process.env.NODE_ENV === "development" && someFlag) {
+            maybeUsed()
+        }
+    "#;
+
+    let defines = [("process.env.NODE_ENV", "development")];
+    let visitor = parse_and_visit_with_defines("unknown.ts", source, &defines);
+
+    assert!(is_referenced(&visitor, "maybeUsed"));
+}
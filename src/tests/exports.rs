@@ -1,4 +1,14 @@
-use crate::tests::utils::{run_test, TestScope, TestSpec};
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use swc_atoms::JsWord;
+
+use crate::{
+    analysis::find_unused_exports,
+    config::Config,
+    dependency_graph::{ExportKind, ExportName, ImportName},
+    tests::utils::{parse_and_analyze, parse_and_visit, run_test, TestScope, TestSpec},
+};
 
 #[test]
 pub fn smoke() {
@@ -126,6 +136,53 @@ pub fn re_export() {
     run_test(spec);
 }
 
+#[test]
+pub fn re_export_default() {
+    let source = r#"
+        export { default } from "./impl"
+        export { default as Thing } from "./thing"
+    "#;
+
+    let spec = TestSpec {
+        source,
+        exports: vec!["default", "Thing"],
+        imports: vec![
+            ("./impl", vec![("default", None)]),
+            ("./thing", vec![("default", None)]),
+        ],
+        scope: TestScope::default(),
+    };
+
+    run_test(spec);
+}
+
+#[test]
+pub fn commonjs_exports() {
+    let source = r#"
+        module.exports = { parse, format: formatDate, print() {} };
+        exports.helper = () => {};
+        module.exports.VERSION = "1.0";
+
+        function local() {
+            exports.notExported = 1;
+        }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    let names = module
+        .exports
+        .iter()
+        .map(|export| export.name.to_string())
+        .sorted()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        names,
+        vec!["VERSION", "default", "format", "helper", "parse", "print"]
+    );
+}
+
 #[test]
 pub fn rename() {
     let source = r#"
@@ -329,3 +386,289 @@ pub fn destructured() {
 
     run_test(spec);
 }
+
+#[test]
+pub fn destructured_defaults_rest_and_computed_keys() {
+    let source = r#"
+        export const { a = (x) => x, b: [c = d, ...e], [key]: f, ...g } = obj
+    "#;
+
+    let spec = TestSpec {
+        source,
+        exports: vec!["a", "c", "e", "f", "g"],
+        imports: vec![],
+        scope: TestScope {
+            bindings: vec!["a", "c", "e", "f", "g"],
+            references: vec!["d", "key", "obj"],
+            inner: vec![TestScope {
+                bindings: vec!["x"],
+                references: vec!["x"],
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+
+    run_test(spec);
+}
+
+#[test]
+pub fn destructuring_assignment_declares_nothing() {
+    let source = r#"
+        let a, c;
+        ({ a = (x) => x, b: c } = obj);
+    "#;
+
+    let spec = TestSpec {
+        source,
+        exports: vec![],
+        imports: vec![],
+        scope: TestScope {
+            bindings: vec!["a", "c"],
+            references: vec!["a", "c", "obj"],
+            inner: vec![TestScope {
+                bindings: vec!["x"],
+                references: vec!["x"],
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    };
+
+    run_test(spec);
+}
+
+#[test]
+pub fn destructured_export_locations() {
+    let source = "export const {\n    a = 1,\n    b: { c },\n} = obj;\n";
+
+    let module = parse_and_analyze("test.ts", source);
+    let lines = module
+        .exports
+        .iter()
+        .map(|export| (export.name.to_string(), export.location.line()))
+        .sorted()
+        .collect::<Vec<_>>();
+
+    assert_eq!(lines, vec![(String::from("a"), 2), (String::from("c"), 3)]);
+}
+
+#[test]
+pub fn namespace_members() {
+    let source = r#"
+        export namespace Api {
+            export function get() { return helper() }
+            function helper() { }
+            export interface Response { }
+
+            export namespace Admin {
+                export const users = []
+            }
+        }
+
+        namespace Internal {
+            export const value = 1
+        }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    let names = module
+        .exports
+        .iter()
+        .map(|export| export.name.to_string())
+        .sorted()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        names,
+        vec![
+            "Api",
+            "Api.Admin",
+            "Api.Admin.users",
+            "Api.Response",
+            "Api.get"
+        ]
+    );
+}
+
+#[test]
+pub fn export_line_counts() {
+    let source = r#"
+        export function multiline() {
+            return 1
+        }
+        export const single = 1
+        const local = 2
+        export { local }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    let line_counts = module
+        .exports
+        .iter()
+        .map(|export| (export.name.to_string(), export.line_count))
+        .sorted()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        line_counts,
+        vec![
+            (String::from("local"), 1),
+            (String::from("multiline"), 3),
+            (String::from("single"), 1),
+        ]
+    );
+}
+
+#[test]
+pub fn exported_imports_are_reexports() {
+    let source = r#"
+        import { a as b } from "./a";
+        export { b as c };
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+    let export = module.get_export(&ExportName::named("c")).unwrap();
+
+    assert_eq!(
+        export.reexported_from,
+        Some((String::from("./a"), ImportName::named("a")))
+    );
+}
+
+#[test]
+pub fn exports_are_grouped_by_statement() {
+    let source = r#"export const {
+    a,
+    b,
+} = createThings();
+export const c = 1, d = 2;
+export const e = 3;
+export const f = 4; export const g = 5;
+console.log(d);
+"#;
+
+    let module = parse_and_analyze("test.ts", source);
+    let mut modules = HashMap::new();
+    modules.insert(module.path.normalized.clone(), module);
+
+    let results = find_unused_exports(modules, &Config::default());
+    let statements = results
+        .statements()
+        .iter()
+        .map(|statement| {
+            (
+                statement.statement().line(),
+                statement
+                    .exports
+                    .iter()
+                    .map(|export| export.name.to_string())
+                    .collect::<Vec<_>>(),
+                statement.is_deletable(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        statements,
+        vec![
+            (1, vec![String::from("a"), String::from("b")], true),
+            (5, vec![String::from("c"), String::from("d")], false),
+            (6, vec![String::from("e")], true),
+            (7, vec![String::from("f")], true),
+            (7, vec![String::from("g")], true),
+        ]
+    );
+
+    // The destructuring statement counts once
+    assert_eq!(results.deletable_lines(), 7);
+}
+
+#[test]
+pub fn abstract_and_ambient_classes() {
+    let source = r#"
+        export abstract class Shape { abstract area(): number }
+        export declare class Native { run(): void }
+        declare class Global { }
+    "#;
+
+    let visitor = parse_and_visit("test.ts", source);
+    let root_scope = &visitor.scopes[0];
+
+    for name in ["Shape", "Native", "Global"] {
+        assert!(root_scope.bindings.contains_key(&JsWord::from(name)));
+        assert!(root_scope.type_bindings.contains_key(&JsWord::from(name)));
+    }
+
+    let module = parse_and_analyze("test.ts", source);
+    let shape = module.get_export(&ExportName::named("Shape")).unwrap();
+    let native = module.get_export(&ExportName::named("Native")).unwrap();
+
+    assert_eq!(shape.kind, ExportKind::Class);
+    assert!(!shape.is_ambient);
+    assert_eq!(native.kind, ExportKind::Class);
+    assert!(native.is_ambient);
+}
+
+#[test]
+pub fn bodyless_functions_are_exports() {
+    let source = r#"
+        export declare function log(message: string): void;
+        export function format(value: string): string;
+        export function format(value: number): string;
+        export function format(value: any) { return String(value); }
+        export declare namespace Api {
+            export function get(id: string): void;
+            export function get(id: number): void;
+        }
+    "#;
+
+    let visitor = parse_and_visit("test.ts", source);
+    let exports = visitor
+        .exports
+        .iter()
+        .map(|export| (export.name.to_string(), export.source.line()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        exports,
+        vec![
+            (String::from("log"), 2),
+            (String::from("format"), 5),
+            (String::from("Api"), 6),
+            (String::from("Api.get"), 7),
+        ]
+    );
+}
+
+#[test]
+pub fn ambient_exports_are_not_unused() {
+    let source = r#"
+        export declare const version: string;
+        export declare function log(message: string): void;
+        export declare namespace Api {
+            export function get(): void;
+        }
+        export function helper() { }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+    for name in ["version", "log", "Api"] {
+        let export = module.get_export(&ExportName::named(name)).unwrap();
+        assert!(export.is_ambient);
+    }
+
+    let mut modules = HashMap::new();
+    modules.insert(module.path.normalized.clone(), module);
+
+    let results = find_unused_exports(modules, &Config::default());
+    let names = results
+        .sorted_exports
+        .iter()
+        .map(|export| export.name.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["helper"]);
+}
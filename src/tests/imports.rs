@@ -1,4 +1,7 @@
-use crate::tests::utils::{run_test, TestScope, TestSpec};
+use crate::{
+    dependency_graph::{ImportKind, ImportName},
+    tests::utils::{parse_and_analyze, parse_and_visit, run_test, TestScope, TestSpec},
+};
 
 #[test]
 pub fn named() {
@@ -121,3 +124,129 @@ pub fn multiple_default_named() {
 
     run_test(spec);
 }
+
+fn package_usages(source: &'static str) -> Vec<(String, bool)> {
+    parse_and_analyze("unknown.ts", source)
+        .package_usages
+        .into_iter()
+        .map(|usage| (usage.package, usage.type_only))
+        .collect()
+}
+
+#[test]
+pub fn package_used_only_as_type() {
+    let source = r#"
+        import { Props } from "react";
+        import type { Theme } from "@emotion/react";
+        import { render } from "react-dom";
+
+        export function mount(props: Props, theme: Theme) {
+            render(props, theme);
+        }
+    "#;
+
+    assert_eq!(
+        package_usages(source),
+        vec![
+            (String::from("react"), true),
+            (String::from("@emotion/react"), true),
+            (String::from("react-dom"), false),
+        ]
+    );
+}
+
+#[test]
+pub fn side_effect_package_import_is_not_type_only() {
+    let source = r#"
+        import "polyfills";
+        export { Component } from "react";
+    "#;
+
+    assert_eq!(
+        package_usages(source),
+        vec![
+            (String::from("polyfills"), false),
+            (String::from("react"), false),
+        ]
+    );
+}
+
+#[test]
+pub fn module_augmentation_uses_package() {
+    let source = r#"
+        import type { Theme } from "@emotion/react";
+
+        declare module "react" {
+            export interface CSSProperties {
+                theme?: Theme;
+            }
+        }
+
+        declare module "*.svg" {
+            const url: string;
+            export default url;
+        }
+    "#;
+
+    let module = parse_and_analyze("unknown.ts", source);
+
+    assert!(module.exports.is_empty());
+    assert!(module.imported_packages.contains("react"));
+    assert_eq!(
+        package_usages(source),
+        vec![
+            (String::from("@emotion/react"), true),
+            (String::from("react"), true),
+        ]
+    );
+}
+
+#[test]
+pub fn ambient_module_in_script_is_not_augmentation() {
+    let source = r#"
+        declare module "untyped-package";
+    "#;
+
+    assert!(package_usages(source).is_empty());
+}
+
+#[test]
+pub fn import_kinds() {
+    let source = r#"
+        import { a } from "./a";
+        import type { B } from "./b";
+        export { c } from "./c";
+    "#;
+
+    let visitor = parse_and_visit("test.ts", source);
+
+    let kind_of = |source: &str| visitor.imports[source][0].kind;
+
+    assert_eq!(kind_of("./a"), ImportKind::Value);
+    assert_eq!(kind_of("./b"), ImportKind::TypeOnly);
+    assert_eq!(kind_of("./c"), ImportKind::Reexport);
+}
+
+#[test]
+pub fn namespace_members_used_through_property_access() {
+    let source = r#"
+        import { Api, Client } from "api";
+
+        Api.get();
+        let response: Api.Response = Api.get();
+
+        Client.connect();
+        register(Client);
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    assert_eq!(
+        module.package_imports["api"],
+        vec![
+            ImportName::Member("Api".into(), "Response".into()),
+            ImportName::Member("Api".into(), "get".into()),
+            ImportName::named("Client"),
+        ]
+    );
+}
@@ -0,0 +1,99 @@
+use swc_atoms::JsWord;
+
+use crate::tests::utils::{is_referenced, is_type_referenced, parse_and_visit};
+
+#[test]
+pub fn component_references() {
+    let source = r#"
+        import { Button, Form, List } from "./components";
+
+        type Item = { id: string };
+        const label = "Save";
+
+        export function App(props: Props) {
+            const items = useItems();
+
+            return (
+                <div className="app" onClick={() => props.onSave(items)}>
+                    <Form.Field name="title" />
+                    <List<Item> items={items} />
+                    <Button {...props}>{label}</Button>
+                </div>
+            );
+        }
+    "#;
+
+    let visitor = parse_and_visit("test.tsx", source);
+
+    for name in [
+        "Button", "List", "Form", "props", "items", "label", "useItems",
+    ] {
+        assert!(is_referenced(&visitor, name), "{}", name);
+    }
+
+    assert!(is_type_referenced(&visitor, "Item"));
+    assert!(visitor.property_accesses[&JsWord::from("Form")].contains(&JsWord::from("Field")));
+    assert!(visitor.has_jsx);
+}
+
+#[test]
+pub fn intrinsic_elements_and_props_are_not_references() {
+    let source = r#"
+        export const className = "app";
+        export const title = "Title";
+
+        export const App = () => (
+            <main className="main">
+                <my-element title="title" />
+                <svg:rect />
+                <Layout.Header title={title} />
+            </main>
+        );
+    "#;
+
+    let visitor = parse_and_visit("test.tsx", source);
+
+    for name in ["main", "my-element", "className", "Header"] {
+        assert!(!is_referenced(&visitor, name), "{}", name);
+        assert!(
+            !visitor.direct_references.contains(&JsWord::from(name)),
+            "{}",
+            name
+        );
+    }
+
+    assert!(is_referenced(&visitor, "title"));
+    assert!(is_referenced(&visitor, "Layout"));
+    assert!(!visitor.direct_references.contains(&JsWord::from("Layout")));
+}
+
+#[test]
+pub fn hook_dependency_arrays() {
+    let source = r#"
+        export function Total({ items, tax }: Props) {
+            const rate = useMemo(() => tax / 100, [tax]);
+            const total = useMemo(() => sum(items) * rate, [items.length, rate]);
+
+            useEffect(() => {
+                report(total);
+            }, [total]);
+
+            return <span>{total}</span>;
+        }
+    "#;
+
+    let visitor = parse_and_visit("test.tsx", source);
+
+    for name in [
+        "useMemo",
+        "useEffect",
+        "tax",
+        "items",
+        "rate",
+        "total",
+        "sum",
+        "report",
+    ] {
+        assert!(is_referenced(&visitor, name), "{}", name);
+    }
+}
@@ -1,6 +1,11 @@
+pub mod dead_branches;
 pub mod exports;
 pub mod imports;
+pub mod jsx;
 pub mod parsing;
+pub mod project;
 pub mod scoping;
+pub mod type_parameters;
 pub mod usages;
 pub mod utils;
+pub mod visitor_dump;
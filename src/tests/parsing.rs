@@ -1,4 +1,17 @@
-use crate::tests::utils::{run_test, TestScope, TestSpec};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    config::Config,
+    dependency_graph::ModuleKind,
+    parsing::{
+        decode_source, find_module_files, has_dynamic_exports_comment, has_generated_header,
+        module_from_source, parse_all_modules, parse_with_jsx_fallback, visit_module_source,
+    },
+    tests::utils::{run_test, TestScope, TestSpec},
+};
 
 #[test]
 pub fn ts_type() {
@@ -58,3 +71,278 @@ pub fn type_and_value_of_same_name() {
 
     run_test(spec);
 }
+
+#[test]
+pub fn dynamic_exports_comment() {
+    assert!(has_dynamic_exports_comment(
+        "// customs-dynamic-exports\nexport const a = 1;"
+    ));
+    assert!(has_dynamic_exports_comment(
+        "/**\n * Generated at build time.\n * customs-dynamic-exports\n */"
+    ));
+    assert!(!has_dynamic_exports_comment(
+        "const note = \"customs-dynamic-exports\";"
+    ));
+}
+
+#[test]
+pub fn jsx_in_ts_module_falls_back_to_tsx() {
+    let parse = |source: &'static str| {
+        parse_with_jsx_fallback(ModuleKind::TS, |kind| {
+            module_from_source(String::from(source), kind)
+        })
+        .map(|(_, kind)| kind)
+    };
+
+    assert_eq!(
+        parse("const a = <T>(value: unknown) => value as T;").unwrap(),
+        ModuleKind::TS
+    );
+    assert_eq!(
+        parse("export const App = () => <div />;").unwrap(),
+        ModuleKind::TSX
+    );
+    assert!(parse("export const = ;").is_err());
+}
+
+#[test]
+pub fn source_encodings() {
+    assert_eq!(
+        decode_source(b"\xEF\xBB\xBFexport const a = 1;".to_vec()),
+        Ok(String::from("export const a = 1;"))
+    );
+    assert_eq!(
+        decode_source(b"export const a = 1;".to_vec()),
+        Ok(String::from("export const a = 1;"))
+    );
+    assert!(decode_source(b"\xFF\xFEe\x00x\x00".to_vec()).is_err());
+    assert!(decode_source(b"const a = \"\xE4\";".to_vec()).is_err());
+}
+
+#[test]
+pub fn include_and_ignore_patterns() {
+    let root = std::env::temp_dir().join(format!("customs-include-{}", std::process::id()));
+
+    for file in ["src/a.ts", "src/a.generated.ts", "scripts/b.ts"] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "export const a = 1;").unwrap();
+    }
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        include_patterns: vec![String::from("src/**")],
+        ignore_patterns: vec![String::from("**/*.generated.ts")],
+        ..Config::default()
+    };
+
+    let modules = parse_all_modules(&config).unwrap();
+    let paths = modules
+        .values()
+        .map(|module| module.path.root_relative.strip_prefix(&root).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(paths, vec![std::path::Path::new("src/a.ts")]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn module_files_are_found_without_parsing() {
+    let root = std::env::temp_dir().join(format!("customs-list-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+
+    for (file, source) in [
+        ("src/a.ts", "export const a = 1;"),
+        ("src/b.tsx", "export const b = <div />;"),
+        ("src/broken.ts", "export const = ;"),
+        ("src/types.d.ts", "export type C = string;"),
+        ("src/readme.md", "# Readme"),
+    ] {
+        std::fs::write(root.join(file), source).unwrap();
+    }
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        ..Config::default()
+    };
+
+    let mut files = find_module_files(&config)
+        .unwrap()
+        .map(|file| (file.path.strip_prefix(&root).unwrap().to_owned(), file.kind))
+        .collect::<Vec<_>>();
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    assert_eq!(
+        files,
+        vec![
+            (PathBuf::from("src/a.ts"), ModuleKind::TS),
+            (PathBuf::from("src/b.tsx"), ModuleKind::TSX),
+            (PathBuf::from("src/broken.ts"), ModuleKind::TS),
+            (PathBuf::from("src/types.d.ts"), ModuleKind::DTS),
+        ]
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn memory_budget_stops_parsing() {
+    let root = std::env::temp_dir().join(format!("customs-budget-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+
+    for file in ["src/a.ts", "src/b.ts"] {
+        std::fs::write(root.join(file), "export const a = 1;").unwrap();
+    }
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        max_memory: Some(1),
+        ..Config::default()
+    };
+
+    let err = parse_all_modules(&config).err().unwrap();
+    assert!(err
+        .to_string()
+        .starts_with("Memory budget of 0 MB exceeded"));
+
+    let config = Config {
+        max_memory: Some(usize::MAX),
+        ..config
+    };
+    assert_eq!(parse_all_modules(&config).unwrap().len(), 2);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn strict_mode_fails_on_unparsable_modules() {
+    let root = std::env::temp_dir().join(format!("customs-strict-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/a.ts"), "export const a = 1;").unwrap();
+    std::fs::write(root.join("src/broken.ts"), "export const = ;").unwrap();
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        ..Config::default()
+    };
+    assert_eq!(parse_all_modules(&config).unwrap().len(), 1);
+
+    let config = Config {
+        strict: true,
+        ..config
+    };
+    let err = parse_all_modules(&config).err().unwrap();
+    assert!(err.to_string().starts_with("1 modules could not be parsed"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn cancelled_analysis_parses_nothing() {
+    let root = std::env::temp_dir().join(format!("customs-cancel-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/a.ts"), "export const a = 1;").unwrap();
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        ..Config::default()
+    };
+    let token = config.cancellation.clone();
+    token.cancel();
+
+    assert!(config.cancellation.is_cancelled());
+    assert!(parse_all_modules(&config).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn hidden_folders_and_extra_ignore_files() {
+    let root = std::env::temp_dir().join(format!("customs-walk-{}", std::process::id()));
+
+    for file in ["src/a.ts", "src/legacy/b.ts", ".storybook/preview.ts"] {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "export const a = 1;").unwrap();
+    }
+
+    std::fs::write(root.join(".eslintignore"), "legacy/\n").unwrap();
+
+    let find_files = |config: &Config| {
+        let mut files = find_module_files(config)
+            .unwrap()
+            .map(|file| file.path.strip_prefix(&root).unwrap().to_owned())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        ..Config::default()
+    };
+
+    assert_eq!(
+        find_files(&config),
+        vec![PathBuf::from("src/a.ts"), PathBuf::from("src/legacy/b.ts")]
+    );
+
+    let config = Config {
+        root: Arc::new(root.clone()),
+        ignore_file_names: vec![String::from(".eslintignore")],
+        include_hidden: true,
+        ..Config::default()
+    };
+
+    assert_eq!(
+        find_files(&config),
+        vec![
+            PathBuf::from(".storybook/preview.ts"),
+            PathBuf::from("src/a.ts")
+        ]
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+pub fn hashbang() {
+    let source = "#!/usr/bin/env node\nimport { run } from './cli';\nrun();\n";
+
+    for kind in [ModuleKind::TS, ModuleKind::TSX] {
+        let (_, module) = module_from_source(String::from(source), kind).unwrap();
+        assert_eq!(module.shebang.as_deref(), Some("/usr/bin/env node"));
+        assert_eq!(module.body.len(), 2);
+    }
+}
+
+#[test]
+pub fn module_source_without_file() {
+    let source = "import { a } from './a';\nexport const b = <div>{a}</div>;\n";
+    let config = Config::default();
+
+    let visitor =
+        visit_module_source(Path::new("src/missing.tsx"), String::from(source), &config).unwrap();
+
+    assert_eq!(visitor.exports.len(), 1);
+    assert!(visitor.imports.contains_key("./a"));
+    assert!(visit_module_source(Path::new("README.md"), String::new(), &config).is_err());
+}
+
+#[test]
+pub fn generated_headers() {
+    assert!(has_generated_header("// @generated\nexport const a = 1;\n"));
+    assert!(has_generated_header(
+        "#!/usr/bin/env node\n/* eslint-disable */\n// Code generated by protoc-gen-ts. DO NOT EDIT.\nexport {};\n"
+    ));
+    assert!(has_generated_header(
+        "/**\n * @generated by graphql-codegen\n */\n"
+    ));
+
+    // Only the header counts, not mentions later in the module
+    assert!(!has_generated_header(
+        "export const a = 1;\n// @generated\n"
+    ));
+    assert!(!has_generated_header("// Generated files are ignored\n"));
+}
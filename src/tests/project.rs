@@ -0,0 +1,294 @@
+use std::time::Duration;
+
+use crate::{
+    analysis::UnusedSuppression,
+    config::{Config, ModuleExtension, Rule, Severity},
+    dependency_graph::ModuleKind,
+    tests::utils::{unused_exports, TestProject},
+};
+
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+#[test]
+pub fn reexports_through_barrel() {
+    let analysis = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import { formatDate } from './utils';\nconsole.log(formatDate());",
+        )
+        .file(
+            "src/utils/index.ts",
+            "export { formatDate, parseDate } from './dates';",
+        )
+        .file(
+            "src/utils/dates.ts",
+            "export const formatDate = () => '';\nexport const parseDate = () => 0;",
+        )
+        .analyze();
+
+    assert_eq!(
+        unused_exports(&analysis),
+        vec![
+            "src/utils/dates.ts:parseDate",
+            "src/utils/index.ts:parseDate"
+        ]
+    );
+}
+
+#[test]
+pub fn wildcard_import_uses_all_exports() {
+    let analysis = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import * as math from './math';\nimport { unused } from './other';\nconsole.log(math, unused);",
+        )
+        .file(
+            "src/math.ts",
+            "export const add = 1;\nexport const subtract = 2;",
+        )
+        .file(
+            "src/other.ts",
+            "export const unused = 1;\nexport const reallyUnused = 2;",
+        )
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), vec!["src/other.ts:reallyUnused"]);
+}
+
+#[test]
+pub fn tsconfig_path_aliases() {
+    let analysis = TestProject::default()
+        .tsconfig(r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@/*": ["src/*"] } } }"#)
+        .file(
+            "src/index.ts",
+            "import { used } from '@/lib';\nconsole.log(used);",
+        )
+        .file(
+            "src/lib.ts",
+            "export const used = 1;\nexport const unused = 2;",
+        )
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), vec!["src/lib.ts:unused"]);
+}
+
+#[test]
+pub fn dependency_usage_across_modules() {
+    let analysis = TestProject::default()
+        .package_json(
+            r#"{ "name": "app", "dependencies": { "react": "*", "lodash": "*", "zod": "*" } }"#,
+        )
+        .file(
+            "src/index.ts",
+            "import React from 'react';\nimport './schema';\nconsole.log(React);",
+        )
+        .file("src/schema.ts", "import { z } from 'zod';\nconsole.log(z);")
+        .analyze();
+
+    assert_eq!(
+        analysis.unused_dependencies,
+        Some(vec![String::from("lodash")])
+    );
+}
+
+#[test]
+pub fn exports_used_by_other_workspace_packages() {
+    let (analyses, unused_package_exports) = TestProject::default()
+        .package_json(r#"{ "private": true, "workspaces": ["packages/*"] }"#)
+        .file(
+            "customs.json",
+            r#"{ "ignore": ["packages/ui/src/legacy/**"] }"#,
+        )
+        .file(
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "main": "src/index.ts" }"#,
+        )
+        .file(
+            "packages/ui/src/index.ts",
+            "export const Button = 1;\nexport const Unused = 2;",
+        )
+        .file("packages/ui/src/legacy/old.ts", "export const old = 1;")
+        .file(
+            "packages/app/package.json",
+            r#"{ "name": "app", "dependencies": { "ui": "*" } }"#,
+        )
+        .file(
+            "packages/app/src/index.ts",
+            "import { Button } from 'ui';\nconsole.log(Button);",
+        )
+        .analyze_workspaces();
+
+    let unused_package_exports = unused_package_exports
+        .iter()
+        .map(|export| format!("{}:{}", export.package, export.name))
+        .collect::<Vec<_>>();
+    assert_eq!(unused_package_exports, vec!["ui:Unused"]);
+
+    // The ignore patterns of the root customs.json apply to the packages as well
+    assert_eq!(analyses.len(), 2);
+    for analysis in &analyses {
+        assert_eq!(unused_exports(analysis), Vec::<String>::new());
+    }
+}
+
+#[test]
+pub fn customs_ignore_comments() {
+    let analysis = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import { used, alsoUsed } from './lib';\nconsole.log(used, alsoUsed);",
+        )
+        .file(
+            "src/lib.ts",
+            "export const used = 1;\n// customs-ignore\nexport const hidden = 2;\nexport const reported = 3;\nexport const alsoUsed = 4; /* customs-ignore */",
+        )
+        .configure(|config| config.report_unused_suppressions = true)
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), vec!["src/lib.ts:reported"]);
+
+    let unused_comments = analysis
+        .unused_suppressions
+        .iter()
+        .map(|suppression| match suppression {
+            UnusedSuppression::IgnoreComment(location) => location.line(),
+            other => panic!("Unexpected suppression {:?}", other),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(unused_comments, vec![5]);
+}
+
+#[test]
+pub fn configured_module_extensions() {
+    let analysis = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import { Button } from './Button';\nconsole.log(Button);",
+        )
+        .file(
+            "src/Button.web.tsx",
+            "export const Button = () => <button />;\nexport const Unused = 1;",
+        )
+        .configure(|config| {
+            config.extensions.push(ModuleExtension {
+                suffix: String::from(".web.tsx"),
+                kind: ModuleKind::TSX,
+            })
+        })
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), vec!["src/Button.web.tsx:Unused"]);
+}
+
+#[test]
+pub fn directory_package_entry_point() {
+    let analysis = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import { Button } from './components/Button';\nconsole.log(Button);",
+        )
+        .file(
+            "src/components/Button/package.json",
+            r#"{ "main": "./Button.js" }"#,
+        )
+        .file(
+            "src/components/Button/Button.tsx",
+            "export const Button = 1;",
+        )
+        .file("src/components/Button/index.ts", "export const Button = 2;")
+        .analyze();
+
+    assert_eq!(
+        unused_exports(&analysis),
+        vec!["src/components/Button/index.ts:Button"]
+    );
+}
+
+#[test]
+pub fn package_json_entries_are_entry_points() {
+    let analysis = TestProject::default()
+        .package_json(r#"{ "name": "lib", "exports": { ".": { "import": "./src/lib.js" } } }"#)
+        .file("src/lib.ts", "export { format } from './format';")
+        .file(
+            "src/format.ts",
+            "export const format = 1;\nexport const parse = 2;",
+        )
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), vec!["src/format.ts:parse"]);
+}
+
+#[test]
+pub fn rule_severities_decide_errors_and_warnings() {
+    let analysis = TestProject::default()
+        .file("src/index.ts", "console.log('index');")
+        .file("src/lib.ts", "export const unused = 1;")
+        .analyze();
+    assert_eq!(unused_exports(&analysis), vec!["src/lib.ts:unused"]);
+
+    let config = Config {
+        max_warnings: Some(0),
+        ..Config::default()
+    };
+    // The unused export, and src/lib.ts which can't be reached from the entry point.
+    assert!(!analysis.has_errors(&config));
+    assert_eq!(analysis.warning_count(&config), 2);
+    assert!(config.exceeds_max_warnings(2));
+    assert!(!config.exceeds_max_warnings(0));
+
+    let config = Config {
+        rule_severities: [(Rule::UnusedExports, Severity::Error)]
+            .into_iter()
+            .collect(),
+        ..Config::default()
+    };
+    assert!(analysis.has_errors(&config));
+    assert_eq!(analysis.warning_count(&config), 1);
+}
+
+#[test]
+pub fn metrics_of_a_run_serialize() {
+    let metrics = TestProject::default()
+        .file(
+            "src/index.ts",
+            "import { a } from './lib';\nconsole.log(a);",
+        )
+        .file("src/lib.ts", "export const a = 1;")
+        .analyze_with_metrics();
+    metrics.record_phase("Analysis", Duration::from_millis(1));
+    metrics.record_total();
+
+    let json: Value =
+        serde_json::from_str(&serde_json::to_string(&metrics.to_json()).unwrap()).unwrap();
+    assert_eq!(json["modules"]["parsed"], 2);
+    assert_eq!(json["imports"]["resolved"], 1);
+    assert!(json["totalMs"].is_u64());
+    assert_eq!(json["phases"][1]["name"], "Total");
+    assert!(json["phases"][1]["ms"].is_u64());
+}
+
+#[test]
+pub fn only_and_fail_on_select_the_analyses() {
+    fn configure(config: &mut Config) {
+        config.only_rules = vec![Rule::UnusedDependencies];
+        config.fail_on_rules = vec![Rule::UnusedDependencies];
+    }
+
+    let analysis = TestProject::default()
+        .package_json(r#"{ "name": "app", "dependencies": { "lodash": "*" } }"#)
+        .file("src/index.ts", "export const unused = 1;")
+        .configure(configure)
+        .analyze();
+
+    assert_eq!(unused_exports(&analysis), Vec::<String>::new());
+    assert_eq!(
+        analysis.unused_dependencies,
+        Some(vec![String::from("lodash")])
+    );
+
+    let mut config = Config::default();
+    configure(&mut config);
+    assert!(analysis.has_errors(&config));
+}
@@ -1,4 +1,7 @@
-use crate::tests::utils::{run_test, TestScope, TestSpec};
+use crate::{
+    dependency_graph::ExportName,
+    tests::utils::{parse_and_analyze, parse_and_visit, run_test, TestScope, TestSpec},
+};
 
 #[test]
 pub fn block() {
@@ -319,7 +322,6 @@ pub fn ts_declare_module() {
         }
     "#;
 
-    // TODO: This misses the reference to React.FC
     let spec = TestSpec {
         source,
         exports: vec![],
@@ -335,7 +337,7 @@ pub fn ts_declare_module() {
             inner: vec![
                 TestScope {
                     bindings: vec!["content"],
-                    type_references: vec!["SvgProps"],
+                    type_references: vec!["React", "SvgProps"],
                     ambiguous_references: vec!["content"],
                     ..Default::default()
                 },
@@ -353,3 +355,148 @@ pub fn ts_declare_module() {
 
     run_test(spec);
 }
+
+#[test]
+pub fn ts_declare_module_exports() {
+    let source = r#"
+        declare module "*.svg" {
+            const content: string;
+            export default content;
+        }
+
+        declare module "icons" {
+            export const Icon: number;
+            export interface IconProps { }
+            const internal: string;
+            export { internal as alias };
+        }
+
+        declare namespace Global {
+            export const value: number;
+        }
+    "#;
+
+    let visitor = parse_and_visit("test.d.ts", source);
+
+    assert!(visitor.exports.is_empty());
+
+    let modules = visitor
+        .ambient_modules
+        .iter()
+        .map(|module| {
+            let exports = module
+                .exports
+                .iter()
+                .map(|export| export.name.clone())
+                .collect::<Vec<_>>();
+
+            (module.name.as_str(), exports)
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        modules,
+        vec![
+            ("*.svg", vec![ExportName::Default]),
+            (
+                "icons",
+                vec![
+                    ExportName::Named("Icon".into()),
+                    ExportName::Named("IconProps".into()),
+                    ExportName::Named("alias".into()),
+                ]
+            ),
+        ]
+    );
+}
+
+fn locally_used_exports(source: &'static str) -> Vec<String> {
+    let module = parse_and_analyze("test.ts", source);
+
+    let mut names = module
+        .exports
+        .iter()
+        .filter(|export| export.usage.get().used_locally)
+        .map(|export| export.name.to_string())
+        .collect::<Vec<_>>();
+
+    names.sort();
+    names
+}
+
+#[test]
+pub fn shadowed_export_is_not_used_locally() {
+    let source = r#"
+        export const foo = 1
+
+        function bar() {
+            const foo = 2
+            return foo
+        }
+    "#;
+
+    assert_eq!(locally_used_exports(source), Vec::<String>::new());
+}
+
+#[test]
+pub fn shadowed_export_used_outside_shadowing_scope() {
+    let source = r#"
+        export const foo = 1
+
+        function bar() {
+            const foo = 2
+            return foo
+        }
+
+        function baz() {
+            return foo
+        }
+    "#;
+
+    assert_eq!(locally_used_exports(source), vec!["foo"]);
+}
+
+#[test]
+pub fn shadowing_is_separate_for_values_and_types() {
+    let source = r#"
+        export const Foo = 1
+        export type Bar = number
+        export const Baz = 1
+
+        function f() {
+            type Foo = string
+            return Foo
+        }
+
+        function g() {
+            const Bar = 1
+            let x: Bar = Bar
+        }
+
+        function h() {
+            const Baz = 2
+            return Baz
+        }
+    "#;
+
+    assert_eq!(locally_used_exports(source), vec!["Bar", "Foo"]);
+}
+
+#[test]
+pub fn ambiguous_references_see_either_binding() {
+    let source = r#"
+        export const Foo = 1
+        export type Bar = number
+        export const Baz = 1
+
+        declare module "augmented" {
+            type Foo = string
+            const Baz: string
+            type Baz = string
+            export { Foo, Bar, Baz }
+        }
+    "#;
+
+    // Foo still refers to the value, and Baz is shadowed as both a value and a type
+    assert_eq!(locally_used_exports(source), vec!["Bar", "Foo"]);
+}
@@ -0,0 +1,72 @@
+use crate::tests::utils::parse_and_analyze;
+
+fn unused_type_parameters(source: &'static str) -> Vec<(String, String)> {
+    parse_and_analyze("unknown.ts", source)
+        .unused_type_parameters
+        .into_iter()
+        .map(|param| (param.declaration.to_string(), param.name.to_string()))
+        .collect()
+}
+
+#[test]
+pub fn used_in_function_params() {
+    let source = r#"
+        export function identity<T>(x: T) { return x }
+    "#;
+
+    assert!(unused_type_parameters(source).is_empty());
+}
+
+#[test]
+pub fn unused_in_function() {
+    let source = r#"
+        export function foo<T, U>(x: T): number { return 10 }
+    "#;
+
+    assert_eq!(
+        vec![(String::from("foo"), String::from("U"))],
+        unused_type_parameters(source)
+    );
+}
+
+#[test]
+pub fn unused_in_type_alias() {
+    let source = r#"
+        export type Foo<T> = { a: string }
+    "#;
+
+    assert_eq!(
+        vec![(String::from("Foo"), String::from("T"))],
+        unused_type_parameters(source)
+    );
+}
+
+#[test]
+pub fn used_in_interface() {
+    let source = r#"
+        export interface Foo<T> { a: T[] }
+    "#;
+
+    assert!(unused_type_parameters(source).is_empty());
+}
+
+#[test]
+pub fn shadowed_in_inner_scope() {
+    let source = r#"
+        export type Foo<T> = <T>(x: T) => T
+    "#;
+
+    assert_eq!(
+        vec![(String::from("Foo"), String::from("T"))],
+        unused_type_parameters(source)
+    );
+}
+
+#[test]
+pub fn not_exported() {
+    let source = r#"
+        type Foo<T> = { a: string }
+    "#;
+
+    assert!(unused_type_parameters(source).is_empty());
+}
@@ -1,4 +1,14 @@
-use crate::tests::utils::{run_test, TestScope, TestSpec};
+use std::path::PathBuf;
+
+use swc_atoms::JsWord;
+use swc_ecma_visit::Visit;
+
+use crate::{
+    dependency_graph::ModuleKind,
+    module_visitor::ModuleVisitor,
+    parsing::module_from_source,
+    tests::utils::{parse_and_analyze, parse_and_visit, run_test, TestScope, TestSpec},
+};
 
 #[test]
 pub fn typeof_uses_variable() {
@@ -592,6 +602,37 @@ pub fn as_cast_object_literal() {
     run_test(spec);
 }
 
+// `satisfies` (TypeScript 4.9) doesn't parse with the current swc_ecma_parser, so it's not covered here yet.
+#[test]
+pub fn nested_casts() {
+    let source = r#"
+        type Foo = string
+        type Bar = number
+        type Baz = boolean
+        const x = { a: { b: ["c" as Foo] }, d: [[1 as unknown as Bar]] } as const
+        const y = [{ e: <Baz>null }]
+    "#;
+
+    let spec = TestSpec {
+        source,
+        exports: vec![],
+        imports: vec![],
+        scope: TestScope {
+            type_bindings: vec!["Foo", "Bar", "Baz"],
+            bindings: vec!["x", "y"],
+            type_references: vec!["Foo", "Bar", "Baz"],
+            inner: vec![
+                TestScope::default(),
+                TestScope::default(),
+                TestScope::default(),
+            ],
+            ..Default::default()
+        },
+    };
+
+    run_test(spec);
+}
+
 #[test]
 pub fn as_cast_mapped_type_literal() {
     let source = r#"
@@ -761,3 +802,150 @@ pub fn class_implements() {
 
     run_test(spec);
 }
+
+#[test]
+pub fn class_heritage() {
+    let source = r#"
+        class A<T> extends B<Props> implements C, D.E<T> { }
+    "#;
+
+    let spec = TestSpec {
+        source,
+        exports: vec![],
+        imports: vec![],
+        scope: TestScope {
+            bindings: vec!["A"],
+            type_bindings: vec!["A", "T"],
+            references: vec!["B"],
+            type_references: vec!["Props", "C", "D", "T"],
+            inner: vec![TestScope::default()],
+            ..Default::default()
+        },
+    };
+
+    run_test(spec);
+
+    let visitor = parse_and_visit("test.ts", source);
+    assert!(visitor.property_accesses[&JsWord::from("D")].contains(&JsWord::from("E")));
+}
+
+#[test]
+pub fn type_predicate_references() {
+    let source = r#"
+        function isFoo(value: unknown): value is Foo { return true }
+        function assertBar(value: unknown): asserts value is Bar { }
+        const isBaz = (value: unknown): value is Baz.Item => true
+    "#;
+
+    let predicate_scope = |type_reference| TestScope {
+        bindings: vec!["value"],
+        type_references: vec![type_reference],
+        ..Default::default()
+    };
+
+    let spec = TestSpec {
+        source,
+        exports: vec![],
+        imports: vec![],
+        scope: TestScope {
+            bindings: vec!["isFoo", "assertBar", "isBaz"],
+            inner: vec![
+                predicate_scope("Foo"),
+                predicate_scope("Bar"),
+                predicate_scope("Baz"),
+            ],
+            ..Default::default()
+        },
+    };
+
+    run_test(spec);
+}
+
+fn visit_with_decorator_metadata(
+    source: &'static str,
+    emit_decorator_metadata: bool,
+) -> ModuleVisitor {
+    let (source_map, module) = module_from_source(String::from(source), ModuleKind::TS).unwrap();
+
+    let mut visitor = ModuleVisitor::new(PathBuf::from("test.ts"), source_map);
+    visitor.set_emit_decorator_metadata(emit_decorator_metadata);
+    visitor.visit_module(&module, &module);
+    visitor
+}
+
+#[test]
+pub fn decorator_references() {
+    let source = r#"
+        @Injectable()
+        class UsersController {
+            constructor(private readonly users: UsersService, @Inject(TOKEN) config: Config) { }
+
+            @Get()
+            find(@Query() query: QueryDto): ResultDto { return null }
+
+            @Input() item: Item;
+            other: Other;
+        }
+    "#;
+
+    let visitor = visit_with_decorator_metadata(source, false);
+
+    for name in ["Injectable", "Inject", "TOKEN", "Get", "Query", "Input"] {
+        assert!(
+            visitor.direct_references.contains(&JsWord::from(name)),
+            "{}",
+            name
+        );
+    }
+
+    assert!(!visitor
+        .direct_references
+        .contains(&JsWord::from("UsersService")));
+
+    // The emitted metadata refers to the classes of the types
+    let visitor = visit_with_decorator_metadata(source, true);
+
+    for name in ["UsersService", "Config", "QueryDto", "ResultDto", "Item"] {
+        assert!(
+            visitor.direct_references.contains(&JsWord::from(name)),
+            "{}",
+            name
+        );
+    }
+
+    assert!(!visitor.direct_references.contains(&JsWord::from("Other")));
+}
+
+#[test]
+pub fn eval_makes_exports_possibly_used() {
+    let source = r#"
+        export const config = { debug: true }
+        export function run(code: string) {
+            return eval(code)
+        }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    assert!(module
+        .exports
+        .iter()
+        .all(|export| export.usage.get().used_dynamically));
+}
+
+#[test]
+pub fn eval_method_is_not_dynamic() {
+    let source = r#"
+        export const config = { debug: true }
+        export function run(code: string) {
+            return sandbox.eval(code)
+        }
+    "#;
+
+    let module = parse_and_analyze("test.ts", source);
+
+    assert!(module
+        .exports
+        .iter()
+        .all(|export| !export.usage.get().used_dynamically));
+}
@@ -1,13 +1,25 @@
 use std::{
     borrow::Borrow,
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use crate::{
-    dependency_graph::{ExportName, ImportName},
+    config::Config,
+    dependency_graph::{
+        normalize_module_path, Export, ExportKind, ExportName, ImportName, Module, ModuleKind,
+        ModulePath, ModuleSourceAndLine, Visibility::Exported,
+    },
+    metrics::Metrics,
     module_visitor::{ModuleVisitor, Scope, ScopeId},
-    parsing::module_from_source,
+    parsing::{analyze_module, module_from_source},
+    project::{analyze_project, load_project_config, ProjectAnalysis},
+    workspaces::{analyze_workspaces, find_unused_package_exports, UnusedPackageExport},
 };
 
 use anyhow::Context;
@@ -16,19 +28,192 @@ use swc_atoms::JsWord;
 use swc_ecma_visit::Visit;
 
 pub fn parse_and_visit(virtual_path: &'static str, source: &'static str) -> ModuleVisitor {
-    let (source_map, module) = module_from_source(
-        String::from(source),
-        crate::dependency_graph::ModuleKind::TS,
-    )
-    .unwrap();
+    parse_and_visit_with_defines(virtual_path, source, &[])
+}
+
+pub fn parse_and_visit_with_defines(
+    virtual_path: &'static str,
+    source: &'static str,
+    defines: &[(&'static str, &'static str)],
+) -> ModuleVisitor {
+    let kind = if virtual_path.ends_with(".tsx") {
+        ModuleKind::TSX
+    } else {
+        ModuleKind::TS
+    };
+
+    let (source_map, module) = module_from_source(String::from(source), kind).unwrap();
 
     // println!("{:#?}", module);
 
     let mut visitor = ModuleVisitor::new(PathBuf::from(virtual_path), source_map);
+    visitor.set_defines(Arc::new(
+        defines
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    ));
     visitor.visit_module(&module, &module);
     visitor
 }
 
+pub fn parse_and_analyze(virtual_path: &'static str, source: &'static str) -> Module {
+    let visitor = parse_and_visit(virtual_path, source);
+    let module = test_module(virtual_path, ModuleKind::TS);
+
+    analyze_module(module, visitor, &Config::default()).unwrap()
+}
+
+/// An empty module for building module graphs by hand. The root is empty, so `file` is also the path shown in
+/// findings, and the normalized path is `file` without its extension.
+pub fn test_module(file: &str, kind: ModuleKind) -> Module {
+    let root = Path::new("");
+
+    Module::new(
+        ModulePath {
+            root: Arc::new(root.to_owned()),
+            root_relative: Arc::new(PathBuf::from(file)),
+            normalized: normalize_module_path(root, Path::new(file)).unwrap(),
+        },
+        kind,
+    )
+}
+
+pub fn test_export(name: ExportName, kind: ExportKind) -> Export {
+    Export::new(name, kind, Exported, ModuleSourceAndLine::new_mock())
+}
+
+/// A module with named value exports.
+pub fn module_with_exports(file: &str, names: &[&str]) -> Module {
+    let mut module = test_module(file, ModuleKind::TS);
+
+    for name in names {
+        module.add_export(test_export(ExportName::named(*name), ExportKind::Value));
+    }
+
+    module
+}
+
+/// Whether any scope of the module references `name` as a value.
+pub fn is_referenced(visitor: &ModuleVisitor, name: &'static str) -> bool {
+    let name = JsWord::from(name);
+
+    visitor
+        .scopes
+        .iter()
+        .any(|scope| scope.references.contains(&name))
+}
+
+/// Whether any scope of the module references `name` as a type.
+pub fn is_type_referenced(visitor: &ModuleVisitor, name: &'static str) -> bool {
+    let name = JsWord::from(name);
+
+    visitor
+        .scopes
+        .iter()
+        .any(|scope| scope.type_references.contains(&name))
+}
+
+/// A project of several modules, written to a temporary folder and analyzed like `customs check` would.
+#[derive(Default)]
+pub struct TestProject {
+    files: Vec<(&'static str, &'static str)>,
+    configure: Option<fn(&mut Config)>,
+}
+
+impl TestProject {
+    /// Adds a file, with a path relative to the project root.
+    pub fn file(mut self, path: &'static str, source: &'static str) -> Self {
+        self.files.push((path, source));
+        self
+    }
+
+    pub fn package_json(self, source: &'static str) -> Self {
+        self.file("package.json", source)
+    }
+
+    pub fn tsconfig(self, source: &'static str) -> Self {
+        self.file("tsconfig.json", source)
+    }
+
+    /// Adjusts the config after the project config has been loaded, like command line options would.
+    pub fn configure(mut self, configure: fn(&mut Config)) -> Self {
+        self.configure = Some(configure);
+        self
+    }
+
+    /// Parses the modules, resolves their imports and runs the analyses. The folder is removed afterwards.
+    pub fn analyze(self) -> ProjectAnalysis {
+        self.run(analyze_project)
+    }
+
+    /// Analyzes the project with metrics collection enabled, replacing any `configure`, and returns the metrics.
+    pub fn analyze_with_metrics(self) -> Arc<Metrics> {
+        self.configure(|config| config.metrics = Some(Arc::new(Metrics::new())))
+            .run(|config| {
+                analyze_project(config)?;
+                Ok(config.metrics.clone().unwrap())
+            })
+    }
+
+    /// Analyzes each workspace package listed in the root package.json, and finds the exports of the packages
+    /// which no other package imports.
+    pub fn analyze_workspaces(self) -> (Vec<ProjectAnalysis>, Vec<UnusedPackageExport>) {
+        self.run(|config| {
+            Ok((
+                analyze_workspaces(config)?,
+                find_unused_package_exports(config)?,
+            ))
+        })
+    }
+
+    /// Writes the files to a temporary folder and runs `analyze` with the project config of the folder loaded.
+    fn run<T>(self, analyze: impl FnOnce(&Config) -> anyhow::Result<T>) -> T {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let root = std::env::temp_dir().join(format!(
+            "customs-project-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        for (path, source) in &self.files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+
+        let mut config = Config {
+            root: Arc::new(root.clone()),
+            ..Config::default()
+        };
+
+        let result = load_project_config(&mut config).and_then(|()| {
+            if let Some(configure) = self.configure {
+                configure(&mut config);
+            }
+
+            analyze(&config)
+        });
+        fs::remove_dir_all(&root).unwrap();
+        result.unwrap()
+    }
+}
+
+/// The unused exports of a project as `path:name`, with root-relative paths, in the order they're reported.
+pub fn unused_exports(analysis: &ProjectAnalysis) -> Vec<String> {
+    analysis
+        .unused_exports
+        .sorted_exports
+        .iter()
+        .map(|export| {
+            let path = export.location.path().strip_prefix(&analysis.root).unwrap();
+            format!("{}:{}", path.display(), export.name)
+        })
+        .collect()
+}
+
+#[derive(Default)]
 pub struct TestScope {
     pub(crate) references: Vec<&'static str>,
     pub(crate) type_references: Vec<&'static str>,
@@ -38,19 +223,6 @@ pub struct TestScope {
     pub(crate) inner: Vec<TestScope>,
 }
 
-impl Default for TestScope {
-    fn default() -> Self {
-        TestScope {
-            references: vec![],
-            type_references: vec![],
-            ambiguous_references: vec![],
-            bindings: vec![],
-            type_bindings: vec![],
-            inner: vec![],
-        }
-    }
-}
-
 use std::cmp::Eq;
 use std::hash::Hash;
 
@@ -91,10 +263,13 @@ impl<K: Hash + Eq> SetLike<K> for HashSet<K> {
     }
 }
 
+/// An import source, and the imported names with their local bindings.
+type TestImports = (&'static str, Vec<(&'static str, Option<&'static str>)>);
+
 pub struct TestSpec {
     pub(crate) source: &'static str,
     pub(crate) exports: Vec<&'static str>,
-    pub(crate) imports: Vec<(&'static str, Vec<(&'static str, Option<&'static str>)>)>,
+    pub(crate) imports: Vec<TestImports>,
     pub(crate) scope: TestScope,
 }
 
@@ -0,0 +1,56 @@
+use serde_json::json;
+
+use crate::{
+    tests::utils::parse_and_visit,
+    visitor_dump::{visitor_to_dot, visitor_to_json},
+};
+
+#[test]
+pub fn scope_tree_json() {
+    let source = r#"
+        import { b, a as c } from "./foo"
+        export function foo<T>(x: T) { return c }
+    "#;
+
+    let visitor = parse_and_visit("test.ts", source);
+    let dump = visitor_to_json(&visitor);
+
+    assert_eq!(
+        dump["imports"],
+        json!([{
+            "source": "./foo",
+            "names": [
+                { "imported_name": "b", "local_binding": "b" },
+                { "imported_name": "a", "local_binding": "c" },
+            ],
+        }])
+    );
+
+    assert_eq!(dump["exports"][0]["name"], json!("foo"));
+    assert_eq!(dump["exports"][0]["line"], json!(3));
+
+    let root = &dump["scope"];
+    assert_eq!(root["kind"], json!("Root"));
+    assert_eq!(
+        root["bindings"],
+        json!([{ "name": "foo", "kind": "Function", "line": 3 }])
+    );
+    assert_eq!(root["children"][0]["type_bindings"][0]["name"], json!("T"));
+}
+
+#[test]
+pub fn scope_tree_dot() {
+    let source = r#"
+        const a = "foo"
+        function foo() { return a.length }
+    "#;
+
+    let visitor = parse_and_visit("test.ts", source);
+    let dot = visitor_to_dot(&visitor);
+
+    assert!(dot.starts_with("digraph scopes {"));
+    assert!(dot.contains("subgraph \"cluster_0\""));
+    assert!(dot.contains("subgraph \"cluster_1\""));
+    assert!(dot.contains("\"binding_0_a\" [label=\"a\", shape=box];"));
+    assert!(dot.contains("\"ref_1_a\" -> \"binding_0_a\";"));
+}
@@ -1,14 +1,129 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
+use lazy_static::lazy_static;
+use regex::Regex;
 use relative_path::RelativePath;
 use serde::Deserialize;
 
-use crate::json_config::JsonConfig;
+use crate::{json_config::JsonConfig, parsing::normalize_package_import};
 
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CompilerOptions {
     type_roots: Option<Vec<String>>,
+    base_url: Option<String>,
+    paths: Option<HashMap<String, Vec<String>>>,
+    jsx: Option<String>,
+    jsx_import_source: Option<String>,
+    allow_js: Option<bool>,
+    check_js: Option<bool>,
+    module_resolution: Option<String>,
+    types: Option<Vec<String>>,
+    resolve_json_module: Option<bool>,
+    emit_decorator_metadata: Option<bool>,
+}
+
+/// The compiler options of tsconfig.json which affect how the project is parsed and how imports are resolved.
+#[derive(Debug, Default, Clone)]
+pub struct ProjectCompilerOptions {
+    /// The folder non-relative imports are resolved from before packages.
+    pub base_url: Option<PathBuf>,
+    /// Path aliases from `paths`, see `TsConfig::path_aliases`.
+    pub path_aliases: Vec<(String, Vec<PathBuf>)>,
+    /// The JSX mode in lowercase, e.g. `react-jsx` or `preserve`.
+    pub jsx: Option<String>,
+    /// The package providing the automatic JSX runtime, `react` by default.
+    pub jsx_import_source: Option<String>,
+    /// Whether JavaScript files are part of the project. `checkJs` implies `allowJs` unless it's set explicitly.
+    pub allow_js: bool,
+    /// The module resolution strategy in lowercase, e.g. `node`, `node16` or `bundler`.
+    pub module_resolution: Option<String>,
+    /// Packages whose global types are included. `None` includes all `@types` packages.
+    pub types: Option<Vec<String>>,
+    pub resolve_json_module: bool,
+    /// Whether the types of decorated class members are emitted as values.
+    pub emit_decorator_metadata: bool,
+}
+
+impl ProjectCompilerOptions {
+    /// Names of the packages listed in `types`, along with their `@types` packages, e.g. `node` and `@types/node`.
+    pub fn type_packages(&self) -> Vec<String> {
+        self.types
+            .iter()
+            .flatten()
+            .filter_map(|name| normalize_package_import(name))
+            .flat_map(|name| {
+                let types_package = match name.strip_prefix('@') {
+                    Some(scoped) => format!("@types/{}", scoped.replacen('/', "__", 1)),
+                    None => format!("@types/{}", name),
+                };
+
+                [name, types_package]
+            })
+            .collect()
+    }
+
+    /// The package whose JSX runtime (e.g. `react/jsx-runtime`) is imported implicitly by a module which uses JSX.
+    /// A `@jsxImportSource` comment in the source overrides the compiler options.
+    /// `None` with the classic runtime, where the factory (like `React`) is imported explicitly,
+    /// and with `preserve` or `react-native`, where another tool decides how JSX is compiled.
+    pub fn jsx_runtime_package(&self, source: &str) -> Option<String> {
+        lazy_static! {
+            static ref IMPORT_SOURCE_PRAGMA_RE: Regex =
+                Regex::new(r"(?m)(?://|/\*+|^\s*\*)\s*@jsxImportSource\s+(\S+)").unwrap();
+            static ref CLASSIC_RUNTIME_PRAGMA_RE: Regex =
+                Regex::new(r"(?m)(?://|/\*+|^\s*\*)\s*@jsxRuntime\s+classic\b").unwrap();
+        }
+
+        if let Some(captures) = IMPORT_SOURCE_PRAGMA_RE.captures(source) {
+            return normalize_package_import(&captures[1]);
+        }
+
+        if CLASSIC_RUNTIME_PRAGMA_RE.is_match(source) {
+            return None;
+        }
+
+        match self.jsx.as_deref() {
+            Some("react-jsx" | "react-jsxdev") => {
+                normalize_package_import(self.jsx_import_source.as_deref().unwrap_or("react"))
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the alias which matches the import source, and returns its targets with the wildcard substituted.
+    /// The alias with the longest prefix before its wildcard wins, like in TypeScript.
+    pub fn aliased_paths(&self, import_source: &str) -> Vec<PathBuf> {
+        let matching_alias = self
+            .path_aliases
+            .iter()
+            .filter_map(|(alias, targets)| {
+                let captured = match_path_alias(alias, import_source)?;
+                let prefix_len = alias.find('*').unwrap_or(alias.len());
+                Some((prefix_len, captured, targets))
+            })
+            .max_by_key(|(prefix_len, _, _)| *prefix_len);
+
+        match matching_alias {
+            Some((_, captured, targets)) => targets
+                .iter()
+                .map(|target| PathBuf::from(target.to_string_lossy().replacen('*', captured, 1)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Matches an alias like `@components/*` or `config` against an import source.
+/// Returns the part matched by the wildcard, which is empty for aliases without one.
+fn match_path_alias<'a>(alias: &str, import_source: &'a str) -> Option<&'a str> {
+    match alias.split_once('*') {
+        Some((prefix, suffix)) => import_source.strip_prefix(prefix)?.strip_suffix(suffix),
+        None => (alias == import_source).then_some(""),
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -21,10 +136,15 @@ impl JsonConfig for TsConfig {
     fn file_name() -> &'static str {
         "tsconfig.json"
     }
+
+    fn allows_comments() -> bool {
+        true
+    }
 }
 
 impl TsConfig {
-    pub fn normalized_type_roots(&self, tsconfig_file_path: &Path) -> Vec<PathBuf> {
+    /// All configured type roots, whether they exist or not.
+    pub fn type_roots(&self, tsconfig_file_path: &Path) -> Vec<PathBuf> {
         let root_folder = tsconfig_file_path
             .parent()
             .expect("tsconfig.json path should always have a parent");
@@ -32,12 +152,191 @@ impl TsConfig {
         match &self.compiler_options {
             Some(CompilerOptions {
                 type_roots: Some(roots),
+                ..
             }) => roots
                 .iter()
                 .map(|type_root| RelativePath::new(type_root).to_logical_path(root_folder))
-                .filter(|path| path.exists())
                 .collect(),
             _ => Vec::new(),
         }
     }
+
+    pub fn normalized_type_roots(&self, tsconfig_file_path: &Path) -> Vec<PathBuf> {
+        self.type_roots(tsconfig_file_path)
+            .into_iter()
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    pub fn base_url(&self, tsconfig_file_path: &Path) -> Option<PathBuf> {
+        let root_folder = tsconfig_file_path
+            .parent()
+            .expect("tsconfig.json path should always have a parent");
+
+        let base_url = self.compiler_options.as_ref()?.base_url.as_ref()?;
+        Some(RelativePath::new(base_url).to_logical_path(root_folder))
+    }
+
+    /// Path aliases from `compilerOptions.paths`, with each target resolved relative to the base URL.
+    /// Wildcards are kept as is, e.g. `@components/*` maps to `<base>/src/components/*`.
+    pub fn path_aliases(&self, tsconfig_file_path: &Path) -> Vec<(String, Vec<PathBuf>)> {
+        let base_folder = self.base_url(tsconfig_file_path).unwrap_or_else(|| {
+            tsconfig_file_path
+                .parent()
+                .expect("tsconfig.json path should always have a parent")
+                .to_owned()
+        });
+
+        let paths = match self
+            .compiler_options
+            .as_ref()
+            .and_then(|o| o.paths.as_ref())
+        {
+            Some(paths) => paths,
+            None => return Vec::new(),
+        };
+
+        let mut aliases = paths
+            .iter()
+            .map(|(alias, targets)| {
+                let targets = targets
+                    .iter()
+                    .map(|target| RelativePath::new(target).to_logical_path(&base_folder))
+                    .collect();
+
+                (alias.clone(), targets)
+            })
+            .collect::<Vec<_>>();
+
+        aliases.sort_by(|(a, _), (b, _)| a.cmp(b));
+        aliases
+    }
+
+    /// The options which are applied to the config.
+    pub fn project_compiler_options(&self, tsconfig_file_path: &Path) -> ProjectCompilerOptions {
+        let options = match &self.compiler_options {
+            Some(options) => options,
+            None => return ProjectCompilerOptions::default(),
+        };
+
+        ProjectCompilerOptions {
+            base_url: self.base_url(tsconfig_file_path),
+            path_aliases: self.path_aliases(tsconfig_file_path),
+            jsx: options.jsx.as_ref().map(|jsx| jsx.to_lowercase()),
+            jsx_import_source: options.jsx_import_source.clone(),
+            allow_js: options
+                .allow_js
+                .unwrap_or_else(|| options.check_js.unwrap_or(false)),
+            module_resolution: options
+                .module_resolution
+                .as_ref()
+                .map(|strategy| strategy.to_lowercase()),
+            types: options.types.clone(),
+            resolve_json_module: options.resolve_json_module.unwrap_or(false),
+            emit_decorator_metadata: options.emit_decorator_metadata.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiler_options() {
+        let tsconfig = serde_json::from_str::<TsConfig>(
+            r#"{
+                "compilerOptions": {
+                    "baseUrl": "src",
+                    "paths": { "@components/*": ["components/*"] },
+                    "jsx": "React-JSX",
+                    "allowJs": true,
+                    "moduleResolution": "Bundler",
+                    "types": ["node", "@testing-library/jest-dom"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let options = tsconfig.project_compiler_options(Path::new("project/tsconfig.json"));
+
+        assert_eq!(options.base_url, Some(PathBuf::from("project/src")));
+        assert_eq!(options.jsx.as_deref(), Some("react-jsx"));
+        assert!(options.allow_js);
+        assert!(!options.resolve_json_module);
+        assert_eq!(options.module_resolution.as_deref(), Some("bundler"));
+        assert_eq!(
+            options.type_packages(),
+            vec![
+                "node",
+                "@types/node",
+                "@testing-library/jest-dom",
+                "@types/testing-library__jest-dom"
+            ]
+        );
+        assert_eq!(
+            options.aliased_paths("@components/Button"),
+            vec![PathBuf::from("project/src/components/Button")]
+        );
+        assert!(options.aliased_paths("lodash").is_empty());
+    }
+
+    #[test]
+    fn check_js_implies_allow_js() {
+        let allow_js = |json: &str| {
+            serde_json::from_str::<TsConfig>(json)
+                .unwrap()
+                .project_compiler_options(Path::new("tsconfig.json"))
+                .allow_js
+        };
+
+        assert!(allow_js(r#"{ "compilerOptions": { "checkJs": true } }"#));
+        assert!(!allow_js(
+            r#"{ "compilerOptions": { "checkJs": true, "allowJs": false } }"#
+        ));
+        assert!(!allow_js(r#"{ "compilerOptions": {} }"#));
+    }
+
+    #[test]
+    fn jsx_runtime_package() {
+        let options = ProjectCompilerOptions {
+            jsx: Some(String::from("react-jsx")),
+            jsx_import_source: Some(String::from("preact")),
+            ..ProjectCompilerOptions::default()
+        };
+
+        assert_eq!(
+            options.jsx_runtime_package("export const A = () => <div />;"),
+            Some(String::from("preact"))
+        );
+        assert_eq!(
+            options.jsx_runtime_package("/** @jsxImportSource @emotion/react */\n"),
+            Some(String::from("@emotion/react"))
+        );
+        assert_eq!(
+            options.jsx_runtime_package(
+                "/**\n * Styled with Emotion.\n * @jsxImportSource @emotion/react\n */\n"
+            ),
+            Some(String::from("@emotion/react"))
+        );
+        assert_eq!(
+            options.jsx_runtime_package("/** @jsxRuntime classic */\n"),
+            None
+        );
+
+        let classic = ProjectCompilerOptions {
+            jsx: Some(String::from("react")),
+            ..ProjectCompilerOptions::default()
+        };
+        assert_eq!(classic.jsx_runtime_package(""), None);
+    }
+
+    #[test]
+    fn path_alias_matching() {
+        assert_eq!(match_path_alias("@/*", "@/utils/date"), Some("utils/date"));
+        assert_eq!(match_path_alias("*.css", "button.css"), Some("button"));
+        assert_eq!(match_path_alias("config", "config"), Some(""));
+        assert_eq!(match_path_alias("config", "config/dev"), None);
+        assert_eq!(match_path_alias("a*a", "a"), None);
+    }
 }
@@ -0,0 +1,261 @@
+//! Structured dumps of the module visitor state (JSON and Graphviz DOT), used by the `visit` debugging binary.
+
+use std::{collections::HashSet, fmt::Write};
+
+use itertools::Itertools;
+use serde_json::{json, Value};
+use swc_atoms::JsWord;
+
+use crate::module_visitor::{ModuleExport, ModuleVisitor, Scope, ScopeId};
+
+fn sorted_names(names: &HashSet<JsWord>) -> Vec<&str> {
+    names.iter().map(|name| &**name).sorted().collect()
+}
+
+fn scope_to_json(visitor: &ModuleVisitor, scope: &Scope) -> Value {
+    let bindings = scope
+        .bindings
+        .values()
+        .sorted_by(|a, b| (*a.name).cmp(&*b.name))
+        .map(|binding| {
+            json!({
+                "name": &*binding.name,
+                "kind": format!("{:?}", binding.kind),
+                "line": visitor.create_span_source(binding.span).line(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let type_bindings = scope
+        .type_bindings
+        .iter()
+        .sorted_by(|(a, _), (b, _)| (**a).cmp(&**b))
+        .map(|(name, binding)| {
+            json!({
+                "name": &**name,
+                "line": binding.source.line(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let children = scope
+        .children
+        .iter()
+        .map(|&child_id| scope_to_json(visitor, visitor.get_scope(child_id)))
+        .collect::<Vec<_>>();
+
+    json!({
+        "id": scope.id.index(),
+        "kind": format!("{:?}", scope.kind),
+        "bindings": bindings,
+        "type_bindings": type_bindings,
+        "references": sorted_names(&scope.references),
+        "type_references": sorted_names(&scope.type_references),
+        "ambiguous_references": sorted_names(&scope.ambiguous_references),
+        "has_dynamic_references": scope.has_dynamic_references,
+        "children": children,
+    })
+}
+
+fn exports_to_json(exports: &[ModuleExport]) -> Vec<Value> {
+    exports
+        .iter()
+        .map(|export| {
+            json!({
+                "name": export.name.to_string(),
+                "local_name": export.local_name.as_deref(),
+                "kind": format!("{:?}", export.kind),
+                "line": export.source.line(),
+                "reexported_from": export.reexported_from.as_ref().map(|(source, name)| {
+                    json!({
+                        "source": source,
+                        "name": name.to_string(),
+                    })
+                }),
+            })
+        })
+        .collect()
+}
+
+/// Converts the scope tree, exports and imports collected by the visitor into JSON.
+/// Names are sorted, so that the output is stable and can be used in golden-file tests.
+pub fn visitor_to_json(visitor: &ModuleVisitor) -> Value {
+    let ambient_modules = visitor
+        .ambient_modules
+        .iter()
+        .map(|module| {
+            json!({
+                "name": module.name,
+                "scope": module.scope.index(),
+                "exports": exports_to_json(&module.exports),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let imports = visitor
+        .imports
+        .iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(source, imports)| {
+            let names = imports
+                .iter()
+                .map(|import| {
+                    json!({
+                        "imported_name": import.imported_name.to_string(),
+                        "local_binding": import.local_binding.as_deref(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            json!({
+                "source": source,
+                "names": names,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "scope": scope_to_json(visitor, visitor.get_scope(ScopeId::root())),
+        "exports": exports_to_json(&visitor.exports),
+        "ambient_modules": ambient_modules,
+        "imports": imports,
+    })
+}
+
+#[derive(Clone, Copy)]
+enum ReferenceKind {
+    Value,
+    Type,
+    Ambiguous,
+}
+
+impl ReferenceKind {
+    fn node_prefix(self) -> &'static str {
+        match self {
+            ReferenceKind::Value => "ref",
+            ReferenceKind::Type => "type_ref",
+            ReferenceKind::Ambiguous => "ambiguous_ref",
+        }
+    }
+}
+
+/// Finds the scope which declares the binding a reference resolves to, returning the node id of the binding.
+fn resolve_reference_node(
+    visitor: &ModuleVisitor,
+    scope: &Scope,
+    name: &JsWord,
+    kind: ReferenceKind,
+) -> Option<String> {
+    let mut current = Some(scope);
+
+    while let Some(scope) = current {
+        let has_value = scope.bindings.contains_key(name);
+        let has_type = scope.type_bindings.contains_key(name);
+
+        match kind {
+            ReferenceKind::Value | ReferenceKind::Ambiguous if has_value => {
+                return Some(format!("binding_{}_{}", scope.id, name));
+            }
+            ReferenceKind::Type | ReferenceKind::Ambiguous if has_type => {
+                return Some(format!("type_binding_{}_{}", scope.id, name));
+            }
+            _ => {}
+        }
+
+        current = scope.parent.map(|parent| visitor.get_scope(parent));
+    }
+
+    None
+}
+
+fn write_scope_cluster(
+    visitor: &ModuleVisitor,
+    scope: &Scope,
+    depth: usize,
+    out: &mut String,
+    edges: &mut Vec<String>,
+) -> std::fmt::Result {
+    let indent = "    ".repeat(depth);
+
+    writeln!(out, "{}subgraph \"cluster_{}\" {{", indent, scope.id)?;
+    writeln!(
+        out,
+        "{}    label=\"scope {} ({:?})\";",
+        indent, scope.id, scope.kind
+    )?;
+
+    for binding in scope
+        .bindings
+        .values()
+        .sorted_by(|a, b| (*a.name).cmp(&*b.name))
+    {
+        writeln!(
+            out,
+            "{}    \"binding_{}_{}\" [label=\"{}\", shape=box];",
+            indent, scope.id, binding.name, binding.name
+        )?;
+    }
+
+    for name in scope.type_bindings.keys().sorted_by(|a, b| (**a).cmp(&**b)) {
+        writeln!(
+            out,
+            "{}    \"type_binding_{}_{}\" [label=\"{}\", shape=box, style=dashed];",
+            indent, scope.id, name, name
+        )?;
+    }
+
+    let references = [
+        (ReferenceKind::Value, &scope.references),
+        (ReferenceKind::Type, &scope.type_references),
+        (ReferenceKind::Ambiguous, &scope.ambiguous_references),
+    ];
+
+    for (kind, names) in references {
+        for name in names.iter().sorted_by(|a, b| (**a).cmp(&**b)) {
+            let node = format!("{}_{}_{}", kind.node_prefix(), scope.id, name);
+
+            writeln!(
+                out,
+                "{}    \"{}\" [label=\"{}\", shape=ellipse];",
+                indent, node, name
+            )?;
+
+            if let Some(target) = resolve_reference_node(visitor, scope, name, kind) {
+                edges.push(format!("    \"{}\" -> \"{}\";", node, target));
+            }
+        }
+    }
+
+    for &child_id in &scope.children {
+        write_scope_cluster(visitor, visitor.get_scope(child_id), depth + 1, out, edges)?;
+    }
+
+    writeln!(out, "{}}}", indent)
+}
+
+/// Renders the scope tree as a Graphviz graph: scopes are nested clusters, bindings are boxes
+/// (dashed for type bindings) and references are ellipses with an edge to the binding they resolve to.
+pub fn visitor_to_dot(visitor: &ModuleVisitor) -> String {
+    let mut out = String::new();
+    let mut edges = Vec::new();
+
+    out.push_str("digraph scopes {\n");
+    out.push_str("    compound=true;\n");
+
+    write_scope_cluster(
+        visitor,
+        visitor.get_scope(ScopeId::root()),
+        1,
+        &mut out,
+        &mut edges,
+    )
+    .expect("Writing to a String should never fail");
+
+    for edge in edges {
+        out.push_str(&edge);
+        out.push('\n');
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use rayon::prelude::*;
+use relative_path::RelativePath;
+
+use crate::{
+    config::{Config, Rule, Severity},
+    customs_config::build_glob_matcher,
+    dependency_graph::{namespace_member_name, ExportName, ImportName, ModuleSourceAndLine},
+    json_config::JsonConfig,
+    package_json::{PackageJson, PackageJsonIndex},
+    parsing::parse_all_modules,
+    project::{analyze_project, load_project_config, ProjectAnalysis},
+};
+
+/// Finds the workspace package folders listed in the `workspaces` field of the package.json in the index root.
+pub fn find_workspace_packages(index: &PackageJsonIndex) -> anyhow::Result<Vec<PathBuf>> {
+    let root = index.root.as_path();
+    let package_json_path = root.join(PackageJson::file_name());
+    let package_json = index
+        .get(root)
+        .ok_or_else(|| anyhow!("{} not found", package_json_path.display()))??;
+
+    let patterns = package_json
+        .workspaces
+        .as_ref()
+        .map(|workspaces| workspaces.patterns())
+        .filter(|patterns| !patterns.is_empty())
+        .ok_or_else(|| anyhow!("{} has no workspaces", package_json_path.display()))?;
+
+    let matcher = build_glob_matcher(root, patterns)?;
+
+    let mut packages = index
+        .folders()
+        .filter(|folder| *folder != root)
+        .filter(|folder| matcher.matched(folder, true).is_whitelist())
+        .map(Path::to_owned)
+        .collect::<Vec<_>>();
+
+    packages.sort();
+    Ok(packages)
+}
+
+/// Makes glob patterns relative to the monorepo root relative to a package folder, which is relative to the root.
+/// Patterns starting with `**` apply to every package, and patterns for files outside the package are left out.
+fn rebase_patterns(patterns: &[String], package: &Path) -> Vec<String> {
+    let prefix = format!("{}/", package.to_string_lossy().replace('\\', "/"));
+
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            let (negation, pattern) = match pattern.strip_prefix('!') {
+                Some(pattern) => ("!", pattern),
+                None => ("", pattern.as_str()),
+            };
+
+            let rebased = if pattern.starts_with("**") {
+                pattern
+            } else {
+                pattern.strip_prefix(&prefix)?
+            };
+
+            Some(format!("{}{}", negation, rebased))
+        })
+        .collect()
+}
+
+/// Analyzes every workspace package as its own project, in parallel. Each package uses its own customs.json and
+/// tsconfig.json on top of the settings of `config`, which has the project config of the monorepo root loaded.
+pub fn analyze_workspaces(config: &Config) -> anyhow::Result<Vec<ProjectAnalysis>> {
+    let index = package_json_index(config);
+    let packages = find_workspace_packages(&index)?;
+
+    packages
+        .into_par_iter()
+        .map(|package| {
+            let relative_package = package.strip_prefix(&*config.root).unwrap_or(&package);
+
+            let mut package_config = Config {
+                root: Arc::new(package.clone()),
+                // Each package is analyzed as a whole
+                source_roots: Vec::new(),
+                package_jsons: Some(index.clone()),
+                ignore_patterns: rebase_patterns(&config.ignore_patterns, relative_package),
+                include_patterns: rebase_patterns(&config.include_patterns, relative_package),
+                entry_points: rebase_patterns(&config.entry_points, relative_package),
+                dynamic_exports: rebase_patterns(&config.dynamic_exports, relative_package),
+                asset_folders: rebase_patterns(&config.asset_folders, relative_package),
+                dev_files: rebase_patterns(&config.dev_files, relative_package),
+                ..config.clone()
+            };
+
+            load_project_config(&mut package_config)
+                .and_then(|_| analyze_project(&package_config))
+                .with_context(|| format!("Failed to analyze {}", package.display()))
+        })
+        .collect()
+}
+
+/// The package.json index of the config, or a new one if the config doesn't have one.
+fn package_json_index(config: &Config) -> Arc<PackageJsonIndex> {
+    config
+        .package_jsons
+        .clone()
+        .unwrap_or_else(|| Arc::new(PackageJsonIndex::discover(&config.root)))
+}
+
+/// An export from the public entry point of a workspace package which no other workspace package imports.
+pub struct UnusedPackageExport {
+    pub package: String,
+    pub name: ExportName,
+    pub location: ModuleSourceAndLine,
+}
+
+struct WorkspacePackage {
+    name: String,
+    folder: PathBuf,
+    entry: PathBuf,
+}
+
+/// Finds the source module of a file declared in package.json, which may be compiled output.
+pub fn probe_source_file(path: &Path, config: &Config) -> Option<PathBuf> {
+    if path.is_file()
+        && (path
+            .extension()
+            .is_some_and(|ext| ext == "ts" || ext == "tsx"))
+    {
+        return Some(path.to_owned());
+    }
+
+    // Entries often point to compiled JavaScript, so look for a TypeScript file with the same name
+    let without_extension = path.with_extension("");
+
+    config.resolve_extensions.iter().find_map(|ext| {
+        let mut with_ext = without_extension.as_os_str().to_owned();
+        with_ext.push(".");
+        with_ext.push(ext);
+
+        let with_ext = PathBuf::from(with_ext);
+        with_ext.is_file().then_some(with_ext)
+    })
+}
+
+/// Finds the source module of the package's public entry point: the root of the exports map,
+/// types or main, falling back to `src/index` and `index`.
+fn resolve_package_entry(
+    folder: &Path,
+    package_json: &PackageJson,
+    config: &Config,
+) -> Option<PathBuf> {
+    package_json
+        .declared_entries()
+        .chain(["src/index", "index"])
+        .find_map(|entry| {
+            probe_source_file(&RelativePath::new(entry).to_logical_path(folder), config)
+        })
+}
+
+fn read_workspace_package(
+    folder: PathBuf,
+    index: &PackageJsonIndex,
+    config: &Config,
+) -> Option<WorkspacePackage> {
+    let package_json = index.get(&folder)?.ok()?;
+    let name = package_json.name.clone()?;
+    let entry = resolve_package_entry(&folder, &package_json, config)?;
+
+    Some(WorkspacePackage {
+        name,
+        folder,
+        entry,
+    })
+}
+
+/// Finds exports of workspace package entry points which are not imported by any other workspace package.
+/// All workspaces are parsed as a single project rooted at the monorepo root, with the project config of the root
+/// loaded into `config`.
+pub fn find_unused_package_exports(config: &Config) -> anyhow::Result<Vec<UnusedPackageExport>> {
+    if config.severity(Rule::UnusedExports) == Severity::Off {
+        return Ok(Vec::new());
+    }
+
+    let index = package_json_index(config);
+    let packages = find_workspace_packages(&index)?
+        .into_iter()
+        .filter_map(|folder| read_workspace_package(folder, &index, config))
+        .collect::<Vec<_>>();
+
+    let modules = parse_all_modules(config)?;
+    let mut unused_exports = Vec::new();
+
+    for package in packages {
+        let entry_module = match modules
+            .values()
+            .find(|module| *module.path.root_relative == package.entry)
+        {
+            Some(module) => module,
+            None => continue,
+        };
+
+        let mut used_names = HashSet::new();
+        let mut is_wildcard_imported = false;
+
+        let imports = modules
+            .values()
+            .filter(|module| !module.path.root_relative.starts_with(&package.folder))
+            .filter_map(|module| module.package_imports.get(&package.name))
+            .flatten();
+
+        for import in imports {
+            let used_name = match import {
+                ImportName::Named(name) => ExportName::Named(name.clone()),
+                ImportName::Default => ExportName::Default,
+                ImportName::Wildcard => {
+                    is_wildcard_imported = true;
+                    continue;
+                }
+                ImportName::Member(namespace, member) => {
+                    used_names.insert(ExportName::Named(namespace.clone()));
+                    namespace_member_name(namespace, member)
+                }
+            };
+
+            used_names.extend(
+                entry_module
+                    .namespace_members(&used_name)
+                    .map(|member| member.name.clone()),
+            );
+            used_names.insert(used_name);
+        }
+
+        if is_wildcard_imported {
+            continue;
+        }
+
+        unused_exports.extend(
+            entry_module
+                .exports
+                .iter()
+                .filter(|export| !used_names.contains(&export.name))
+                .map(|export| UnusedPackageExport {
+                    package: package.name.clone(),
+                    name: export.name.clone(),
+                    location: export.location.clone(),
+                }),
+        );
+    }
+
+    unused_exports.sort_unstable_by(|a, b| {
+        a.location
+            .path()
+            .cmp(b.location.path())
+            .then_with(|| a.location.line().cmp(&b.location.line()))
+    });
+
+    Ok(unused_exports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_patterns_are_rebased_to_packages() {
+        let patterns = [
+            "**/*.generated.ts",
+            "packages/ui/src/legacy/**",
+            "!packages/ui/src/legacy/keep.ts",
+            "packages/app/src/main.ts",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            rebase_patterns(&patterns, Path::new("packages/ui")),
+            vec!["**/*.generated.ts", "src/legacy/**", "!src/legacy/keep.ts"]
+        );
+    }
+}